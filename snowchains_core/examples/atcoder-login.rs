@@ -42,12 +42,15 @@ fn main() -> anyhow::Result<()> {
     let outcome = Atcoder::exec(Login {
         credentials: AtcoderLoginCredentials {
             username_and_password: &mut username_and_password(credentials),
+            revel_session: None,
         },
         cookie_storage: CookieStorage {
             cookie_store: CookieStore::default(),
             on_update: Box::new(|_| Ok(())),
         },
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
         shell: Shell::new(),
     })?;
 
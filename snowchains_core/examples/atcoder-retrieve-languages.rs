@@ -66,6 +66,8 @@ fn main() -> anyhow::Result<()> {
             on_update: Box::new(|_| Ok(())),
         },
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
         shell: Shell::new(),
     })?;
 
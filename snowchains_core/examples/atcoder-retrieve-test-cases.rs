@@ -9,6 +9,7 @@ use snowchains_core::{
     },
 };
 use std::{
+    collections::BTreeSet,
     env, fmt,
     io::{self, Write as _},
 };
@@ -72,6 +73,7 @@ fn main() -> anyhow::Result<()> {
                         CredentialsVia::Env => env::var("DROPBOX_ACCESS_TOKEN")?,
                     },
                 },
+                count_only: false,
             })
         } else {
             None
@@ -81,6 +83,10 @@ fn main() -> anyhow::Result<()> {
             on_update: Box::new(|_| Ok(())),
         },
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
+        request_interval: None,
+        skip_problems: BTreeSet::new(),
         shell: Shell::new(),
     })?;
 
@@ -73,6 +73,8 @@ fn main() -> anyhow::Result<()> {
         code: fs::read_to_string(&file)
             .with_context(|| format!("Failed to read {}", file.display()))?,
         watch_submission: !no_watch,
+        watch_timeout: None,
+        warn_if_contest_ended: true,
         cookie_storage: CookieStorage {
             cookie_store: CookieStore::default(),
             on_update: Box::new(move |cookie_store| -> _ {
@@ -90,6 +92,8 @@ fn main() -> anyhow::Result<()> {
             }),
         },
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
         shell: Shell::new(),
     })?;
 
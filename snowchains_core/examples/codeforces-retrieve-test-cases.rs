@@ -8,6 +8,7 @@ use snowchains_core::{
     },
 };
 use std::{
+    collections::BTreeSet,
     env, fmt,
     io::{self, Write as _},
 };
@@ -63,6 +64,10 @@ fn main() -> anyhow::Result<()> {
             on_update: Box::new(|_| Ok(())),
         },
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
+        request_interval: None,
+        skip_problems: BTreeSet::new(),
         shell: Shell::new(),
     })?;
 
@@ -80,11 +80,15 @@ fn main() -> anyhow::Result<()> {
         code: fs::read_to_string(&file)
             .with_context(|| format!("Failed to read {}", file.display()))?,
         watch_submission: false,
+        watch_timeout: None,
+        warn_if_contest_ended: true,
         cookie_storage: CookieStorage {
             cookie_store: CookieStore::default(),
             on_update: Box::new(|_| Ok(())),
         },
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
         shell: Shell::new(),
     })?;
 
@@ -1,7 +1,10 @@
 use anyhow::{bail, Context as _};
 use indicatif::ProgressDrawTarget;
 use maplit::btreemap;
-use snowchains_core::{judge::CommandExpression, testsuite::TestSuite};
+use snowchains_core::{
+    judge::CommandExpression,
+    testsuite::{Match, TestSuite},
+};
 use std::{env, ffi::OsString, fs, path::PathBuf};
 use structopt::StructOpt;
 use termcolor::BufferedStandardStream;
@@ -41,6 +44,7 @@ fn main() -> anyhow::Result<()> {
     let test_cases = test_suite.load_test_cases(
         file.parent().expect("should have file name"),
         testcases.map(|ss| ss.into_iter().collect()),
+        &Match::Lines,
         |_| todo!(),
     )?;
 
@@ -52,8 +56,13 @@ fn main() -> anyhow::Result<()> {
             args: args[1..].to_owned(),
             cwd,
             env: btreemap!(),
+            inherit_stderr: false,
+            seed: None,
+            capture_limit: None,
         },
         &test_cases,
+        false,
+        false,
     )?;
 
     let stdout = BufferedStandardStream::stdout(if atty::is(atty::Stream::Stdout) {
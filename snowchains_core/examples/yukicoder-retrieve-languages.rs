@@ -33,6 +33,8 @@ fn main() -> anyhow::Result<()> {
         credentials: (),
         cookie_storage: (),
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
         shell: Shell::new(),
     })?;
 
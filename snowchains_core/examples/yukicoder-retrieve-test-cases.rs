@@ -7,6 +7,7 @@ use snowchains_core::{
     },
 };
 use std::{
+    collections::BTreeSet,
     env, fmt,
     io::{self, Write as _},
     str,
@@ -81,12 +82,17 @@ fn main() -> anyhow::Result<()> {
                         CredentialsVia::Env => env::var("YUKICODER_API_KEY")?,
                     },
                 },
+                count_only: false,
             })
         } else {
             None
         },
         cookie_storage: (),
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
+        request_interval: None,
+        skip_problems: BTreeSet::new(),
         shell: Shell::new(),
     })?;
 
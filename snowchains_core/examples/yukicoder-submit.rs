@@ -70,8 +70,12 @@ fn main() -> anyhow::Result<()> {
         code: fs::read_to_string(&file)
             .with_context(|| format!("Failed to read {}", file.display()))?,
         watch_submission: false,
+        watch_timeout: None,
+        warn_if_contest_ended: true,
         cookie_storage: (),
         timeout: timeout.map(Into::into),
+        user_agent: None,
+        headers: Default::default(),
         shell: Shell::new(),
     })?;
 
@@ -1,12 +1,12 @@
-use crate::testsuite::{BatchTestCase, CheckerShell, ExpectedOutput};
-use anyhow::{anyhow, bail};
+use crate::testsuite::{BatchTestCase, ExpectedOutput, Normalizer};
+use anyhow::{anyhow, bail, ensure};
 use futures_util::{select, FutureExt as _};
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use std::{
     cmp,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     env,
-    ffi::{OsStr, OsString},
+    ffi::OsString,
     future::Future,
     io, iter,
     path::{Path, PathBuf},
@@ -15,13 +15,19 @@ use std::{
     time::{Duration, Instant},
 };
 use termcolor::{Color, WriteColor};
-use tokio::io::AsyncWriteExt as _;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWriteExt as _};
 use unicode_width::UnicodeWidthStr as _;
 
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub struct JudgeOutcome {
     pub verdicts: Vec<Verdict>,
+    /// `true` if the stderr of the tested program was inherited instead of captured.
+    pub stderr_streamed: bool,
+    /// Value of `$seed` in the command.
+    pub seed: Option<u64>,
+    /// `true` if elapsed times are printed with microseconds.
+    pub precise_time: bool,
 }
 
 impl JudgeOutcome {
@@ -35,16 +41,13 @@ impl JudgeOutcome {
                 writeln!(wtr)?;
             }
 
-            write!(
-                wtr,
-                "{}/{} ({:?}) ",
-                i + 1,
-                self.verdicts.len(),
-                verdict.test_case_name().unwrap_or(""),
-            )?;
+            write!(wtr, "{}/{} ", i + 1, self.verdicts.len())?;
+            if let Some(name) = verdict.test_case_name() {
+                write!(wtr, "{}  ", name)?;
+            }
 
             wtr.set_color(color_spec!(Bold, Fg(verdict.summary_color())))?;
-            writeln!(wtr, "{}", verdict.summary())?;
+            writeln!(wtr, "{}", verdict.summary(self.precise_time))?;
             wtr.reset()?;
 
             let mut write_text =
@@ -59,36 +62,22 @@ impl JudgeOutcome {
                         return wtr.reset();
                     }
 
-                    if matches!(display_limit, Some(l) if l < text.len()) {
-                        wtr.set_color(color_spec!(Bold, Fg(Color::Yellow)))?;
-                        writeln!(wtr, "{} B", text.len())?;
-                        return wtr.reset();
-                    }
-
-                    for token in parse_to_tokens(text, highlight_numbers) {
-                        match token {
-                            Token::SpcLf(s) | Token::Plain(s) => wtr.write_all(s.as_ref())?,
-                            Token::Cr(n) => {
-                                wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
-                                (0..n).try_for_each(|_| wtr.write_all(b"\\r"))?;
-                                wtr.reset()?;
-                            }
-                            Token::Tab(n) => {
-                                wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
-                                (0..n).try_for_each(|_| wtr.write_all(b"\\t"))?;
-                                wtr.reset()?;
-                            }
-                            Token::OtherWhitespaceControl(s) => {
-                                wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
-                                write!(wtr, "{}", s.escape_unicode())?;
-                                wtr.reset()?;
-                            }
-                            Token::HighlightedNumber(s) => {
-                                wtr.set_color(color_spec!(Fg(Color::Cyan)))?;
-                                wtr.write_all(s.as_ref())?;
-                                wtr.reset()?;
-                            }
+                    if let Some(limit) = display_limit.filter(|&l| l < text.len()) {
+                        let (head, tail) = split_head_tail(text, limit);
+                        write_tokens(&mut wtr, head, highlight_numbers)?;
+                        if !head.ends_with('\n') {
+                            writeln!(wtr)?;
                         }
+                        wtr.set_color(color_spec!(Bold, Fg(Color::Yellow)))?;
+                        writeln!(
+                            wtr,
+                            "... ({} B omitted) ...",
+                            text.len() - head.len() - tail.len(),
+                        )?;
+                        wtr.reset()?;
+                        write_tokens(&mut wtr, tail, highlight_numbers)?;
+                    } else {
+                        write_tokens(&mut wtr, text, highlight_numbers)?;
                     }
 
                     if !text.ends_with('\n') {
@@ -101,7 +90,7 @@ impl JudgeOutcome {
                 };
 
             write_text("stdin:", verdict.stdin(), false)?;
-            if let Some(expected) = verdict.expected().expected_stdout() {
+            if let Some(expected) = verdict.expected().closest_expected_stdout(verdict.stdout()) {
                 write_text("expected:", expected, verdict.expected().is_float())?;
             } else if let Some(example) = verdict.expected().example() {
                 write_text("example:", example, verdict.expected().is_float())?;
@@ -109,7 +98,9 @@ impl JudgeOutcome {
             if let Some(stdout) = verdict.stdout() {
                 write_text("actual:", stdout, verdict.expected().is_float())?;
             }
-            if let Some(stderr) = verdict.stderr().filter(|s| !s.is_empty()) {
+            if self.stderr_streamed && verdict.stderr().is_some() {
+                write_text("stderr:", "(streamed live)\n", false)?;
+            } else if let Some(stderr) = verdict.stderr().filter(|s| !s.is_empty()) {
                 write_text("stderr:", stderr, verdict.expected().is_float())?;
             }
             if let Some(checker_stdout) = verdict.checker_stdout().filter(|s| !s.is_empty()) {
@@ -121,6 +112,21 @@ impl JudgeOutcome {
             if let Some(wrong_answer_note) = verdict.wrong_answer_note() {
                 write_text("note: ", &(wrong_answer_note.to_string() + "\n"), false)?;
             }
+            if let Verdict::PresentationError { .. } = verdict {
+                write_text("note: ", "only whitespace differs\n", false)?;
+            }
+            if let Some(status) = verdict
+                .status()
+                .filter(|s| !s.success() && !matches!(verdict, Verdict::RuntimeError { .. }))
+            {
+                write_text("note: ", &format!("{} (allowed)\n", status), false)?;
+            }
+            if let Some(seed) = self
+                .seed
+                .filter(|_| !matches!(verdict, Verdict::Accepted { .. }))
+            {
+                write_text("seed:", &format!("{}\n", seed), false)?;
+            }
         }
 
         return wtr.flush();
@@ -197,6 +203,158 @@ impl JudgeOutcome {
                 }
             }
         }
+
+        fn write_tokens(
+            mut wtr: impl WriteColor,
+            text: &str,
+            highlight_numbers: bool,
+        ) -> io::Result<()> {
+            for token in parse_to_tokens(text, highlight_numbers) {
+                match token {
+                    Token::SpcLf(s) | Token::Plain(s) => wtr.write_all(s.as_ref())?,
+                    Token::Cr(n) => {
+                        wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                        (0..n).try_for_each(|_| wtr.write_all(b"\\r"))?;
+                        wtr.reset()?;
+                    }
+                    Token::Tab(n) => {
+                        wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                        (0..n).try_for_each(|_| wtr.write_all(b"\\t"))?;
+                        wtr.reset()?;
+                    }
+                    Token::OtherWhitespaceControl(s) => {
+                        wtr.set_color(color_spec!(Fg(Color::Yellow)))?;
+                        write!(wtr, "{}", s.escape_unicode())?;
+                        wtr.reset()?;
+                    }
+                    Token::HighlightedNumber(s) => {
+                        wtr.set_color(color_spec!(Fg(Color::Cyan)))?;
+                        wtr.write_all(s.as_ref())?;
+                        wtr.reset()?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Splits `text` into its first and last `limit / 2` bytes, on char boundaries.
+        fn split_head_tail(text: &str, limit: usize) -> (&str, &str) {
+            let mut head_end = limit / 2;
+            while !text.is_char_boundary(head_end) {
+                head_end -= 1;
+            }
+            let mut tail_start = text.len() - limit / 2;
+            while !text.is_char_boundary(tail_start) {
+                tail_start += 1;
+            }
+            (&text[..head_end], &text[tail_start..])
+        }
+    }
+
+    /// Prints the slowest, average, and total time of the test cases.
+    pub fn print_timing_summary<W: WriteColor>(&self, mut wtr: W) -> io::Result<()> {
+        let (slowest_index, slowest) = match self
+            .verdicts
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, v)| v.elapsed())
+        {
+            Some(slowest) => slowest,
+            None => return Ok(()),
+        };
+
+        let total = self.verdicts.iter().map(Verdict::elapsed).sum::<Duration>();
+        let average = total / self.verdicts.len() as u32;
+
+        wtr.set_color(color_spec!(Bold))?;
+        write!(wtr, "Time:")?;
+        wtr.reset()?;
+        write!(wtr, " slowest ")?;
+        wtr.set_color(color_spec!(Bold, Fg(slowest.summary_color())))?;
+        write!(wtr, "{}", format_ms(slowest.elapsed(), self.precise_time))?;
+        wtr.reset()?;
+        write!(wtr, " ({}/{}", slowest_index + 1, self.verdicts.len())?;
+        if let Some(name) = slowest.test_case_name() {
+            write!(wtr, " {}", name)?;
+        }
+        writeln!(
+            wtr,
+            "), average {}, total {}",
+            format_ms(average, self.precise_time),
+            format_ms(total, self.precise_time),
+        )?;
+        wtr.flush()
+    }
+
+    /// Prints the number of the test cases for each verdict (e.g. "AC 8  WA 1  TLE 1"), each
+    /// in the color of the verdict.
+    pub fn print_verdict_summary<W: WriteColor>(&self, mut wtr: W) -> io::Result<()> {
+        const ORDER: [&str; 5] = ["AC", "WA", "PE", "RE", "TLE"];
+
+        if self.verdicts.is_empty() {
+            return Ok(());
+        }
+
+        let mut counts = BTreeMap::new();
+        for verdict in &self.verdicts {
+            let rank = ORDER.iter().position(|&a| a == verdict.abbreviation());
+            counts.entry(rank).or_insert((verdict, 0)).1 += 1;
+        }
+
+        wtr.set_color(color_spec!(Bold))?;
+        write!(wtr, "Verdicts:")?;
+        wtr.reset()?;
+        for (i, (verdict, count)) in counts.values().enumerate() {
+            write!(wtr, "{}", if i == 0 { " " } else { "  " })?;
+            wtr.set_color(color_spec!(Bold, Fg(verdict.summary_color())))?;
+            write!(wtr, "{} {}", verdict.abbreviation(), count)?;
+            wtr.reset()?;
+        }
+        writeln!(wtr)?;
+        wtr.flush()
+    }
+
+    /// Prints the verdicts in [TAP](https://testanything.org/tap-version-13-specification.html).
+    pub fn print_tap<W: io::Write>(&self, mut wtr: W) -> io::Result<()> {
+        writeln!(wtr, "TAP version 13")?;
+        writeln!(wtr, "1..{}", self.verdicts.len())?;
+
+        for (i, verdict) in self.verdicts.iter().enumerate() {
+            let name = verdict
+                .test_case_name()
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|| (i + 1).to_string());
+
+            if let Verdict::Accepted { .. } = verdict {
+                writeln!(wtr, "ok {} - {}", i + 1, name)?;
+            } else {
+                writeln!(wtr, "not ok {} - {}", i + 1, name)?;
+                writeln!(wtr, "  ---")?;
+                writeln!(wtr, "  verdict: {}", verdict.name())?;
+                if self.precise_time {
+                    writeln!(
+                        wtr,
+                        "  elapsed_ms: {:.3}",
+                        as_fractional_ms(verdict.elapsed())
+                    )?;
+                } else {
+                    writeln!(wtr, "  elapsed_ms: {}", verdict.elapsed().as_millis())?;
+                }
+                if let Verdict::RuntimeError { status, .. } = verdict {
+                    writeln!(wtr, "  status: {:?}", status.to_string())?;
+                }
+                writeln!(wtr, "  ...")?;
+            }
+        }
+
+        wtr.flush()
+    }
+
+    pub fn num_accepted(&self) -> usize {
+        self.verdicts
+            .iter()
+            .filter(|v| matches!(v, Verdict::Accepted { .. }))
+            .count()
     }
 
     pub fn error_on_fail(&self) -> anyhow::Result<()> {
@@ -221,6 +379,7 @@ impl JudgeOutcome {
 
 #[derive(Debug, Clone)]
 pub enum Verdict {
+    /// `status` can be unsuccessful if the test suite has `allow_nonzero_exit`.
     Accepted {
         test_case_name: Option<String>,
         elapsed: Duration,
@@ -228,6 +387,7 @@ pub enum Verdict {
         stdout: Arc<str>,
         stderr: Arc<str>,
         expected: ExpectedOutput,
+        status: ExitStatus,
     },
     WrongAnswer {
         test_case_name: Option<String>,
@@ -239,6 +399,17 @@ pub enum Verdict {
         checker_stderr: Arc<str>,
         expected: ExpectedOutput,
         note: Option<WrongAnswerNote>,
+        status: ExitStatus,
+    },
+    /// The output differs from the expected one only in whitespace. Only for `Match::Exact`.
+    PresentationError {
+        test_case_name: Option<String>,
+        elapsed: Duration,
+        stdin: Arc<str>,
+        stdout: Arc<str>,
+        stderr: Arc<str>,
+        expected: ExpectedOutput,
+        status: ExitStatus,
     },
     RuntimeError {
         test_case_name: Option<String>,
@@ -258,19 +429,32 @@ pub enum Verdict {
 }
 
 impl Verdict {
-    fn test_case_name(&self) -> Option<&str> {
+    pub fn test_case_name(&self) -> Option<&str> {
         match self {
             Verdict::Accepted { test_case_name, .. }
             | Verdict::WrongAnswer { test_case_name, .. }
+            | Verdict::PresentationError { test_case_name, .. }
             | Verdict::RuntimeError { test_case_name, .. }
             | Verdict::TimelimitExceeded { test_case_name, .. } => test_case_name.as_deref(),
         }
     }
 
+    /// Elapsed time. For `TimelimitExceeded`, this is the timelimit.
+    pub fn elapsed(&self) -> Duration {
+        match *self {
+            Verdict::Accepted { elapsed, .. }
+            | Verdict::WrongAnswer { elapsed, .. }
+            | Verdict::PresentationError { elapsed, .. }
+            | Verdict::RuntimeError { elapsed, .. } => elapsed,
+            Verdict::TimelimitExceeded { timelimit, .. } => timelimit,
+        }
+    }
+
     fn stdin(&self) -> &str {
         match self {
             Verdict::Accepted { stdin, .. }
             | Verdict::WrongAnswer { stdin, .. }
+            | Verdict::PresentationError { stdin, .. }
             | Verdict::RuntimeError { stdin, .. }
             | Verdict::TimelimitExceeded { stdin, .. } => stdin,
         }
@@ -280,6 +464,7 @@ impl Verdict {
         match self {
             Verdict::Accepted { stdout, .. }
             | Verdict::WrongAnswer { stdout, .. }
+            | Verdict::PresentationError { stdout, .. }
             | Verdict::RuntimeError { stdout, .. } => Some(stdout),
             Verdict::TimelimitExceeded { .. } => None,
         }
@@ -289,6 +474,7 @@ impl Verdict {
         match self {
             Verdict::Accepted { stderr, .. }
             | Verdict::WrongAnswer { stderr, .. }
+            | Verdict::PresentationError { stderr, .. }
             | Verdict::RuntimeError { stderr, .. } => Some(stderr),
             Verdict::TimelimitExceeded { .. } => None,
         }
@@ -298,6 +484,7 @@ impl Verdict {
         match self {
             Verdict::Accepted { expected, .. }
             | Verdict::WrongAnswer { expected, .. }
+            | Verdict::PresentationError { expected, .. }
             | Verdict::RuntimeError { expected, .. }
             | Verdict::TimelimitExceeded { expected, .. } => expected,
         }
@@ -317,25 +504,59 @@ impl Verdict {
         }
     }
 
-    fn wrong_answer_note(&self) -> Option<WrongAnswerNote> {
+    fn status(&self) -> Option<ExitStatus> {
         match *self {
-            Self::WrongAnswer { note, .. } => note,
+            Verdict::Accepted { status, .. }
+            | Verdict::WrongAnswer { status, .. }
+            | Verdict::PresentationError { status, .. }
+            | Verdict::RuntimeError { status, .. } => Some(status),
+            Verdict::TimelimitExceeded { .. } => None,
+        }
+    }
+
+    fn wrong_answer_note(&self) -> Option<&WrongAnswerNote> {
+        match self {
+            Self::WrongAnswer { note, .. } => note.as_ref(),
             _ => None,
         }
     }
 
-    fn summary(&self) -> String {
+    /// Name of the verdict (e.g. `"Wrong Answer"`).
+    pub fn name(&self) -> &'static str {
         match self {
-            Self::Accepted { elapsed, .. } => format!("Accepted ({} ms)", elapsed.as_millis()),
+            Self::Accepted { .. } => "Accepted",
+            Self::TimelimitExceeded { .. } => "Timelimit Exceeded",
+            Self::WrongAnswer { .. } => "Wrong Answer",
+            Self::PresentationError { .. } => "Presentation Error",
+            Self::RuntimeError { .. } => "Runtime Error",
+        }
+    }
+
+    fn abbreviation(&self) -> &'static str {
+        match self {
+            Self::Accepted { .. } => "AC",
+            Self::TimelimitExceeded { .. } => "TLE",
+            Self::WrongAnswer { .. } => "WA",
+            Self::PresentationError { .. } => "PE",
+            Self::RuntimeError { .. } => "RE",
+        }
+    }
+
+    fn summary(&self, precise_time: bool) -> String {
+        let ms = |d| format_ms(d, precise_time);
+
+        match self {
+            Self::Accepted { elapsed, .. } => format!("Accepted ({})", ms(*elapsed)),
             Self::TimelimitExceeded { timelimit, .. } => {
-                format!("Timelimit Exceeded ({} ms)", timelimit.as_millis())
+                format!("Timelimit Exceeded ({})", ms(*timelimit))
             }
-            Self::WrongAnswer { elapsed, .. } => {
-                format!("Wrong Answer ({} ms)", elapsed.as_millis())
+            Self::WrongAnswer { elapsed, .. } => format!("Wrong Answer ({})", ms(*elapsed)),
+            Self::PresentationError { elapsed, .. } => {
+                format!("Presentation Error ({})", ms(*elapsed))
             }
             Self::RuntimeError {
                 elapsed, status, ..
-            } => format!("Runtime Error ({} ms, {})", elapsed.as_millis(), status),
+            } => format!("Runtime Error ({}, {})", ms(*elapsed), status),
         }
     }
 
@@ -344,6 +565,7 @@ impl Verdict {
             Self::Accepted { .. } => Color::Green,
             Self::TimelimitExceeded { .. } => Color::Red,
             Self::WrongAnswer { .. } | Self::RuntimeError { .. } => Color::Yellow,
+            Self::PresentationError { .. } => Color::Cyan,
         }
     }
 
@@ -352,27 +574,144 @@ impl Verdict {
             Self::Accepted { .. } => ".bold.green",
             Self::TimelimitExceeded { .. } => ".bold.red",
             Self::WrongAnswer { .. } | Self::RuntimeError { .. } => ".bold.yellow",
+            Self::PresentationError { .. } => ".bold.cyan",
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, derive_more::Display)]
+/// Formats `d` as "12 ms", or as "12.345 ms" if `precise` is `true`.
+fn format_ms(d: Duration, precise: bool) -> String {
+    if precise {
+        format!("{:.3} ms", as_fractional_ms(d))
+    } else {
+        format!("{} ms", d.as_millis())
+    }
+}
+
+fn as_fractional_ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+#[derive(Clone, Debug, derive_more::Display)]
 pub enum WrongAnswerNote {
     #[display(
         fmt = "whitespace-separated words matched. try setting `match` to `SplitWhitespace`"
     )]
     WordsMatched,
+    #[display(
+        fmt = "the outputs differ at byte {} (0x{:x})\n{}",
+        offset,
+        offset,
+        hex_dump
+    )]
+    BytesDiffer { offset: usize, hex_dump: String },
+}
+
+impl WrongAnswerNote {
+    /// Hex-dumps the rows around the first differing byte of `expected` and `actual`.
+    fn bytes_differ(expected: &[u8], actual: &[u8]) -> Option<Self> {
+        const WIDTH: usize = 16;
+
+        if expected == actual {
+            return None;
+        }
+        let offset = expected
+            .iter()
+            .zip(actual)
+            .position(|(e, a)| e != a)
+            .unwrap_or_else(|| cmp::min(expected.len(), actual.len()));
+
+        let start = (offset / WIDTH).saturating_sub(1) * WIDTH;
+        let end = (offset / WIDTH + 2) * WIDTH;
+
+        let mut hex_dump = "".to_owned();
+        for (header, bytes) in &[("expected:", expected), ("actual:", actual)] {
+            hex_dump += header;
+            hex_dump += "\n";
+            for row_start in (start..cmp::min(end, bytes.len())).step_by(WIDTH) {
+                let row = &bytes[row_start..cmp::min(row_start + WIDTH, bytes.len())];
+                hex_dump += &format!("{:08x} ", row_start);
+                for (i, byte) in row.iter().enumerate() {
+                    let marker = if row_start + i == offset { '>' } else { ' ' };
+                    hex_dump += &format!("{}{:02x}", marker, byte);
+                }
+                hex_dump += &" ".repeat(3 * (WIDTH - row.len()));
+                hex_dump += "  |";
+                hex_dump.extend(row.iter().map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                }));
+                hex_dump += "|\n";
+            }
+        }
+        hex_dump.pop();
+
+        Some(Self::BytesDiffer { offset, hex_dump })
+    }
 }
 
+/// Returned when the judge is interrupted by Ctrl-C.
+#[derive(Debug, Clone, Copy, derive_more::Display)]
+#[display(fmt = "Interrupted by Ctrl-C")]
+pub struct Interrupted;
+
+impl std::error::Error for Interrupted {}
+
+/// Returned when the program to test does not exist.
+#[derive(Debug, Clone, derive_more::Display)]
+#[display(
+    fmt = "Could not find `{}` in `{}`. The code may not be compiled, or the path to the binary may \
+           be wrong",
+    "program.to_string_lossy()",
+    "cwd.display()"
+)]
+pub struct ProgramNotFound {
+    pub program: OsString,
+    pub cwd: PathBuf,
+}
+
+impl std::error::Error for ProgramNotFound {}
+
 #[derive(Debug, Clone)]
 pub struct CommandExpression {
     pub program: OsString,
     pub args: Vec<OsString>,
     pub cwd: PathBuf,
     pub env: BTreeMap<OsString, OsString>,
+    /// Let the program write to the terminal directly instead of capturing its stderr.
+    pub inherit_stderr: bool,
+    /// Replaces `$seed` and `${seed}` in `args`.
+    pub seed: Option<u64>,
+    /// Maximum bytes kept of the captured stderr and the output of `Checker`s. The rest is read
+    /// and discarded.
+    pub capture_limit: Option<usize>,
 }
 
 impl CommandExpression {
+    pub fn uses_seed(&self) -> bool {
+        self.args
+            .iter()
+            .flat_map(|arg| arg.to_str())
+            .any(|arg| arg.contains("$seed") || arg.contains("${seed}"))
+    }
+
+    fn expanded_args(&self) -> Vec<OsString> {
+        let seed = match self.seed {
+            Some(seed) => seed.to_string(),
+            None => return self.args.clone(),
+        };
+        self.args
+            .iter()
+            .map(|arg| match arg.to_str() {
+                Some(arg) => arg.replace("${seed}", &seed).replace("$seed", &seed).into(),
+                None => arg.clone(),
+            })
+            .collect()
+    }
+
     async fn build(
         &self,
         stdin: Option<&Path>,
@@ -387,7 +726,21 @@ impl CommandExpression {
         };
         let stdout = tokio::fs::File::create(stdout).await?.into_std().await;
         let stderr = tokio::fs::File::create(stderr).await?.into_std().await;
-        cmd.args(&self.args)
+        let stderr = if self.inherit_stderr {
+            Stdio::inherit()
+        } else {
+            stderr.into()
+        };
+        // So that `ProcessGroup` can kill the descendants as well.
+        #[cfg(unix)]
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setpgid(0, 0);
+                Ok(())
+            });
+        }
+        cmd.args(self.expanded_args())
+            .kill_on_drop(true)
             .current_dir(&self.cwd)
             .envs(&self.env)
             .stdin(stdin)
@@ -402,14 +755,16 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
     ctrl_c: fn() -> C,
     cmd: &CommandExpression,
     test_cases: &[BatchTestCase],
+    fail_fast: bool,
+    precise_time: bool,
 ) -> anyhow::Result<JudgeOutcome> {
     let cmd = Arc::new(cmd.clone());
     let num_test_cases = test_cases.len();
 
-    let quoted_name_width = test_cases
+    let name_width = test_cases
         .iter()
         .flat_map(|BatchTestCase { name, .. }| name.as_ref())
-        .map(|s| format!("{:?}", s).width())
+        .map(|s| s.width())
         .max()
         .unwrap_or(0);
 
@@ -439,15 +794,15 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
 
         pb.set_style(progress_style("{prefix}{spinner} {msg:bold}"));
 
-        pb.set_prefix(&format!(
-            "{}/{} ({} ",
+        let mut prefix = format!(
+            "{}/{} ",
             align_right(&(i + 1).to_string(), num_test_cases.to_string().len()),
             num_test_cases,
-            align_left(
-                &format!("{:?})", test_case.name.as_deref().unwrap_or("")),
-                quoted_name_width + 1,
-            ),
-        ));
+        );
+        if name_width > 0 {
+            prefix += &align_left(test_case.name.as_deref().unwrap_or(""), name_width + 2);
+        }
+        pb.set_prefix(&prefix);
 
         pb.set_message("Judging...");
         pb.enable_steady_tick(50);
@@ -471,21 +826,23 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
             .collect::<Vec<_>>();
 
         tokio::task::spawn(async move {
-            let err_msg = match ctrl_c().await {
-                Ok(()) => "Recieved Ctrl-c".to_owned(),
-                Err(err) => err.to_string(),
-            };
-            ctrl_c_tx.send(err_msg).unwrap();
+            let result = ctrl_c().await.map_err(|err| err.to_string());
+            ctrl_c_tx.send(result).unwrap();
         });
 
+        // Notified when `fail_fast` is enabled and a test case fails.
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+        let cancel_tx = Arc::new(cancel_tx);
+
         let (job_start_tx, mut job_start_rx) = tokio::sync::mpsc::channel(num_cpus::get());
         for _ in 0..num_cpus::get() {
             job_start_tx.send(()).await?;
         }
 
         let mut results = vec![];
+        let mut targets = targets.into_iter().enumerate();
 
-        for (i, (test_case, pb)) in targets.into_iter().enumerate() {
+        while let Some((i, (test_case, pb))) = targets.next() {
             let cmd = cmd.clone();
             let stdin_path = tempdir_path.join(format!("{}-stdin", i));
             let actual_stdout_path = tempdir_path.join(format!("{}-actual-stdout", i));
@@ -495,12 +852,22 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
 
             job_start_rx.recv().await;
 
+            if *cancel_rx.borrow() {
+                for pb in iter::once(pb).chain(targets.map(|(_, (_, pb))| pb)) {
+                    pb.set_style(progress_style("{prefix}{msg}"));
+                    pb.finish_with_message("Skipped");
+                }
+                break;
+            }
+
             let job_start_tx = job_start_tx.clone();
             let mut ctrl_c_rx = ctrl_c_rxs.pop().expect("should have enough length");
+            let cancel_tx = cancel_tx.clone();
+            let mut cancel_rx = cancel_rx.clone();
             let pb_clone = pb.clone();
 
             results.push(tokio::task::spawn(async move {
-                let result = tokio::task::spawn(async move {
+                let mut task = tokio::task::spawn(async move {
                     tokio::fs::write(&stdin_path, test_case.input.as_ref()).await?;
 
                     let test_case_name = test_case.name.clone();
@@ -508,7 +875,9 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                     let stdin = test_case.input.clone();
                     let expected = test_case.output.clone();
 
+                    let program = &cmd.program;
                     let cwd = &cmd.cwd;
+                    let capture_limit = cmd.capture_limit;
                     let cmd = cmd
                         .build(
                             (stdin.len() >= 10 * 1024).then(|| &*stdin_path),
@@ -519,7 +888,18 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
 
                     let started = Instant::now();
 
-                    let mut child = { cmd }.spawn()?;
+                    let mut child = { cmd }.spawn().map_err(|err| {
+                        if err.kind() == io::ErrorKind::NotFound {
+                            anyhow::Error::from(ProgramNotFound {
+                                program: program.clone(),
+                                cwd: cwd.clone(),
+                            })
+                        } else {
+                            err.into()
+                        }
+                    })?;
+
+                    let _process_group = ProcessGroup::new(&child);
 
                     if let Some(mut child_stdin) = child.stdin.take() {
                         child_stdin.write_all((*stdin).as_ref()).await?;
@@ -529,9 +909,12 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                         ($future:expr) => {
                             select! {
                                 __output = $future => __output,
-                                err_msg = ctrl_c_rx.recv().fuse() => {
-                                    let _ = child.kill();
-                                    bail!("{}", err_msg?);
+                                result = ctrl_c_rx.recv().fuse() => {
+                                    let _ = child.start_kill();
+                                    return Err(match result? {
+                                        Ok(()) => Interrupted.into(),
+                                        Err(err_msg) => anyhow!("{}", err_msg),
+                                    });
                                 },
                             }
                         };
@@ -557,7 +940,7 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                                     "{{prefix}}{{msg:{}}}",
                                     verdict.summary_style(),
                                 )));
-                                pb_clone.finish_with_message(&verdict.summary());
+                                pb_clone.finish_with_message(&verdict.summary(precise_time));
                             });
                             return Ok(verdict);
                         }
@@ -567,8 +950,16 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
 
                     let elapsed = Instant::now() - started;
 
-                    let stdout = utf8(tokio::fs::read(&actual_stdout_path).await?)?;
-                    let stderr = utf8(tokio::fs::read(&stderr_path).await?)?;
+                    let stdout_bytes = tokio::fs::read(&actual_stdout_path).await?;
+                    let stdout = if test_case.output.is_binary() {
+                        String::from_utf8_lossy(&stdout_bytes).into()
+                    } else {
+                        utf8(stdout_bytes.clone())?
+                    };
+                    let stderr = capped_utf8(
+                        read_capped(tokio::fs::File::open(&stderr_path).await?, capture_limit)
+                            .await?,
+                    )?;
 
                     if matches!(timelimit, Some(t) if t < elapsed) {
                         Ok(Verdict::TimelimitExceeded {
@@ -577,7 +968,7 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                             stdin,
                             expected,
                         })
-                    } else if !status.success() {
+                    } else if !status.success() && !test_case.allow_nonzero_exit {
                         Ok(Verdict::RuntimeError {
                             test_case_name,
                             elapsed,
@@ -589,15 +980,32 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                         })
                     } else if let Err((checker_stdout, checker_stderr, note)) = check(
                         &test_case.output,
-                        &stdout,
+                        test_case.normalizer.as_ref(),
+                        &stdout_bytes,
                         cwd,
                         &stdin_path,
                         &actual_stdout_path,
                         &expected_stdout_path,
                         &bash_exe,
+                        capture_limit,
                     )
                     .await?
                     {
+                        let exact = matches!(
+                            &test_case.output,
+                            ExpectedOutput::Deterministic(expected) if expected.is_exact()
+                        );
+                        if exact && matches!(note, Some(WrongAnswerNote::WordsMatched)) {
+                            return Ok(Verdict::PresentationError {
+                                test_case_name,
+                                elapsed,
+                                stdin,
+                                stdout,
+                                stderr,
+                                expected,
+                                status,
+                            });
+                        }
                         Ok(Verdict::WrongAnswer {
                             test_case_name,
                             elapsed,
@@ -608,6 +1016,7 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                             checker_stderr,
                             expected,
                             note,
+                            status,
                         })
                     } else {
                         Ok(Verdict::Accepted {
@@ -617,20 +1026,37 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
                             stdout,
                             stderr,
                             expected,
+                            status,
                         })
                     }
-                })
-                .await
-                .unwrap();
+                });
+
+                // Aborting the task kills the child process since it is spawned with
+                // `kill_on_drop(true)`.
+                let result = select! {
+                    result = (&mut task).fuse() => result.unwrap(),
+                    _ = cancel_rx.changed().fuse() => {
+                        task.abort();
+                        tokio::task::block_in_place(|| {
+                            pb.set_style(progress_style("{prefix}{msg}"));
+                            pb.finish_with_message("Cancelled");
+                        });
+                        job_start_tx.send(()).await?;
+                        return Ok((i, None));
+                    },
+                };
 
                 match &result {
                     Ok(verdict) => {
+                        if fail_fast && !matches!(verdict, Verdict::Accepted { .. }) {
+                            let _ = cancel_tx.send(true);
+                        }
                         tokio::task::block_in_place(|| {
                             pb.set_style(progress_style(&format!(
                                 "{{prefix}}{{msg:{}}}",
                                 verdict.summary_style(),
                             )));
-                            pb.finish_with_message(&verdict.summary());
+                            pb.finish_with_message(&verdict.summary(precise_time));
                         });
                     }
                     Err(err) => {
@@ -643,18 +1069,23 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
 
                 job_start_tx.send(()).await?;
                 let verdict = result?;
-                Ok::<_, anyhow::Error>((i, verdict))
+                Ok::<_, anyhow::Error>((i, Some(verdict)))
             }));
         }
 
         let mut verdicts = vec![None; num_targets];
         for result in results {
             let (i, element) = result.await??;
-            verdicts[i] = Some(element);
+            verdicts[i] = element;
         }
-        let verdicts = verdicts.into_iter().map(Option::unwrap).collect();
-
-        Ok::<_, anyhow::Error>(JudgeOutcome { verdicts })
+        let verdicts = verdicts.into_iter().flatten().collect();
+
+        Ok::<_, anyhow::Error>(JudgeOutcome {
+            verdicts,
+            stderr_streamed: cmd.inherit_stderr,
+            seed: cmd.seed,
+            precise_time,
+        })
     });
 
     mp.join()?;
@@ -678,29 +1109,76 @@ pub fn judge<C: 'static + Future<Output = tokio::io::Result<()>> + Send>(
     }
 }
 
+/// Kills the process group of a child on drop, including the processes the child spawned.
+struct ProcessGroup {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pgid: Option<u32>,
+}
+
+impl ProcessGroup {
+    fn new(child: &tokio::process::Child) -> Self {
+        Self { pgid: child.id() }
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pgid) = self.pgid {
+            unsafe {
+                libc::kill(-(pgid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn check(
     expected: &ExpectedOutput,
-    actual: &str,
+    normalizer: Option<&Normalizer>,
+    actual: &[u8],
     cwd: &Path,
     stdin_path: &Path,
     actual_stdout_path: &Path,
     expected_stdout_path: &Path,
     bash_exe: &Path,
+    capture_limit: Option<usize>,
 ) -> anyhow::Result<Result<(), (Arc<str>, Arc<str>, Option<WrongAnswerNote>)>> {
+    let normalized;
+    let (expected, actual) = if let Some(normalizer) = normalizer {
+        let actual = normalize(normalizer, actual, cwd, bash_exe).await?;
+        // For `$ACTUAL_OUTPUT` of checkers.
+        tokio::fs::write(actual_stdout_path, &actual).await?;
+
+        let mut texts = HashMap::new();
+        for text in expected.texts() {
+            let normalized = utf8(normalize(normalizer, text.as_bytes(), cwd, bash_exe).await?)?;
+            texts.insert(text.clone(), normalized);
+        }
+        normalized = (expected.map_texts(|text| texts[text].clone()), actual);
+        (&normalized.0, &*normalized.1)
+    } else {
+        (expected, actual)
+    };
+
     match expected {
-        ExpectedOutput::Deterministic(expected) => Ok(if expected.accepts(actual) {
+        ExpectedOutput::Deterministic(expected) => Ok(if expected.accepts_bytes(actual) {
             Ok(())
-        } else {
+        } else if expected.is_binary() {
             let note = expected
                 .expected_stdout()
+                .and_then(|expected| WrongAnswerNote::bytes_differ(expected.as_ref(), actual));
+            Err((Arc::from(""), Arc::from(""), note))
+        } else {
+            let actual = String::from_utf8_lossy(actual);
+            let note = expected
+                .closest_expected_stdout(&actual)
                 .filter(|expected| expected.split_whitespace().eq(actual.split_whitespace()))
                 .map(|_| WrongAnswerNote::WordsMatched);
             Err((Arc::from(""), Arc::from(""), note))
         }),
         ExpectedOutput::Checker { text, cmd, shell } => {
-            let (program, args) = match shell {
-                CheckerShell::Bash => (bash_exe, [OsStr::new("-c"), OsStr::new(cmd)]),
-            };
+            let (program, args) = shell.command_line(bash_exe, cmd)?;
 
             let mut env_vars = vec![("INPUT", stdin_path), ("ACTUAL_OUTPUT", actual_stdout_path)];
             if let Some(text) = text {
@@ -708,11 +1186,7 @@ async fn check(
                 env_vars.push(("EXPECTED_OUTPUT", expected_stdout_path));
             }
 
-            let Output {
-                status,
-                stdout,
-                stderr,
-            } = tokio::process::Command::new(program)
+            let mut child = tokio::process::Command::new(program)
                 .args(&args)
                 .envs(env_vars)
                 .current_dir(cwd)
@@ -720,10 +1194,18 @@ async fn check(
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
                 .kill_on_drop(true)
-                .output()
-                .await?;
+                .spawn()?;
+
+            // Reads both while the checker is running so that it never blocks on a full pipe.
+            let stdout = child.stdout.take().expect("`stdout` should be piped");
+            let stderr = child.stderr.take().expect("`stderr` should be piped");
+            let (stdout, stderr, status) = futures_util::try_join!(
+                read_capped(stdout, capture_limit),
+                read_capped(stderr, capture_limit),
+                child.wait(),
+            )?;
 
-            let (stdout, stderr) = (utf8(stdout)?, utf8(stderr)?);
+            let (stdout, stderr) = (capped_utf8(stdout)?, capped_utf8(stderr)?);
 
             Ok(if status.success() {
                 Ok(())
@@ -734,8 +1216,146 @@ async fn check(
     }
 }
 
+async fn normalize(
+    normalizer: &Normalizer,
+    text: &[u8],
+    cwd: &Path,
+    bash_exe: &Path,
+) -> anyhow::Result<Vec<u8>> {
+    let Normalizer { cmd, shell } = normalizer;
+    let (program, args) = shell.command_line(bash_exe, cmd)?;
+
+    let mut child = tokio::process::Command::new(program)
+        .args(&args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().expect("`stdin` should be piped");
+    let (written, output) = futures_util::join!(
+        async move { stdin.write_all(text).await },
+        child.wait_with_output(),
+    );
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = output?;
+
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let stderr = stderr.trim_end();
+        bail!(
+            "The normalizer `{}` failed ({}){}{}",
+            cmd,
+            status,
+            if stderr.is_empty() { "" } else { ": " },
+            stderr,
+        );
+    }
+    // The normalizer may exit without reading all of the input.
+    if let Err(err) = written {
+        ensure!(err.kind() == io::ErrorKind::BrokenPipe, err);
+    }
+    Ok(stdout)
+}
+
+/// Reads `reader` to the end, keeping at most `limit` bytes.
+///
+/// Returns the kept bytes and the total length.
+async fn read_capped(
+    mut reader: impl AsyncRead + Unpin,
+    limit: Option<usize>,
+) -> io::Result<(Vec<u8>, usize)> {
+    let limit = limit.unwrap_or(usize::MAX);
+    let (mut kept, mut total) = (vec![], 0);
+    let mut buf = [0; 8 * 1024];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break Ok((kept, total));
+        }
+        let rest = limit.saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..cmp::min(n, rest)]);
+        total += n;
+    }
+}
+
+/// Converts the output of [`read_capped`], appending a note if it was truncated.
+fn capped_utf8((bytes, total): (Vec<u8>, usize)) -> anyhow::Result<Arc<str>> {
+    if bytes.len() == total {
+        return utf8(bytes);
+    }
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text += &format!(
+        "... (truncated. {} of {} bytes shown)\n",
+        bytes.len(),
+        total
+    );
+    Ok(text.into())
+}
+
 fn utf8(bytes: Vec<u8>) -> anyhow::Result<Arc<str>> {
     String::from_utf8(bytes)
         .map(Into::into)
         .map_err(|_| anyhow!("the output was not a valid UTF-8 string"))
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::runtime::Runtime;
+
+    fn read_capped(input: &[u8], limit: Option<usize>) -> (Vec<u8>, usize) {
+        Runtime::new()
+            .unwrap()
+            .block_on(super::read_capped(input, limit))
+            .unwrap()
+    }
+
+    #[test]
+    fn read_capped_under_limit() {
+        assert_eq!((b"hello\n".to_vec(), 6), read_capped(b"hello\n", Some(10)));
+        assert_eq!((b"hello\n".to_vec(), 6), read_capped(b"hello\n", None));
+    }
+
+    #[test]
+    fn read_capped_at_limit() {
+        assert_eq!((b"hello\n".to_vec(), 6), read_capped(b"hello\n", Some(6)));
+    }
+
+    #[test]
+    fn read_capped_over_limit() {
+        let input = vec![b'a'; 20 * 1024];
+        assert_eq!((vec![b'a'; 3], 20 * 1024), read_capped(&input, Some(3)));
+    }
+
+    #[test]
+    fn capped_utf8() -> anyhow::Result<()> {
+        assert_eq!(
+            "hello\n",
+            &*super::capped_utf8(read_capped(b"hello\n", Some(6)))?,
+        );
+        assert_eq!(
+            "hel\n... (truncated. 3 of 6 bytes shown)\n",
+            &*super::capped_utf8(read_capped(b"hello\n", Some(3)))?,
+        );
+        assert!(super::capped_utf8((b"\xff\n".to_vec(), 2)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn capped_utf8_lossy() -> anyhow::Result<()> {
+        // "あ" is cut in the middle.
+        assert_eq!(
+            "\u{fffd}\n... (truncated. 2 of 4 bytes shown)\n",
+            &*super::capped_utf8(read_capped("あ\n".as_bytes(), Some(2)))?,
+        );
+        Ok(())
+    }
+}
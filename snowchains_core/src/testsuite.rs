@@ -1,5 +1,6 @@
-use anyhow::{bail, ensure, Context as _};
+use anyhow::{anyhow, bail, ensure, Context as _};
 use camino::Utf8PathBuf;
+use flate2::{write::GzEncoder, Compression};
 use humantime_serde::Serde;
 use ignore::{overrides::OverrideBuilder, WalkBuilder};
 use itertools::{EitherOrBoth, Itertools as _};
@@ -7,11 +8,16 @@ use maplit::hashmap;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use std::{
     borrow::Borrow,
+    cmp,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    convert::TryFrom,
+    ffi::OsStr,
     fs,
     hash::Hash,
+    io::{self, Write as _},
+    iter, ops,
     path::Path,
-    str::FromStr,
+    str::{self, FromStr},
     sync::Arc,
     time::Duration,
 };
@@ -26,6 +32,80 @@ pub enum TestSuite {
 }
 
 impl TestSuite {
+    /// Starts building a `Batch` test suite.
+    ///
+    /// ```
+    /// # use snowchains_core::testsuite::{Match, TestSuite};
+    /// # use std::time::Duration;
+    /// let test_suite = TestSuite::batch()
+    ///     .r#match(Match::Exact)
+    ///     .timelimit(Duration::from_secs(2))
+    ///     .case("sample1", "1 2\n", "3\n")
+    ///     .build();
+    /// ```
+    pub fn batch() -> BatchTestSuiteBuilder {
+        BatchTestSuiteBuilder {
+            suite: BatchTestSuite {
+                timelimit: None,
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
+                cases: vec![],
+                extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
+            },
+        }
+    }
+
+    /// Writes `self` to `path` as JSON if the extension is `json`, otherwise as YAML.
+    ///
+    /// If the extension is `gz` (e.g. `a.yml.gz`), the content is gzipped and the format is
+    /// decided by the inner extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        let gzipped = path.extension() == Some("gz".as_ref());
+        let inner = if gzipped {
+            Path::new(path.file_stem().unwrap_or_default())
+        } else {
+            path
+        };
+
+        let content = if inner.extension() == Some("json".as_ref()) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            self.to_yaml_pretty()
+        };
+
+        let content = if gzipped {
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(content.as_ref())?;
+            encoder.finish()?
+        } else {
+            content.into()
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create `{}`", parent.display()))?;
+        }
+        fs::write(path, content).with_context(|| format!("Could not write `{}`", path.display()))
+    }
+
+    /// Merges `self`, which is newly retrieved, with `old`, which is the existing one.
+    ///
+    /// The settings are taken from `self`. The test cases in `old` that do not appear in `self`
+    /// are appended after the ones in `self`, keeping their order. A test case in `old` is
+    /// replaced by the one in `self` with the same name, so that a changed sample is updated
+    /// instead of duplicated.
+    pub fn merge(self, old: Self) -> Self {
+        match (self, old) {
+            (Self::Batch(new), Self::Batch(old)) => Self::Batch(new.merge(old)),
+            (new, _) => new,
+        }
+    }
+
     pub fn to_yaml_pretty(&self) -> String {
         return if let Self::Batch(suite) = self {
             (|| -> _ {
@@ -33,7 +113,21 @@ impl TestSuite {
 
                 yaml += &key_value("type", "Batch").ok()?;
                 yaml += &key_value("timelimit", Serde::from(suite.timelimit)).ok()?;
-                yaml += &key_value("match", &suite.r#match).ok()?;
+                if let Some(memorylimit) = &suite.memorylimit {
+                    yaml += &key_value("memorylimit", memorylimit).ok()?;
+                }
+                if let Some(systemtests) = suite.systemtests {
+                    yaml += &key_value("systemtests", systemtests).ok()?;
+                }
+                if let Some(r#match) = &suite.r#match {
+                    yaml += &key_value("match", r#match).ok()?;
+                }
+                if suite.allow_nonzero_exit {
+                    yaml += &key_value("allow_nonzero_exit", true).ok()?;
+                }
+                if let Some(normalizer) = &suite.normalizer {
+                    yaml += &key_value("normalizer", normalizer).ok()?;
+                }
 
                 yaml += if suite.cases.is_empty() {
                     "\ncases: []\n"
@@ -51,7 +145,15 @@ impl TestSuite {
                     part += &key_value_in_literal_style("in", &case.r#in).ok()?;
 
                     if let Some(out) = &case.out {
-                        part += &key_value_in_literal_style("out", out).ok()?;
+                        if case.alternative_outs.is_empty() {
+                            part += &key_value_in_literal_style("out", out).ok()?;
+                        } else {
+                            let outs = iter::once(out)
+                                .chain(&case.alternative_outs)
+                                .map(|s| &**s)
+                                .collect::<Vec<_>>();
+                            part += &key_value("out", outs).ok()?;
+                        }
                     }
 
                     if let Some(timelimit) = case.timelimit {
@@ -153,14 +255,33 @@ impl TestSuite {
 pub struct BatchTestSuite {
     #[serde(default, with = "humantime_serde")]
     pub timelimit: Option<Duration>,
-    pub r#match: Match,
+    /// Memory limit shown on the problem page (e.g. `"1024 MB"`). Only informational.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memorylimit: Option<String>,
+    /// Number of the system test cases, recorded by `retrieve testcases --count`. Only informational.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub systemtests: Option<usize>,
+    /// `None` if the file omits it. [`load_test_cases`] uses its `default_match` then.
+    ///
+    /// [`load_test_cases`]: BatchTestSuite::load_test_cases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#match: Option<Match>,
     #[serde(default)]
     pub cases: Vec<PartialBatchTestCase>,
     #[serde(default)]
     pub extend: Vec<Additional>,
+    /// Does not treat nonzero exit statuses as `Runtime Error`. The output is checked as usual.
+    #[serde(default, skip_serializing_if = "ops::Not::not")]
+    pub allow_nonzero_exit: bool,
+    /// Applied to both the expected and actual outputs before matching them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalizer: Option<Normalizer>,
 }
 
 impl BatchTestSuite {
+    /// Loads the test cases, including the ones in `extend`.
+    ///
+    /// `default_match` is used if the test suite does not have `match`.
     pub fn load_test_cases<
         S: Borrow<str> + Eq + Hash,
         F: FnMut(Option<&Url>) -> anyhow::Result<Vec<PartialBatchTestCase>>,
@@ -168,13 +289,24 @@ impl BatchTestSuite {
         &self,
         parent_dir: &Path,
         mut names: Option<HashSet<S>>,
+        default_match: &Match,
         mut prepare_system_test_cases: F,
     ) -> anyhow::Result<Vec<BatchTestCase>> {
+        let r#match = self.r#match.as_ref().unwrap_or(default_match);
+
         let mut cases = self.cases.clone();
         for extend in &self.extend {
             cases.extend(extend.load_test_cases(parent_dir, &mut prepare_system_test_cases)?);
         }
 
+        let mut seen = HashSet::new();
+        for name in cases
+            .iter()
+            .flat_map(|PartialBatchTestCase { name, .. }| name)
+        {
+            ensure!(seen.insert(name), "Duplicate test case name: {:?}", name);
+        }
+
         let cases = cases
             .into_iter()
             .filter(
@@ -183,7 +315,15 @@ impl BatchTestSuite {
                     _ => true,
                 },
             )
-            .map(|case| BatchTestCase::new(case, self.timelimit, &self.r#match))
+            .map(|case| {
+                BatchTestCase::new(
+                    case,
+                    self.timelimit,
+                    r#match,
+                    self.allow_nonzero_exit,
+                    self.normalizer.clone(),
+                )
+            })
             .collect();
 
         if let Some(names) = names {
@@ -197,20 +337,204 @@ impl BatchTestSuite {
 
         Ok(cases)
     }
+
+    /// Returns `cases` followed by the test cases loaded from the `Text` entries of `extend`.
+    ///
+    /// Fails if `extend` has any other entries.
+    pub fn cases_with_text_files(
+        &self,
+        parent_dir: &Path,
+    ) -> anyhow::Result<Vec<PartialBatchTestCase>> {
+        let mut cases = self.cases.clone();
+        for extend in &self.extend {
+            cases.extend(extend.load_test_cases(parent_dir, |_| {
+                bail!("`extend` has entries other than `Text`")
+            })?);
+        }
+        Ok(cases)
+    }
+
+    fn merge(mut self, old: Self) -> Self {
+        for case in old.cases {
+            if !self
+                .cases
+                .iter()
+                .any(|c| c.has_same_in_and_out(&case) || c.name.is_some() && c.name == case.name)
+            {
+                self.cases.push(case);
+            }
+        }
+        for extend in old.extend {
+            if !self.extend.contains(&extend) {
+                self.extend.push(extend);
+            }
+        }
+        self
+    }
+}
+
+/// Builder of a `Batch` test suite, created by [`TestSuite::batch`].
+#[derive(Debug)]
+pub struct BatchTestSuiteBuilder {
+    suite: BatchTestSuite,
+}
+
+impl BatchTestSuiteBuilder {
+    /// Sets `match`. Defaults to [`Match::Lines`].
+    pub fn r#match(mut self, r#match: Match) -> Self {
+        self.suite.r#match = Some(r#match);
+        self
+    }
+
+    pub fn timelimit(mut self, timelimit: Duration) -> Self {
+        self.suite.timelimit = Some(timelimit);
+        self
+    }
+
+    pub fn memorylimit(mut self, memorylimit: impl Into<String>) -> Self {
+        self.suite.memorylimit = Some(memorylimit.into());
+        self
+    }
+
+    /// Adds a test case with the expected output.
+    pub fn case(
+        self,
+        name: impl Into<String>,
+        input: impl Into<Arc<str>>,
+        output: impl Into<Arc<str>>,
+    ) -> Self {
+        self.push_case(name.into(), input.into(), Some(output.into()))
+    }
+
+    /// Adds a test case whose output is only checked for the exit status.
+    pub fn case_without_output(self, name: impl Into<String>, input: impl Into<Arc<str>>) -> Self {
+        self.push_case(name.into(), input.into(), None)
+    }
+
+    fn push_case(mut self, name: String, input: Arc<str>, output: Option<Arc<str>>) -> Self {
+        self.suite.cases.push(PartialBatchTestCase {
+            name: Some(name),
+            r#in: input,
+            out: output,
+            alternative_outs: vec![],
+            timelimit: None,
+            r#match: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> TestSuite {
+        TestSuite::Batch(self.suite)
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(
+    try_from = "PartialBatchTestCaseRepr",
+    into = "PartialBatchTestCaseRepr"
+)]
 pub struct PartialBatchTestCase {
     pub name: Option<String>,
-    #[serde(with = "serde_fn::arc_str")]
     pub r#in: Arc<str>,
-    #[serde(default, with = "serde_fn::option_arc_str")]
     pub out: Option<Arc<str>>,
-    #[serde(default, with = "humantime_serde")]
+    /// Acceptable outputs other than `out`.
+    ///
+    /// In YAML, `out` can be a list of acceptable outputs.
+    pub alternative_outs: Vec<Arc<str>>,
     pub timelimit: Option<Duration>,
     pub r#match: Option<Match>,
 }
 
+#[derive(Deserialize, Serialize)]
+struct PartialBatchTestCaseRepr {
+    name: Option<String>,
+    #[serde(with = "serde_fn::arc_str")]
+    r#in: Arc<str>,
+    #[serde(default)]
+    out: Option<Outs>,
+    #[serde(default, with = "humantime_serde")]
+    timelimit: Option<Duration>,
+    r#match: Option<Match>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Outs {
+    One(#[serde(with = "serde_fn::arc_str")] Arc<str>),
+    AnyOf(#[serde(with = "serde_fn::vec_arc_str")] Vec<Arc<str>>),
+}
+
+impl PartialBatchTestCase {
+    fn has_same_in_and_out(&self, other: &Self) -> bool {
+        self.r#in == other.r#in
+            && self.out == other.out
+            && self.alternative_outs == other.alternative_outs
+    }
+}
+
+impl TryFrom<PartialBatchTestCaseRepr> for PartialBatchTestCase {
+    type Error = &'static str;
+
+    fn try_from(repr: PartialBatchTestCaseRepr) -> Result<Self, &'static str> {
+        let PartialBatchTestCaseRepr {
+            name,
+            r#in,
+            out,
+            timelimit,
+            r#match,
+        } = repr;
+
+        let (out, alternative_outs) = match out {
+            None => (None, vec![]),
+            Some(Outs::One(out)) => (Some(out), vec![]),
+            // An empty list would silently accept any output.
+            Some(Outs::AnyOf(outs)) if outs.is_empty() => return Err("`out` must not be empty"),
+            Some(Outs::AnyOf(outs)) => {
+                let mut outs = outs.into_iter();
+                (outs.next(), outs.collect())
+            }
+        };
+
+        Ok(Self {
+            name,
+            r#in,
+            out,
+            alternative_outs,
+            timelimit,
+            r#match,
+        })
+    }
+}
+
+impl From<PartialBatchTestCase> for PartialBatchTestCaseRepr {
+    fn from(case: PartialBatchTestCase) -> Self {
+        let PartialBatchTestCase {
+            name,
+            r#in,
+            out,
+            alternative_outs,
+            timelimit,
+            r#match,
+        } = case;
+
+        let out = match (out, alternative_outs) {
+            (None, _) => None,
+            (Some(out), alternative_outs) if alternative_outs.is_empty() => Some(Outs::One(out)),
+            (Some(out), alternative_outs) => Some(Outs::AnyOf(
+                iter::once(out).chain(alternative_outs).collect(),
+            )),
+        };
+
+        Self {
+            name,
+            r#in,
+            out,
+            timelimit,
+            r#match,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 #[serde(tag = "type")]
 pub enum Additional {
@@ -275,7 +599,19 @@ impl Additional {
                                 .into_owned();
 
                             let content = fs::read_to_string(&path)
-                                .with_context(|| format!("Could not read {}", path.display()))?
+                                .map_err(|err| {
+                                    // Even with `match: Binary`, only stdout is compared as bytes.
+                                    if err.kind() == io::ErrorKind::InvalidData {
+                                        anyhow!(
+                                            "{} is not valid UTF-8. Test cases are text, including \
+                                             the expected outputs for `Binary`",
+                                            path.display(),
+                                        )
+                                    } else {
+                                        anyhow::Error::from(err)
+                                            .context(format!("Could not read {}", path.display()))
+                                    }
+                                })?
                                 .into();
 
                             Ok(Some((name, content)))
@@ -310,6 +646,7 @@ impl Additional {
                             name: Some(name),
                             r#in,
                             out,
+                            alternative_outs: vec![],
                             timelimit: *timelimit,
                             r#match: r#match.clone(),
                         })
@@ -323,8 +660,14 @@ impl Additional {
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum Match {
+    /// Compares the outputs byte by byte.
     Exact,
     SplitWhitespace,
+    /// Compares the outputs line by line, ignoring trailing `\r`s and spaces of each line and
+    /// trailing empty lines.
+    ///
+    /// This is the default for downloaded test cases on every platform, so `\r\n` from programs
+    /// on Windows are accepted.
     Lines,
     Float {
         relative_error: Option<PositiveFinite<f64>>,
@@ -334,11 +677,44 @@ pub enum Match {
         cmd: String,
         shell: CheckerShell,
     },
+    /// Compares the raw bytes of stdout with `out`, without requiring stdout to be valid UTF-8.
+    ///
+    /// `out` itself is text, so it cannot express a non-UTF-8 output.
+    Binary,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
 pub enum CheckerShell {
     Bash,
+    /// A program and its arguments to which `cmd` is appended (e.g. `[zsh, -c]`).
+    Custom(Vec<String>),
+}
+
+impl CheckerShell {
+    pub(crate) fn command_line<'a>(
+        &'a self,
+        bash_exe: &'a Path,
+        cmd: &'a str,
+    ) -> anyhow::Result<(&'a OsStr, Vec<&'a OsStr>)> {
+        match self {
+            Self::Bash => Ok((bash_exe.as_ref(), vec!["-c".as_ref(), cmd.as_ref()])),
+            Self::Custom(args) => {
+                let (program, args) = args.split_first().with_context(|| "`shell` is empty")?;
+                let args = args
+                    .iter()
+                    .map(AsRef::as_ref)
+                    .chain(iter::once(cmd.as_ref()));
+                Ok((program.as_ref(), args.collect()))
+            }
+        }
+    }
+}
+
+/// A command that reads a text from stdin and writes the normalized one to stdout.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Normalizer {
+    pub cmd: String,
+    pub shell: CheckerShell,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
@@ -353,15 +729,29 @@ pub struct BatchTestCase {
     pub timelimit: Option<Duration>,
     pub input: Arc<str>,
     pub output: ExpectedOutput,
+    pub allow_nonzero_exit: bool,
+    pub normalizer: Option<Normalizer>,
 }
 
 impl BatchTestCase {
-    fn new(case: PartialBatchTestCase, timelimit: Option<Duration>, matching: &Match) -> Self {
+    fn new(
+        case: PartialBatchTestCase,
+        timelimit: Option<Duration>,
+        matching: &Match,
+        allow_nonzero_exit: bool,
+        normalizer: Option<Normalizer>,
+    ) -> Self {
         BatchTestCase {
             name: case.name,
             timelimit: case.timelimit.or(timelimit),
             input: case.r#in,
-            output: ExpectedOutput::new(case.out, case.r#match.unwrap_or_else(|| matching.clone())),
+            output: ExpectedOutput::new(
+                case.out,
+                case.alternative_outs,
+                case.r#match.unwrap_or_else(|| matching.clone()),
+            ),
+            allow_nonzero_exit,
+            normalizer,
         }
     }
 }
@@ -377,38 +767,52 @@ pub enum ExpectedOutput {
 }
 
 impl ExpectedOutput {
-    fn new(text: Option<Arc<str>>, matching: Match) -> Self {
-        match (text, matching) {
+    fn new(text: Option<Arc<str>>, alternatives: Vec<Arc<str>>, matching: Match) -> Self {
+        return match (text, matching) {
             (text, Match::Checker { cmd, shell }) => Self::Checker { text, cmd, shell },
-            (Some(text), Match::Exact) => {
-                Self::Deterministic(DeterministicExpectedOutput::Exact { text })
+            (Some(text), matching) if alternatives.is_empty() => {
+                Self::Deterministic(deterministic(text, &matching))
             }
-            (Some(text), Match::SplitWhitespace) => {
-                Self::Deterministic(DeterministicExpectedOutput::SplitWhitespace { text })
-            }
-            (Some(text), Match::Lines) => {
-                Self::Deterministic(DeterministicExpectedOutput::Lines { text })
-            }
-            (
-                Some(text),
+            (Some(text), matching) => Self::Deterministic(DeterministicExpectedOutput::AnyOf {
+                candidates: iter::once(text)
+                    .chain(alternatives)
+                    .map(|text| deterministic(text, &matching))
+                    .collect(),
+            }),
+            (None, _) => Self::Deterministic(DeterministicExpectedOutput::Pass),
+        };
+
+        fn deterministic(text: Arc<str>, matching: &Match) -> DeterministicExpectedOutput {
+            match *matching {
+                Match::Exact => DeterministicExpectedOutput::Exact { text },
+                Match::SplitWhitespace => DeterministicExpectedOutput::SplitWhitespace { text },
+                Match::Lines => DeterministicExpectedOutput::Lines { text },
                 Match::Float {
                     relative_error,
                     absolute_error,
+                } => DeterministicExpectedOutput::Float {
+                    text,
+                    relative_error,
+                    absolute_error,
                 },
-            ) => Self::Deterministic(DeterministicExpectedOutput::Float {
-                text,
-                relative_error,
-                absolute_error,
-            }),
-            (None, _) => Self::Deterministic(DeterministicExpectedOutput::Pass),
+                Match::Binary => DeterministicExpectedOutput::Binary { text },
+                Match::Checker { .. } => unreachable!(),
+            }
         }
     }
 
     pub(crate) fn is_float(&self) -> bool {
-        matches!(
-            self,
-            Self::Deterministic(DeterministicExpectedOutput::Float { .. })
-        )
+        match self {
+            Self::Deterministic(expected) => expected.is_float(),
+            Self::Checker { .. } => false,
+        }
+    }
+
+    pub(crate) fn is_binary(&self) -> bool {
+        match self {
+            Self::Deterministic(expected) => expected.is_binary(),
+            Self::Checker { .. } => false,
+        }
     }
 
     pub(crate) fn expected_stdout(&self) -> Option<&str> {
@@ -418,12 +822,42 @@ impl ExpectedOutput {
         }
     }
 
+    /// Returns the expected output closest to `actual` if any.
+    pub(crate) fn closest_expected_stdout(&self, actual: Option<&str>) -> Option<&str> {
+        match (self, actual) {
+            (Self::Deterministic(expected), Some(actual)) => {
+                expected.closest_expected_stdout(actual)
+            }
+            _ => self.expected_stdout(),
+        }
+    }
+
     pub(crate) fn example(&self) -> Option<&str> {
         match self {
             Self::Checker { text, .. } => text.as_deref(),
             _ => None,
         }
     }
+
+    /// Returns every text in `self`, including the alternatives.
+    pub(crate) fn texts(&self) -> Vec<&Arc<str>> {
+        match self {
+            Self::Deterministic(expected) => expected.texts(),
+            Self::Checker { text, .. } => text.iter().collect(),
+        }
+    }
+
+    /// Replaces every text in `self` with `f(text)`.
+    pub(crate) fn map_texts(&self, mut f: impl FnMut(&Arc<str>) -> Arc<str>) -> Self {
+        match self {
+            Self::Deterministic(expected) => Self::Deterministic(expected.map_texts(&mut f)),
+            Self::Checker { text, cmd, shell } => Self::Checker {
+                text: text.as_ref().map(f),
+                cmd: cmd.clone(),
+                shell: shell.clone(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -443,15 +877,23 @@ pub enum DeterministicExpectedOutput {
         relative_error: Option<PositiveFinite<f64>>,
         absolute_error: Option<PositiveFinite<f64>>,
     },
+    /// The expected output is UTF-8 text. Only the actual output is compared as raw bytes.
+    Binary {
+        text: Arc<str>,
+    },
+    AnyOf {
+        candidates: Vec<Self>,
+    },
 }
 
 impl DeterministicExpectedOutput {
     pub(crate) fn accepts(&self, actual: &str) -> bool {
         match self {
             Self::Pass => true,
-            Self::Exact { text } => &**text == actual,
+            Self::AnyOf { candidates } => candidates.iter().any(|c| c.accepts(actual)),
+            Self::Exact { text } | Self::Binary { text } => &**text == actual,
             Self::SplitWhitespace { text } => text.split_whitespace().eq(actual.split_whitespace()),
-            Self::Lines { text } => text.lines().eq(actual.lines()),
+            Self::Lines { text } => normalized_lines(text) == normalized_lines(actual),
             Self::Float {
                 text,
                 relative_error,
@@ -484,17 +926,117 @@ impl DeterministicExpectedOutput {
         }
     }
 
+    /// Same as `accepts`, except that `Binary` sees `actual` as it is and the others reject
+    /// `actual` if it is not valid UTF-8.
+    pub(crate) fn accepts_bytes(&self, actual: &[u8]) -> bool {
+        match self {
+            Self::Binary { text } => text.as_bytes() == actual,
+            Self::AnyOf { candidates } => candidates.iter().any(|c| c.accepts_bytes(actual)),
+            _ => str::from_utf8(actual).is_ok_and(|actual| self.accepts(actual)),
+        }
+    }
+
+    /// Whether this compares the outputs byte by byte.
+    pub(crate) fn is_exact(&self) -> bool {
+        match self {
+            Self::Exact { .. } => true,
+            Self::AnyOf { candidates } => candidates.iter().all(Self::is_exact),
+            _ => false,
+        }
+    }
+
     pub(crate) fn expected_stdout(&self) -> Option<&str> {
         match self {
             Self::Pass => None,
             Self::Exact { text }
+            | Self::Binary { text }
             | Self::SplitWhitespace { text }
             | Self::Lines { text }
             | Self::Float { text, .. } => Some(text),
+            Self::AnyOf { candidates } => candidates.first()?.expected_stdout(),
+        }
+    }
+
+    pub(crate) fn closest_expected_stdout(&self, actual: &str) -> Option<&str> {
+        if let Self::AnyOf { candidates } = self {
+            candidates
+                .iter()
+                .flat_map(Self::expected_stdout)
+                .max_by_key(|expected| {
+                    let num_matched_lines = expected
+                        .lines()
+                        .zip(actual.lines())
+                        .filter(|(l, r)| l == r)
+                        .count();
+                    (num_matched_lines, cmp::Reverse(expected.len()))
+                })
+        } else {
+            self.expected_stdout()
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        match self {
+            Self::Float { .. } => true,
+            Self::AnyOf { candidates } => candidates.iter().any(Self::is_float),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn is_binary(&self) -> bool {
+        match self {
+            Self::Binary { .. } => true,
+            Self::AnyOf { candidates } => candidates.iter().any(Self::is_binary),
+            _ => false,
+        }
+    }
+
+    fn texts(&self) -> Vec<&Arc<str>> {
+        match self {
+            Self::Pass => vec![],
+            Self::Exact { text }
+            | Self::Binary { text }
+            | Self::SplitWhitespace { text }
+            | Self::Lines { text }
+            | Self::Float { text, .. } => vec![text],
+            Self::AnyOf { candidates } => candidates.iter().flat_map(Self::texts).collect(),
+        }
+    }
+
+    fn map_texts(&self, f: &mut impl FnMut(&Arc<str>) -> Arc<str>) -> Self {
+        match self {
+            Self::Pass => Self::Pass,
+            Self::Exact { text } => Self::Exact { text: f(text) },
+            Self::SplitWhitespace { text } => Self::SplitWhitespace { text: f(text) },
+            Self::Lines { text } => Self::Lines { text: f(text) },
+            Self::Float {
+                text,
+                relative_error,
+                absolute_error,
+            } => Self::Float {
+                text: f(text),
+                relative_error: *relative_error,
+                absolute_error: *absolute_error,
+            },
+            Self::Binary { text } => Self::Binary { text: f(text) },
+            Self::AnyOf { candidates } => Self::AnyOf {
+                candidates: candidates.iter().map(|c| c.map_texts(f)).collect(),
+            },
         }
     }
 }
 
+fn normalized_lines(s: &str) -> Vec<&str> {
+    let mut lines = s
+        .lines()
+        .map(|line| line.trim_end_matches([' ', '\r']))
+        .collect::<Vec<_>>();
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize)]
 #[serde(transparent)]
 pub struct PositiveFinite<F>(F);
@@ -551,29 +1093,23 @@ mod serde_fn {
         }
     }
 
-    pub(super) mod option_arc_str {
+    pub(super) mod vec_arc_str {
         use serde::{Deserialize, Deserializer, Serializer};
         use std::sync::Arc;
 
-        pub(crate) fn serialize<S>(
-            this: &Option<Arc<str>>,
-            serializer: S,
-        ) -> Result<S::Ok, S::Error>
+        pub(crate) fn serialize<S>(this: &[Arc<str>], serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
-            if let Some(s) = this {
-                serializer.serialize_some(&**s)
-            } else {
-                serializer.serialize_none()
-            }
+            serializer.collect_seq(this.iter().map(|s| &**s))
         }
 
-        pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Arc<str>>, D::Error>
+        pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Arc<str>>, D::Error>
         where
             D: Deserializer<'de>,
         {
-            Option::<String>::deserialize(deserializer).map(|s| s.map(Into::into))
+            Vec::<String>::deserialize(deserializer)
+                .map(|ss| ss.into_iter().map(Into::into).collect())
         }
     }
 }
@@ -581,12 +1117,12 @@ mod serde_fn {
 #[cfg(test)]
 mod tests {
     use crate::testsuite::{
-        Additional, BatchTestSuite, DeterministicExpectedOutput, Match, PartialBatchTestCase,
-        PositiveFinite, TestSuite,
+        Additional, BatchTestCase, BatchTestSuite, CheckerShell, DeterministicExpectedOutput,
+        ExpectedOutput, Match, Normalizer, PartialBatchTestCase, PositiveFinite, TestSuite,
     };
     use difference::assert_diff;
     use pretty_assertions::assert_eq;
-    use std::time::Duration;
+    use std::{collections::HashSet, ffi::OsStr, fs, path::Path, sync::Arc, time::Duration};
 
     #[test]
     fn atcoder_abc162_a() {
@@ -617,12 +1153,15 @@ extend: []
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
-                r#match: Match::Lines,
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
                 cases: vec![
                     PartialBatchTestCase {
                         name: Some("Sample 1".to_owned()),
                         r#in: "117\n".into(),
                         out: Some("Yes\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
@@ -630,6 +1169,7 @@ extend: []
                         name: Some("Sample 2".to_owned()),
                         r#in: "123\n".into(),
                         out: Some("No\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
@@ -637,11 +1177,14 @@ extend: []
                         name: Some("Sample 3".to_owned()),
                         r#in: "777\n".into(),
                         out: Some("Yes\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
                 ],
                 extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
             }),
         );
 
@@ -661,7 +1204,9 @@ extend:
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
-                r#match: Match::Lines,
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
                 cases: vec![],
                 extend: vec![Additional::Text {
                     path: "./a".into(),
@@ -670,6 +1215,8 @@ extend:
                     timelimit: None,
                     r#match: None,
                 }],
+                allow_nonzero_exit: false,
+                normalizer: None,
             }),
         );
     }
@@ -701,15 +1248,18 @@ extend: []
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
-                r#match: Match::Float {
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Float {
                     relative_error: Some(PositiveFinite(0.01)),
                     absolute_error: Some(PositiveFinite(0.01)),
-                },
+                }),
                 cases: vec![
                     PartialBatchTestCase {
                         name: Some("Sample 1".to_owned()),
                         r#in: "1\n".into(),
                         out: Some("6.28318530717958623200\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
@@ -717,11 +1267,14 @@ extend: []
                         name: Some("Sample 2".to_owned()),
                         r#in: "73\n".into(),
                         out: Some("458.67252742410977361942\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
                 ],
                 extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
             }),
         );
     }
@@ -755,12 +1308,15 @@ extend: []
 "#,
             &TestSuite::Batch(BatchTestSuite {
                 timelimit: Some(Duration::from_secs(2)),
-                r#match: Match::Lines,
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
                 cases: vec![
                     PartialBatchTestCase {
                         name: Some("Sample 1".to_owned()),
                         r#in: "3\ncbaa\ndaacc\nacacac\n".into(),
                         out: Some("aac\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
@@ -768,15 +1324,440 @@ extend: []
                         name: Some("Sample 2".to_owned()),
                         r#in: "3\na\naa\nb\n".into(),
                         out: Some("\n".into()),
+                        alternative_outs: vec![],
                         timelimit: None,
                         r#match: None,
                     },
                 ],
                 extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
             }),
         );
     }
 
+    #[test]
+    fn any_of_outs() {
+        test_serialize_deserialize(
+            r#"---
+type: Batch
+timelimit: 2s
+match: Lines
+
+cases:
+  - name: Sample 1
+    in: |
+      4
+    out:
+      - "2 2\n"
+      - "1 3\n"
+
+extend: []
+"#,
+            &TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
+                cases: vec![PartialBatchTestCase {
+                    name: Some("Sample 1".to_owned()),
+                    r#in: "4\n".into(),
+                    out: Some("2 2\n".into()),
+                    alternative_outs: vec!["1 3\n".into()],
+                    timelimit: None,
+                    r#match: None,
+                }],
+                extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
+            }),
+        );
+
+        let empty = r#"---
+type: Batch
+timelimit: 2s
+match: Lines
+
+cases:
+  - in: "4\n"
+    out: []
+"#;
+        assert!(serde_yaml::from_str::<TestSuite>(empty).is_err());
+    }
+
+    #[test]
+    fn allow_nonzero_exit_pretty() {
+        test_serialize_deserialize(
+            r#"---
+type: Batch
+timelimit: 2s
+match: Lines
+allow_nonzero_exit: true
+
+cases:
+  - in: |
+      4
+    out: |
+      2 2
+
+extend: []
+"#,
+            &TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs(2)),
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
+                cases: vec![PartialBatchTestCase {
+                    name: None,
+                    r#in: "4\n".into(),
+                    out: Some("2 2\n".into()),
+                    alternative_outs: vec![],
+                    timelimit: None,
+                    r#match: None,
+                }],
+                extend: vec![],
+                allow_nonzero_exit: true,
+                normalizer: None,
+            }),
+        );
+    }
+
+    #[test]
+    fn json() {
+        let test_suite = TestSuite::Batch(BatchTestSuite {
+            timelimit: Some(Duration::from_secs(2)),
+            memorylimit: None,
+            systemtests: None,
+            r#match: Some(Match::Float {
+                relative_error: Some(PositiveFinite(1e-6)),
+                absolute_error: None,
+            }),
+            cases: vec![PartialBatchTestCase {
+                name: Some("Sample 1".to_owned()),
+                r#in: "4\n".into(),
+                out: Some("2 2\n".into()),
+                alternative_outs: vec!["1 3\n".into()],
+                timelimit: Some(Duration::from_millis(500)),
+                r#match: Some(Match::Lines),
+            }],
+            extend: vec![Additional::Text {
+                path: "./a".into(),
+                r#in: "/in/*.txt".to_owned(),
+                out: "/out/*.txt".to_owned(),
+                timelimit: None,
+                r#match: None,
+            }],
+            allow_nonzero_exit: false,
+            normalizer: None,
+        });
+
+        let json = serde_json::to_string(&test_suite).unwrap();
+        assert_eq!(test_suite, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn merge() {
+        fn case(name: &str, r#in: &str, out: &str) -> PartialBatchTestCase {
+            PartialBatchTestCase {
+                name: Some(name.to_owned()),
+                r#in: r#in.into(),
+                out: Some(out.into()),
+                alternative_outs: vec![],
+                timelimit: None,
+                r#match: None,
+            }
+        }
+
+        fn suite(timelimit: u64, cases: Vec<PartialBatchTestCase>) -> TestSuite {
+            TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs(timelimit)),
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
+                cases,
+                extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
+            })
+        }
+
+        let old = suite(
+            2,
+            vec![
+                case("Sample 1", "1\n", "1\n"),
+                case("mine 2", "0\n", "0\n"),
+                case("mine 1", "100\n", "100\n"),
+            ],
+        );
+        let new = suite(
+            3,
+            vec![
+                case("Sample 1", "1\n", "1\n"),
+                case("Sample 2", "2\n", "4\n"),
+            ],
+        );
+
+        assert_eq!(
+            suite(
+                3,
+                vec![
+                    case("Sample 1", "1\n", "1\n"),
+                    case("Sample 2", "2\n", "4\n"),
+                    case("mine 2", "0\n", "0\n"),
+                    case("mine 1", "100\n", "100\n"),
+                ],
+            ),
+            new.merge(old),
+        );
+
+        let old = suite(
+            2,
+            vec![case("Sample 1", "1\n", "2\n"), case("mine 1", "0\n", "0\n")],
+        );
+        let new = suite(2, vec![case("Sample 1", "1\n", "1\n")]);
+
+        assert_eq!(
+            suite(
+                2,
+                vec![case("Sample 1", "1\n", "1\n"), case("mine 1", "0\n", "0\n")],
+            ),
+            new.merge(old),
+        );
+    }
+
+    #[test]
+    fn non_utf8_text_file() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-core-tests-")
+            .tempdir()?;
+        fs::create_dir_all(tempdir.path().join("a").join("in"))?;
+        fs::create_dir_all(tempdir.path().join("a").join("out"))?;
+        fs::write(tempdir.path().join("a").join("in").join("1.txt"), "")?;
+        fs::write(
+            tempdir.path().join("a").join("out").join("1.txt"),
+            b"\xff\n",
+        )?;
+
+        let suite = BatchTestSuite {
+            timelimit: None,
+            memorylimit: None,
+            systemtests: None,
+            r#match: Some(Match::Binary),
+            cases: vec![],
+            extend: vec![Additional::Text {
+                path: "./a".into(),
+                r#in: "/in/*.txt".to_owned(),
+                out: "/out/*.txt".to_owned(),
+                timelimit: None,
+                r#match: None,
+            }],
+            allow_nonzero_exit: false,
+            normalizer: None,
+        };
+
+        let err = suite.cases_with_text_files(tempdir.path()).unwrap_err();
+        assert!(format!("{:#}", err).contains("is not valid UTF-8"));
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_names() {
+        fn case(name: Option<&str>) -> PartialBatchTestCase {
+            PartialBatchTestCase {
+                name: name.map(ToOwned::to_owned),
+                r#in: "".into(),
+                out: None,
+                alternative_outs: vec![],
+                timelimit: None,
+                r#match: None,
+            }
+        }
+
+        fn load(cases: Vec<PartialBatchTestCase>) -> anyhow::Result<Vec<BatchTestCase>> {
+            BatchTestSuite {
+                timelimit: None,
+                memorylimit: None,
+                systemtests: None,
+                r#match: Some(Match::Lines),
+                cases,
+                extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
+            }
+            .load_test_cases(
+                Path::new(""),
+                None::<HashSet<String>>,
+                &Match::Lines,
+                |_| Ok(vec![]),
+            )
+        }
+
+        assert!(load(vec![case(Some("Sample 1")), case(None), case(None)]).is_ok());
+
+        let err = load(vec![case(Some("Sample 1")), case(Some("Sample 1"))]).unwrap_err();
+        assert_eq!(r#"Duplicate test case name: "Sample 1""#, err.to_string());
+    }
+
+    #[test]
+    fn default_match() -> anyhow::Result<()> {
+        let load = |yaml: &str, default_match: &Match| -> anyhow::Result<_> {
+            let test_suite = match serde_yaml::from_str(yaml)? {
+                TestSuite::Batch(test_suite) => test_suite,
+                _ => unreachable!(),
+            };
+            let cases = test_suite.load_test_cases(
+                Path::new(""),
+                None::<HashSet<String>>,
+                default_match,
+                |_| Ok(vec![]),
+            )?;
+            match &cases[0].output {
+                ExpectedOutput::Deterministic(output) => Ok(output.clone()),
+                _ => unreachable!(),
+            }
+        };
+
+        let without_match = r#"---
+type: Batch
+timelimit: 2s
+cases:
+  - in: "1\n"
+    out: "1\n"
+"#;
+        let with_match = r#"---
+type: Batch
+timelimit: 2s
+match: Exact
+cases:
+  - in: "1\n"
+    out: "1\n"
+"#;
+
+        let lines = load(without_match, &Match::Lines)?;
+        assert!(lines.accepts("1\r\n"));
+
+        let exact = load(without_match, &Match::Exact)?;
+        assert!(!exact.accepts("1\r\n"));
+
+        let exact = load(with_match, &Match::Lines)?;
+        assert!(!exact.accepts("1\r\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn allow_nonzero_exit() -> anyhow::Result<()> {
+        let load = |yaml: &str| -> anyhow::Result<_> {
+            let test_suite = match serde_yaml::from_str(yaml)? {
+                TestSuite::Batch(test_suite) => test_suite,
+                _ => unreachable!(),
+            };
+            let cases = test_suite.load_test_cases(
+                Path::new(""),
+                None::<HashSet<String>>,
+                &Match::Lines,
+                |_| Ok(vec![]),
+            )?;
+            Ok(cases[0].allow_nonzero_exit)
+        };
+
+        assert!(!load(
+            r#"---
+type: Batch
+cases:
+  - in: "1\n"
+    out: "1\n"
+"#,
+        )?);
+        assert!(load(
+            r#"---
+type: Batch
+allow_nonzero_exit: true
+cases:
+  - in: "1\n"
+    out: "1\n"
+"#,
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn normalizer() -> anyhow::Result<()> {
+        let yaml = r#"---
+type: Batch
+timelimit: ~
+match: Lines
+normalizer:
+  cmd: sort
+  shell: Bash
+
+cases:
+  - in: |
+      1
+    out: |
+      1
+      2
+
+extend: []
+"#;
+
+        let test_suite = match serde_yaml::from_str::<TestSuite>(yaml)? {
+            TestSuite::Batch(test_suite) => test_suite,
+            _ => unreachable!(),
+        };
+        let cases = test_suite.load_test_cases(
+            Path::new(""),
+            None::<HashSet<String>>,
+            &Match::Lines,
+            |_| Ok(vec![]),
+        )?;
+        assert_eq!(
+            Some(Normalizer {
+                cmd: "sort".to_owned(),
+                shell: CheckerShell::Bash,
+            }),
+            cases[0].normalizer,
+        );
+        assert_eq!(yaml, TestSuite::Batch(test_suite).to_yaml_pretty(),);
+
+        let expected = cases[0].output.map_texts(|text| text.trim_end().into());
+        assert_eq!(vec![&Arc::<str>::from("1\n2")], expected.texts());
+        Ok(())
+    }
+
+    #[test]
+    fn checker_shell() -> anyhow::Result<()> {
+        let r#match = serde_yaml::from_str::<Match>(
+            r#"Checker:
+  cmd: diff "$EXPECTED_OUTPUT" "$ACTUAL_OUTPUT"
+  shell:
+    Custom: [bash, -c]
+"#,
+        )?;
+
+        let shell = match &r#match {
+            Match::Checker { shell, .. } => shell,
+            _ => panic!("expected `Checker`"),
+        };
+        assert_eq!(
+            CheckerShell::Custom(vec!["bash".to_owned(), "-c".to_owned()]),
+            *shell,
+        );
+
+        let (program, args) = shell.command_line(Path::new("/bin/bash-default"), "true")?;
+        assert_eq!(OsStr::new("bash"), program);
+        assert_eq!(vec![OsStr::new("-c"), OsStr::new("true")], args);
+
+        let (program, args) =
+            CheckerShell::Bash.command_line(Path::new("/bin/bash-default"), "true")?;
+        assert_eq!(OsStr::new("/bin/bash-default"), program);
+        assert_eq!(vec![OsStr::new("-c"), OsStr::new("true")], args);
+
+        assert!(CheckerShell::Custom(vec![])
+            .command_line(Path::new("bash"), "true")
+            .is_err());
+        Ok(())
+    }
+
     fn test_serialize_deserialize(yaml: &str, expected: &TestSuite) {
         let actual = serde_yaml::from_str::<TestSuite>(yaml).unwrap();
         assert_eq!(*expected, actual);
@@ -802,6 +1783,21 @@ extend: []
         }
         .accepts("1\n2\n"));
 
+        assert!(DeterministicExpectedOutput::Binary {
+            text: "1 2\n".into()
+        }
+        .accepts_bytes(b"1 2\n"));
+
+        assert!(!DeterministicExpectedOutput::Binary {
+            text: "1 2\n".into()
+        }
+        .accepts_bytes(b"1 2\n\xff"));
+
+        assert!(!DeterministicExpectedOutput::Exact {
+            text: "1 2\n".into()
+        }
+        .accepts_bytes(b"1 2\n\xff"));
+
         assert!(DeterministicExpectedOutput::SplitWhitespace { text: "".into() }.accepts(""));
 
         assert!(DeterministicExpectedOutput::SplitWhitespace { text: "\n".into() }.accepts(""));
@@ -837,6 +1833,16 @@ extend: []
         }
         .accepts("1\n2\n"));
 
+        assert!(DeterministicExpectedOutput::Lines {
+            text: "1 2\n3\n".into()
+        }
+        .accepts("1 2 \r\n3\r\n\n"));
+
+        assert!(!DeterministicExpectedOutput::Exact {
+            text: "1 2\n".into()
+        }
+        .accepts("1 2\r\n"));
+
         assert!(DeterministicExpectedOutput::Float {
             text: "10000.0\n".into(),
             relative_error: Some(PositiveFinite(0.01)),
@@ -850,5 +1856,19 @@ extend: []
             absolute_error: None,
         }
         .accepts("0\n"));
+
+        let any_of = DeterministicExpectedOutput::AnyOf {
+            candidates: vec![
+                DeterministicExpectedOutput::Lines {
+                    text: "2 2\n".into(),
+                },
+                DeterministicExpectedOutput::Lines {
+                    text: "1 3\n".into(),
+                },
+            ],
+        };
+        assert!(any_of.accepts("2 2\n"));
+        assert!(any_of.accepts("1 3\n"));
+        assert!(!any_of.accepts("3 1\n"));
     }
 }
@@ -0,0 +1,277 @@
+use crate::{
+    testsuite::{BatchTestSuite, Match, PartialBatchTestCase, TestSuite},
+    web::{
+        aoj::api::SessionMutExt as _, Exec, Platform, RetrieveTestCases, RetrieveTestCasesOutcome,
+        RetrieveTestCasesOutcomeProblem, Session, Shell,
+    },
+};
+use anyhow::bail;
+use indexmap::indexmap;
+use once_cell::sync::Lazy;
+use std::{collections::BTreeSet, convert::Infallible, time::Duration};
+use url::Url;
+
+static BASE_URL: Lazy<Url> = lazy_url!("https://onlinejudge.u-aizu.ac.jp");
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Aoj {}
+
+impl Aoj {
+    pub fn exec<A>(args: A) -> anyhow::Result<<Self as Exec<A>>::Output>
+    where
+        Self: Exec<A>,
+    {
+        <Self as Exec<_>>::exec(args)
+    }
+}
+
+impl Platform for Aoj {
+    type CookieStorage = ();
+    type LoginCredentials = Infallible;
+    type ParticipateTarget = Infallible;
+    type ParticipateCredentials = Infallible;
+    type RetrieveLanguagesTarget = Infallible;
+    type RetrieveLanguagesCredentials = Infallible;
+    type RetrieveTestCasesTargets = AojRetrieveTestCasesTargets;
+    type RetrieveTestCasesCredentials = ();
+    type RetrieveFullTestCasesCredentials = Infallible;
+    type RetrieveSubmissionSummariesTarget = Infallible;
+    type RetrieveSubmissionSummariesCredentials = Infallible;
+    type WatchSubmissionsTarget = Infallible;
+    type WatchSubmissionsCredentials = Infallible;
+    type SubmitTarget = Infallible;
+    type SubmitCredentials = Infallible;
+}
+
+impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Aoj {
+    type Output = RetrieveTestCasesOutcome;
+
+    fn exec(args: RetrieveTestCases<Self, S>) -> anyhow::Result<RetrieveTestCasesOutcome> {
+        let RetrieveTestCases {
+            targets,
+            credentials: (),
+            full,
+            cookie_storage: (),
+            timeout,
+            user_agent,
+            headers,
+            request_interval,
+            skip_problems,
+            shell,
+        } = args;
+
+        if let Some(full) = full {
+            match full.credentials {}
+        }
+
+        let mut sess = Session::new(timeout, user_agent.as_deref(), &headers, None, shell)?;
+        sess.request_interval = request_interval;
+
+        let problem_ids = match targets {
+            AojRetrieveTestCasesTargets::ProblemIds(problem_ids) => problem_ids,
+            AojRetrieveTestCasesTargets::Urls(urls) => urls
+                .iter()
+                .map(parse_problem_url)
+                .collect::<anyhow::Result<_>>()?,
+        };
+
+        let mut outcome = RetrieveTestCasesOutcome { problems: vec![] };
+
+        for problem_id in &problem_ids {
+            if super::is_skipped(&skip_problems, problem_id) {
+                continue;
+            }
+
+            let api::Problem {
+                id,
+                name,
+                problem_time_limit,
+                problem_memory_limit,
+            } = sess.get_problem(&normalize_problem_id(problem_id))?;
+
+            let cases = sess
+                .get_sample_test_cases(&id)?
+                .into_iter()
+                .map(|api::Sample { serial, r#in, out }| PartialBatchTestCase {
+                    name: Some(format!("sample{}", serial)),
+                    r#in: r#in.into(),
+                    out: Some(out.into()),
+                    alternative_outs: vec![],
+                    timelimit: None,
+                    r#match: None,
+                })
+                .collect();
+
+            let test_suite = TestSuite::Batch(BatchTestSuite {
+                timelimit: Some(Duration::from_secs_f64(problem_time_limit)),
+                memorylimit: Some(format_memorylimit(problem_memory_limit)),
+                systemtests: None,
+                r#match: Some(Match::Lines),
+                cases,
+                extend: vec![],
+                allow_nonzero_exit: false,
+                normalizer: None,
+            });
+
+            outcome.problems.push(RetrieveTestCasesOutcomeProblem {
+                contest: None,
+                index: problem_id.clone(),
+                url: problem_url(&id),
+                screen_name: Some(id),
+                display_name: name,
+                score: None,
+                test_suite,
+                text_files: indexmap!(),
+            });
+        }
+
+        Ok(outcome)
+    }
+}
+
+#[derive(Debug)]
+pub enum AojRetrieveTestCasesTargets {
+    ProblemIds(BTreeSet<String>),
+    Urls(BTreeSet<Url>),
+}
+
+/// Returns the URL of the problem page (e.g. `https://onlinejudge.u-aizu.ac.jp/problems/ITP1_1_A`).
+pub fn problem_url(problem_id: &str) -> Url {
+    // `url!` would percent-encode `_`.
+    BASE_URL
+        .join(&format!("/problems/{}", normalize_problem_id(problem_id)))
+        .expect("should be valid")
+}
+
+/// Converts a problem ID given in the file name form (e.g. `itp1-1-a`) into the actual one
+/// (e.g. `ITP1_1_A`).
+fn normalize_problem_id(problem_id: &str) -> String {
+    problem_id.to_ascii_uppercase().replace('-', "_")
+}
+
+fn parse_problem_url(url: &Url) -> anyhow::Result<String> {
+    match url.domain() {
+        Some("onlinejudge.u-aizu.ac.jp") => {
+            // `/problems/{id}` or `/courses/.../{id}`
+            if let Some(caps) =
+                static_regex!(r"\A/(problems|courses/.*)/([0-9A-Za-z_]+)/?\z").captures(url.path())
+            {
+                return Ok(caps[2].to_owned());
+            }
+        }
+        Some("judge.u-aizu.ac.jp") => {
+            if let Some((_, id)) = url.query_pairs().find(|(k, _)| k == "id") {
+                return Ok(id.into_owned());
+            }
+        }
+        _ => bail!("wrong domain. expected `onlinejudge.u-aizu.ac.jp`: {}", url),
+    }
+    bail!("not a URL for a problem in AOJ: {}", url);
+}
+
+/// Formats a memory limit in KB as the other services write it (e.g. "128 MB").
+fn format_memorylimit(kilobytes: u64) -> String {
+    if kilobytes.is_multiple_of(1024) {
+        format!("{} MB", kilobytes / 1024)
+    } else {
+        format!("{} KB", kilobytes)
+    }
+}
+
+mod api {
+    //! <http://developers.u-aizu.ac.jp/index>
+
+    use crate::web::{ResponseExt as _, SessionMut};
+    use anyhow::bail;
+    use once_cell::sync::Lazy;
+    use serde::Deserialize;
+    use url::Url;
+
+    static JUDGEAPI_BASE_URL: Lazy<Url> = lazy_url!("https://judgeapi.u-aizu.ac.jp/");
+    static JUDGEDAT_BASE_URL: Lazy<Url> = lazy_url!("https://judgedat.u-aizu.ac.jp/");
+
+    pub(super) trait SessionMutExt: SessionMut {
+        /// > 問題IDに対応する問題の情報を取得します。
+        fn get_problem(&mut self, problem_id: &str) -> anyhow::Result<Problem> {
+            let url = JUDGEAPI_BASE_URL.join(&format!("problems/{}", problem_id))?;
+
+            let res = self
+                .get(url)
+                .colorize_status_code(&[200], (), ..)
+                .send()?
+                .ensure_status(&[200, 400, 404])?;
+
+            if res.status() == 200 {
+                res.json().map_err(Into::into)
+            } else {
+                log::debug!("{}", res.text()?);
+                bail!("No such problem: `{}`", problem_id);
+            }
+        }
+
+        /// > 問題IDに対応するサンプル入出力を取得します。
+        fn get_sample_test_cases(&mut self, problem_id: &str) -> anyhow::Result<Vec<Sample>> {
+            let url = JUDGEDAT_BASE_URL.join(&format!("testcases/samples/{}", problem_id))?;
+
+            self.get(url)
+                .colorize_status_code(&[200], (), ..)
+                .send()?
+                .ensure_status(&[200])?
+                .json()
+                .map_err(Into::into)
+        }
+    }
+
+    impl<S: SessionMut> SessionMutExt for S {}
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub(super) struct Problem {
+        pub(super) id: String,
+        pub(super) name: String,
+        /// In seconds
+        pub(super) problem_time_limit: f64,
+        /// In KB
+        pub(super) problem_memory_limit: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub(super) struct Sample {
+        pub(super) serial: u64,
+        pub(super) r#in: String,
+        pub(super) out: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    #[test]
+    fn problem_url() {
+        assert_eq!(
+            "https://onlinejudge.u-aizu.ac.jp/problems/ITP1_1_A",
+            super::problem_url("itp1-1-a").as_str(),
+        );
+    }
+
+    #[test]
+    fn parse_problem_url() -> anyhow::Result<()> {
+        let parse = |url: &str| super::parse_problem_url(&url.parse::<Url>()?);
+
+        assert_eq!(
+            "ITP1_1_A",
+            parse("https://onlinejudge.u-aizu.ac.jp/problems/ITP1_1_A")?,
+        );
+        assert_eq!(
+            "ITP1_1_A",
+            parse("https://onlinejudge.u-aizu.ac.jp/courses/lesson/2/ITP1/1/ITP1_1_A")?,
+        );
+        assert_eq!(
+            "0001",
+            parse("https://judge.u-aizu.ac.jp/onlinejudge/description.jsp?id=0001")?,
+        );
+        assert!(parse("https://atcoder.jp/contests/abc100/tasks/abc100_a").is_err());
+        Ok(())
+    }
+}
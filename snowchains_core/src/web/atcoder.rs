@@ -4,13 +4,13 @@ use crate::{
         TestSuite,
     },
     web::{
-        AnsiColored, CaseConverted, CookieStorage, Exec, Login, LoginOutcome, LowerCase,
-        Participate, ParticipateOutcome, Platform, ProblemInContest, ProblemsInContest,
-        ResponseExt as _, RetrieveFullTestCases, RetrieveLanguages, RetrieveLanguagesOutcome,
-        RetrieveSubmissionSummaries, RetrieveTestCases, RetrieveTestCasesOutcome,
-        RetrieveTestCasesOutcomeProblem, RetrieveTestCasesOutcomeProblemContest,
-        RetrieveTestCasesOutcomeProblemTextFiles, Session, SessionMut, Shell, Submit,
-        SubmitOutcome, WatchSubmissions,
+        AnsiColored, CaseConverted, ContestNotBegun, CookieStorage, Exec, Login, LoginOutcome,
+        LoginRequired, LowerCase, Participate, ParticipateOutcome, Platform, PlatformKind,
+        ProblemInContest, ProblemsInContest, ResponseExt as _, RetrieveFullTestCases,
+        RetrieveLanguages, RetrieveLanguagesOutcome, RetrieveSubmissionSummaries,
+        RetrieveTestCases, RetrieveTestCasesOutcome, RetrieveTestCasesOutcomeProblem,
+        RetrieveTestCasesOutcomeProblemContest, RetrieveTestCasesOutcomeProblemTextFiles, Session,
+        SessionMut, Shell, Submit, SubmitError, SubmitOutcome, UnknownLanguageId, WatchSubmissions,
     },
 };
 use anyhow::{anyhow, bail, Context as _};
@@ -47,6 +47,15 @@ use url::Url;
 
 static BASE_URL: Lazy<Url> = lazy_url!("https://atcoder.jp");
 
+/// Returns the URL that the code for `target` is posted to.
+pub fn submit_url(target: &ProblemInContest) -> anyhow::Result<Url> {
+    let contest = match target {
+        ProblemInContest::Index { contest, .. } => contest.to_lowercase(),
+        ProblemInContest::Url { url } => contest_id_from_url(url)?,
+    };
+    Ok(url!("/contests/{}/submit", contest))
+}
+
 pub fn contest_id_from_url(url: &Url) -> anyhow::Result<String> {
     if url.domain() != Some("atcoder.jp") {
         bail!("wrong domain. expected `atcoder.jp`: {}", url);
@@ -99,20 +108,43 @@ impl<S: Shell> Exec<Login<Self, S>> for Atcoder<'_> {
             credentials:
                 AtcoderLoginCredentials {
                     username_and_password,
+                    revel_session,
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
 
         if check_logged_in(&mut sess)? {
-            Ok(LoginOutcome::AlreadyLoggedIn)
-        } else {
-            login(sess, username_and_password)?;
-            Ok(LoginOutcome::Success)
+            return Ok(LoginOutcome::AlreadyLoggedIn);
+        }
+
+        match (login(&mut sess, username_and_password), revel_session) {
+            (Ok(()), _) => {}
+            (Err(err), None) => return Err(err),
+            (Err(_), Some(revel_session)) => {
+                // For accounts that can only sign in with Google or GitHub.
+                while {
+                    let value = revel_session()?;
+                    sess.insert_cookie(
+                        &format!("REVEL_SESSION={}; Path=/; Secure; HttpOnly", value.trim()),
+                        &url!("/"),
+                    )?;
+                    !check_logged_in(&mut sess)?
+                } {}
+            }
         }
+        Ok(LoginOutcome::Success)
     }
 }
 
@@ -128,11 +160,19 @@ impl<S: Shell> Exec<Participate<Self, S>> for Atcoder<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
         let contest = CaseConverted::new(contest);
-        let sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
         participate(sess, username_and_password, &contest, true)
     }
 }
@@ -149,6 +189,8 @@ impl<S: Shell> Exec<RetrieveLanguages<Self, S>> for Atcoder<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
@@ -161,7 +203,13 @@ impl<S: Shell> Exec<RetrieveLanguages<Self, S>> for Atcoder<'_> {
             (CaseConverted::<LowerCase>::new("practice"), None)
         };
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
 
         if !check_logged_in(&mut sess)? {
             login(&mut sess, username_and_password)?;
@@ -203,18 +251,31 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Atcoder<'_> {
             full,
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
+            request_interval,
+            skip_problems,
             shell,
         } = args;
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
+        sess.request_interval = request_interval;
 
-        let mut outcome = retrieve_sample_test_cases(&mut sess, username_and_password, &targets)?;
+        let mut outcome =
+            retrieve_sample_test_cases(&mut sess, username_and_password, &targets, &skip_problems)?;
 
         if let Some(RetrieveFullTestCases {
             credentials:
                 AtcoderRetrieveFullTestCasesCredentials {
                     dropbox_access_token,
                 },
+            count_only,
         }) = full
         {
             static DROPBOX_PATH_PREFIXES: Lazy<HashMap<String, String>> = Lazy::new(|| {
@@ -262,6 +323,14 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Atcoder<'_> {
                     ),
                 };
 
+                if let TestSuite::Batch(test_suite) = &mut problem.test_suite {
+                    test_suite.systemtests = Some(in_file_paths.len());
+                }
+
+                if count_only {
+                    continue;
+                }
+
                 let mut retrieve_files = |file_paths| -> anyhow::Result<_> {
                     retrieve_files(&mut sess, &dropbox_access_token, file_paths)
                 };
@@ -445,7 +514,10 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Atcoder<'_> {
                         (path.clone(), req)
                     })
                     .collect(),
-            )?;
+                None,
+            )?
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
             return Ok(file_paths.iter().map(file_stem).zip_eq(contents).collect());
 
@@ -477,12 +549,20 @@ impl<S: Shell> Exec<RetrieveSubmissionSummaries<Self, S>> for Atcoder<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
         let contest = CaseConverted::<LowerCase>::new(contest);
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
 
         let (mut summaries, num_pages) =
             retrieve_submission_summaries(&mut sess, &contest, 1, username_and_password)?;
@@ -511,12 +591,22 @@ impl<S: Shell> Exec<Submit<Self, S>> for Atcoder<'_> {
             language_id,
             code,
             watch_submission,
+            watch_timeout,
+            warn_if_contest_ended,
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
 
         let (contest, url) = match target {
             ProblemInContest::Index { contest, problem } => {
@@ -544,13 +634,40 @@ impl<S: Shell> Exec<Submit<Self, S>> for Atcoder<'_> {
                 .map(|cs| cs[1].to_owned())
                 .with_context(|| "Could not extract screen name of the problem")?;
 
-        let csrf_token = sess
+        let res = sess
             .get(url)
-            .colorize_status_code(&[200], (), ..)
+            .colorize_status_code(&[200], &[302], ..)
             .send()?
-            .ensure_status(&[200])?
-            .html()?
-            .extract_csrf_token()?;
+            .ensure_status(&[200, 302])?;
+
+        let html = task_page(res)?;
+
+        if warn_if_contest_ended {
+            // Only informational. Some pages may not show the duration.
+            match html.extract_contest_duration() {
+                Ok((_, end)) if end < Utc::now() => {
+                    sess.shell().warn(format!(
+                        "`{}` ended at {}. This submission does not count for the contest",
+                        contest,
+                        end.with_timezone(&Local),
+                    ))?;
+                }
+                Ok(_) => {}
+                Err(err) => log::debug!("{:?}", err),
+            }
+        }
+
+        let names_by_id = html.extract_langs()?;
+        if !names_by_id.contains_key(&language_id) {
+            return Err(UnknownLanguageId {
+                platform: PlatformKind::Atcoder,
+                language_id: language_id.clone(),
+                names_by_id,
+            }
+            .into());
+        }
+
+        let csrf_token = html.extract_csrf_token()?;
 
         let res = sess
             .post(url!("/contests/{}/submit", contest))
@@ -567,29 +684,43 @@ impl<S: Shell> Exec<Submit<Self, S>> for Atcoder<'_> {
         if res.status() == 302 {
             let loc = res.location_url()?;
 
+            if is_login_page(&loc) {
+                return Err(LoginRequired(PlatformKind::Atcoder).into());
+            }
+
             if loc.path().starts_with("/contests/") && loc.path().ends_with("/submissions/me") {
                 let (submission_summaries, _) =
                     retrieve_submission_summaries(&mut sess, &contest, 1, || {
                         bail!("Should be logged in")
                     })?;
 
-                let outcome = SubmitOutcome {
+                let verdict = if watch_submission {
+                    watch_submissions(sess, &contest, &submission_summaries, watch_timeout)?
+                        .swap_remove(0)
+                } else {
+                    None
+                };
+
+                Ok(SubmitOutcome {
                     problem_screen_name: Some(problem_screen_name),
                     submission_url: submission_summaries[0].detail.clone(),
                     submissions_url: url!("/contests/{}/submissions/me", contest),
-                };
-
-                if watch_submission {
-                    watch_submissions(sess, &contest, &submission_summaries)?;
-                }
-
-                Ok(outcome)
+                    verdict,
+                })
             } else {
-                sess.get(loc).colorize_status_code((), (), ..).send()?;
-                bail!("Submission rejected");
+                // The reason is shown on the page we are redirected to.
+                let message = sess
+                    .get(loc)
+                    .colorize_status_code((), (), ..)
+                    .send()?
+                    .html()
+                    .ok()
+                    .and_then(|html| html.extract_alert());
+                Err(SubmitError::classify(PlatformKind::Atcoder, message, code.len()).into())
             }
         } else {
-            bail!("Submission rejected");
+            let message = res.html().ok().and_then(|html| html.extract_alert());
+            Err(SubmitError::classify(PlatformKind::Atcoder, message, code.len()).into())
         }
     }
 }
@@ -606,12 +737,20 @@ impl<S: Shell> Exec<WatchSubmissions<Self, S>> for Atcoder<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             mut shell,
         } = args;
 
         let contest = CaseConverted::<LowerCase>::new(contest);
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), &mut shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            &mut shell,
+        )?;
 
         let (summaries, _) =
             retrieve_submission_summaries(&mut sess, &contest, 1, username_and_password)?;
@@ -621,7 +760,7 @@ impl<S: Shell> Exec<WatchSubmissions<Self, S>> for Atcoder<'_> {
         });
 
         if any_incomplete {
-            watch_submissions(sess, &contest, &summaries)?;
+            watch_submissions(sess, &contest, &summaries, None)?;
         } else {
             let content = AnsiColored::new(|w| print_submissions(w, &summaries))?;
             shell.print_ansi(content.get())?;
@@ -632,6 +771,8 @@ impl<S: Shell> Exec<WatchSubmissions<Self, S>> for Atcoder<'_> {
 
 pub struct AtcoderLoginCredentials<'closures> {
     pub username_and_password: &'closures mut dyn FnMut() -> anyhow::Result<(String, String)>,
+    /// Asked for the value of the `REVEL_SESSION` cookie if `username_and_password` fails.
+    pub revel_session: Option<&'closures mut dyn FnMut() -> anyhow::Result<String>>,
 }
 
 #[derive(Debug)]
@@ -680,6 +821,27 @@ impl AtcoderRetrieveSubmissionSummariesOutcome {
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).expect("should not fail")
     }
+
+    /// Retains only the submissions for the problem with the index (e.g. "a").
+    pub fn retain_problem(&mut self, problem: &str) {
+        self.summaries.retain(|summary| {
+            summary
+                .task
+                .display_name
+                .split(" - ")
+                .next()
+                .is_some_and(|index| index.eq_ignore_ascii_case(problem))
+        });
+    }
+
+    /// Keeps only the `limit` latest submissions.
+    pub fn truncate(&mut self, limit: usize) {
+        self.summaries.truncate(limit);
+    }
+
+    pub fn print_pretty(&self, wtr: impl WriteColor) -> io::Result<()> {
+        print_submissions(wtr, &self.summaries)
+    }
 }
 
 #[derive(Debug)]
@@ -699,6 +861,7 @@ fn retrieve_sample_test_cases(
     mut sess: impl SessionMut,
     mut username_and_password: impl FnMut() -> anyhow::Result<(String, String)>,
     targets: &ProblemsInContest,
+    skip_problems: &BTreeSet<String>,
 ) -> anyhow::Result<RetrieveTestCasesOutcome> {
     let problems = match targets.clone() {
         ProblemsInContest::Indexes { contest, problems } => {
@@ -775,6 +938,11 @@ fn retrieve_sample_test_cases(
     let mut outcome = RetrieveTestCasesOutcome { problems: vec![] };
 
     for (contest, (contest_display_name, mut indexes_and_urls)) in problems {
+        indexes_and_urls.retain(|index, _| !super::is_skipped(skip_problems, index));
+        if indexes_and_urls.is_empty() {
+            continue;
+        }
+
         let test_suites = sess
             .get(url!("/contests/{}/tasks_print", contest))
             .colorize_status_code(&[200], (), ..)
@@ -796,11 +964,12 @@ fn retrieve_sample_test_cases(
             display_name: contest_display_name,
             url: url!("/contests/{}", contest),
             submissions_url: url!("/contests/{}/submissions/me", contest),
+            standings_url: url!("/contests/{}/standings", contest),
         };
 
         for result in test_suites {
             match result {
-                Ok((index, display_name, test_suite)) => {
+                Ok((index, display_name, score, test_suite)) => {
                     if let Some(url) = indexes_and_urls.shift_remove(&*index) {
                         let screen_name = url
                             .path_segments()
@@ -815,9 +984,13 @@ fn retrieve_sample_test_cases(
 
                                 TestSuite::Batch(BatchTestSuite {
                                     timelimit: None,
-                                    r#match: Match::Lines,
+                                    memorylimit: None,
+                                    systemtests: None,
+                                    r#match: Some(Match::Lines),
                                     cases: vec![],
                                     extend: vec![],
+                                    allow_nonzero_exit: false,
+                                    normalizer: None,
                                 })
                             }
                         };
@@ -828,6 +1001,7 @@ fn retrieve_sample_test_cases(
                             index,
                             screen_name: Some(screen_name),
                             display_name,
+                            score,
                             test_suite,
                             text_files: indexmap![],
                         });
@@ -880,14 +1054,49 @@ fn login(
 }
 
 fn check_logged_in(mut sess: impl SessionMut) -> anyhow::Result<bool> {
-    let status = sess
+    let res = sess
         .get(url!("/settings"))
         .colorize_status_code(&[200], &[302], ())
         .send()?
-        .ensure_status(&[200, 302])?
-        .status();
+        .ensure_status(&[200, 302])?;
+
+    if res.status() == 200 {
+        return Ok(true);
+    }
+
+    // `https://atcoder.jp/settings` redirects to `https://atcoder.jp/login?continue=..` when we
+    // are not logged in.
+    let location = res.location_url()?;
+    if !is_login_page(&location) {
+        bail!("Unexpected redirection to {}", location);
+    }
+    Ok(false)
+}
+
+fn is_login_page(url: &Url) -> bool {
+    url.domain() == Some("atcoder.jp") && url.path() == "/login"
+}
 
-    Ok(status == 200)
+/// Reads the response to a task page, or returns [`LoginRequired`] if we were redirected to the
+/// login page or the page has no submission form.
+fn task_page(res: reqwest::blocking::Response) -> anyhow::Result<Html> {
+    if res.status() == 302 {
+        let location = res.location_url()?;
+        if is_login_page(&location) {
+            return Err(LoginRequired(PlatformKind::Atcoder).into());
+        }
+        bail!("Unexpected redirection to {}", location);
+    }
+    let html = res.html()?;
+    // Logged-out users get the page with 200, just without the language `<select>`.
+    if html
+        .select(static_selector!("#select-lang"))
+        .next()
+        .is_none()
+    {
+        return Err(LoginRequired(PlatformKind::Atcoder).into());
+    }
+    Ok(html)
 }
 
 fn participate(
@@ -1061,13 +1270,16 @@ fn print_submissions(mut wtr: impl WriteColor, summaries: &[SubmissionSummary])
     Ok(())
 }
 
+/// Returns the final verdicts, or `None`s for the ones that timed out.
 fn watch_submissions(
     mut sess: impl SessionMut,
     contest: &CaseConverted<LowerCase>,
     summaries: &[SubmissionSummary],
-) -> anyhow::Result<()> {
+    timeout: Option<Duration>,
+) -> anyhow::Result<Vec<Option<String>>> {
     let rt = Runtime::new()?;
     let mut handles = vec![];
+    let mut verdicts = vec![None; summaries.len()];
 
     let mp = MultiProgress::with_draw_target(sess.shell().progress_draw_target());
 
@@ -1088,7 +1300,7 @@ fn watch_submissions(
         .max()
         .unwrap_or(0);
 
-    for summary in summaries {
+    for (i, summary) in summaries.iter().enumerate() {
         let pb = mp.add(ProgressBar::new(0));
 
         pb.set_style(
@@ -1118,7 +1330,9 @@ fn watch_submissions(
 
             let cookie_header = sess.cookie_header(&"https://atcoder.jp".parse().unwrap());
 
-            handles.push(rt.spawn(async move {
+            let pb_on_timeout = pb.clone();
+
+            let watch = async move {
                 let finish_pb = || tokio::task::block_in_place(|| pb.finish_at_current_pos());
 
                 macro_rules! trap(($result:expr $(,)?) => {
@@ -1153,6 +1367,7 @@ fn watch_submissions(
                             .send()
                             .await,
                     );
+                    log::debug!("{} {}", res.status(), res.url());
 
                     let VerdictProgress { interval, result } = trap!(res.json().await);
 
@@ -1212,11 +1427,31 @@ fn watch_submissions(
                         tokio::task::block_in_place(|| {
                             finish(&pb, &verdict, &exec_time, memory);
                         });
-                        break Result::<(), anyhow::Error>::Ok(());
+                        break anyhow::Ok(Some(verdict.to_string()));
                     }
                 }
-            }));
+            };
+
+            handles.push((
+                i,
+                rt.spawn(async move {
+                    let timeout = match timeout {
+                        Some(timeout) => timeout,
+                        None => return watch.await,
+                    };
+                    match tokio::time::timeout(timeout, watch).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            tokio::task::block_in_place(|| {
+                                pb_on_timeout.abandon_with_message("timed out");
+                            });
+                            Ok(None)
+                        }
+                    }
+                }),
+            ));
         } else {
+            verdicts[i] = Some(summary.status.to_string());
             finish(
                 &pb,
                 &summary.status,
@@ -1228,11 +1463,11 @@ fn watch_submissions(
 
     mp.join()?;
 
-    for handle in handles {
-        rt.block_on(handle)??;
+    for (i, handle) in handles {
+        verdicts[i] = rt.block_on(handle)??;
     }
 
-    return Ok(());
+    return Ok(verdicts);
 
     static JUDGING: Lazy<Regex> = lazy_regex!(r"\A\s*([0-9]{1,3})/([0-9]{1,3})\s*(\S*)\s*\z");
 
@@ -1300,8 +1535,12 @@ impl ContestStatus {
     }
 
     fn raise_if_not_begun(&self) -> anyhow::Result<()> {
-        if let ContestStatus::NotBegun(contest, time) = self {
-            bail!("`{}` will begin at {}", contest, time)
+        if let ContestStatus::NotBegun(contest, start) = self {
+            return Err(ContestNotBegun {
+                contest: contest.to_string(),
+                start: *start,
+            }
+            .into());
         }
         Ok(())
     }
@@ -1448,7 +1687,7 @@ impl Serialize for Verdict {
     }
 }
 
-#[ext]
+#[ext(HtmlExt)]
 impl Html {
     fn extract_title(&self) -> anyhow::Result<&str> {
         self.select(static_selector!(":root > head > title"))
@@ -1458,6 +1697,12 @@ impl Html {
             .with_context(|| "Could not find `<title>`")
     }
 
+    fn extract_alert(&self) -> Option<String> {
+        self.select(static_selector!("div.alert-danger"))
+            .map(|r| r.text().collect::<String>())
+            .find(|s| !s.trim().is_empty())
+    }
+
     fn extract_csrf_token(&self) -> anyhow::Result<String> {
         (|| -> _ {
             let token = self
@@ -1518,7 +1763,10 @@ impl Html {
         .with_context(|| "Could not extract task indexes and URLs")
     }
 
-    fn extract_samples(&self) -> Vec<anyhow::Result<(String, String, anyhow::Result<TestSuite>)>> {
+    #[allow(clippy::type_complexity)]
+    fn extract_samples(
+        &self,
+    ) -> Vec<anyhow::Result<(String, String, Option<u64>, anyhow::Result<TestSuite>)>> {
         return self
             .select(static_selector!(
                 "#main-container > div.row div[class=\"col-sm-12\"]",
@@ -1540,13 +1788,35 @@ impl Html {
                     (caps[1].to_owned(), caps[2].to_owned())
                 };
 
+                // Older contests do not show the scores.
+                let score = div
+                    .select(static_selector!(":scope > div[id=\"task-statement\"] p"))
+                    .find_map(|p| parse_score(&p.collect_text()));
+
                 let test_suite = (|| {
-                    let timelimit = div
+                    // "Time Limit: 2 sec / Memory Limit: 1024 MB"
+                    // Without the time limit, the default of `judge` is used.
+                    let paragraphs = div
                         .select(static_selector!(":scope > p"))
                         .flat_map(|r| r.text())
-                        .flat_map(parse_timelimit)
+                        .collect::<Vec<_>>();
+                    let constraints = paragraphs
+                        .iter()
+                        .copied()
+                        .filter_map(parse_limits)
                         .exactly_one()
-                        .map_err(|_| "Could not extract the timelimit")?;
+                        .ok()
+                        .or_else(|| {
+                            let timelimit = paragraphs
+                                .iter()
+                                .copied()
+                                .filter_map(parse_timelimit)
+                                .exactly_one()
+                                .ok()?;
+                            Some((Some(timelimit), None))
+                        });
+                    let timelimit = constraints.as_ref().and_then(|(t, _)| *t);
+                    let memorylimit = constraints.and_then(|(_, m)| m);
 
                     // In `tasks_print`, there are multiple `#task-statement`s.
                     let samples = div
@@ -1556,12 +1826,14 @@ impl Html {
                         .and_then(extract_samples)
                         .ok_or("Could not extract the sample cases")?;
 
-                    Ok::<_, &str>(if timelimit == Duration::new(0, 0) {
+                    Ok::<_, &str>(if timelimit == Some(Duration::new(0, 0)) {
                         TestSuite::Unsubmittable
                     } else if let Samples::Batch(r#match, samples) = samples {
                         TestSuite::Batch(BatchTestSuite {
-                            timelimit: Some(timelimit),
-                            r#match,
+                            timelimit,
+                            memorylimit,
+                            systemtests: None,
+                            r#match: Some(r#match),
                             cases: samples
                                 .into_iter()
                                 .enumerate()
@@ -1569,24 +1841,41 @@ impl Html {
                                     name: Some(format!("sample{}", i + 1)),
                                     r#in: input.into(),
                                     out: Some(output.into()),
+                                    alternative_outs: vec![],
                                     timelimit: None,
                                     r#match: None,
                                 })
                                 .collect(),
                             extend: vec![],
+                            allow_nonzero_exit: false,
+                            normalizer: None,
                         })
                     } else {
-                        TestSuite::Interactive(InteractiveTestSuite {
-                            timelimit: Some(timelimit),
-                        })
+                        TestSuite::Interactive(InteractiveTestSuite { timelimit })
                     })
                 })()
                 .map_err(|e| anyhow!("{}: {}", index, e));
 
-                Ok((index, display_name, test_suite))
+                Ok((index, display_name, score, test_suite))
             })
             .collect();
 
+        fn parse_score(text: &str) -> Option<u64> {
+            let caps =
+                static_regex!(r"\A\s*(配点|Score)\s*[:：]\s*([0-9]{1,9})\s*(点|points?)\s*\z")
+                    .captures(text)?;
+            caps[2].parse().ok()
+        }
+
+        fn parse_limits(text: &str) -> Option<(Option<Duration>, Option<String>)> {
+            let caps = static_regex!(
+                r"\A\s*(Time Limit|実行時間制限)\s*[:：]\s*([^/]*)/\s*(Memory Limit|メモリ制限)\s*[:：]\s*(.*)\z"
+            )
+            .captures(text)?;
+            let memorylimit = Some(caps[4].trim().to_owned()).filter(|s| !s.is_empty());
+            Some((parse_timelimit(&caps[2]), memorylimit))
+        }
+
         fn parse_timelimit(text: &str) -> Option<Duration> {
             let caps =
                 static_regex!(r"\A\D*([0-9]{1,9})(\.[0-9]{1,3})?\s*(m)?sec.*\z").captures(text)?;
@@ -1730,6 +2019,26 @@ impl Html {
                     } else if let Some(caps) = re_output.captures(&text) {
                         next = Some((false, parse_zenkaku(&caps[1]).ok()?));
                     }
+
+                    // Take the first `pre` following the header in the same section if any, so
+                    // that we do not rely on the order of the other `pre`s.
+                    if let Some((is_input, n)) = next {
+                        let pre = elem_ref
+                            .next_siblings()
+                            .flat_map(ElementRef::wrap)
+                            .take_while(|e| e.value().name() != "h3")
+                            .find(|e| e.value().name() == "pre");
+
+                        if let Some(pre) = pre {
+                            let text = pre.collect_text();
+                            if is_input {
+                                inputs.insert(n, text);
+                            } else {
+                                outputs.insert(n, text);
+                            }
+                            next = None;
+                        }
+                    }
                 } else if ["pre", "section"].contains(&elem_ref.value().name()) {
                     if let Some((is_input, n)) = next {
                         let text = elem_ref.collect_text();
@@ -1970,3 +2279,161 @@ impl Html {
         .with_context(|| "Could not parse the submissions page")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::web::{LoginRequired, PlatformKind, SubmitError};
+    use reqwest::ResponseBuilderExt as _;
+    use std::time::Duration;
+    use url::Url;
+
+    #[test]
+    fn is_login_page() {
+        let url = |s| Url::parse(s).unwrap();
+
+        assert!(super::is_login_page(&url(
+            "https://atcoder.jp/login?continue=https%3A%2F%2Fatcoder.jp%2Fcontests%2Fpractice%2Fsubmit",
+        )));
+        assert!(!super::is_login_page(&url(
+            "https://atcoder.jp/contests/practice/submissions/me",
+        )));
+    }
+
+    #[test]
+    fn extract_alert() {
+        use super::HtmlExt as _;
+
+        let html = scraper::Html::parse_document(
+            r#"<!DOCTYPE html>
+<html>
+<body>
+<div class="alert alert-danger alert-dismissible col-sm-12 fade in" role="alert">
+  <button type="button" class="close" data-dismiss="alert" aria-label="Close"><span aria-hidden="true">&times;</span></button>
+  ソースコードが長すぎます。
+</div>
+</body>
+</html>
+"#,
+        );
+
+        let message = html.extract_alert();
+        assert_eq!(
+            Some(SubmitError::CodeTooLong {
+                platform: PlatformKind::Atcoder,
+                len: 600_000,
+            }),
+            message.map(|m| SubmitError::classify(PlatformKind::Atcoder, Some(m), 600_000)),
+        );
+    }
+
+    #[test]
+    fn extract_samples() {
+        use super::HtmlExt as _;
+
+        let html = scraper::Html::parse_document(
+            r#"<!DOCTYPE html>
+<html>
+<body>
+<div id="main-container">
+<div class="row">
+<div class="col-sm-12">
+<span class="h2">A - Sum</span>
+<p>Time Limit: 1.5 sec / Memory Limit: 1024 MB</p>
+<div id="task-statement">
+<span class="lang">
+<span class="lang-ja">
+<p>配点 : <var>100</var> 点</p>
+<div class="part">
+<section>
+<h3>入力</h3>
+<pre><var>N</var>
+<var>A_1</var> <var>\ldots</var> <var>A_N</var>
+</pre>
+</section>
+</div>
+<div class="part">
+<section>
+<h3>入力例 1</h3>
+<div class="div-btn-copy"><span class="btn-copy">Copy</span></div>
+<pre id="pre-sample0">3
+<span>1 2 3</span>
+</pre>
+</section>
+</div>
+<div class="part">
+<section>
+<h3>出力例 1</h3>
+<div class="div-btn-copy"><span class="btn-copy">Copy</span></div>
+<pre id="pre-sample1">6
+</pre>
+<p>例えば、次のように計算できます。</p>
+<pre>1 + 2 + 3 = 6
+</pre>
+</section>
+</div>
+</span>
+</span>
+</div>
+</div>
+</div>
+</div>
+</body>
+</html>
+"#,
+        );
+
+        let samples = html.extract_samples();
+        assert_eq!(samples.len(), 1);
+        let (index, display_name, score, test_suite) = samples.into_iter().next().unwrap().unwrap();
+        assert_eq!(index, "A");
+        assert_eq!(display_name, "Sum");
+        assert_eq!(score, Some(100));
+        match test_suite.unwrap() {
+            crate::testsuite::TestSuite::Batch(test_suite) => {
+                assert_eq!(test_suite.timelimit, Some(Duration::from_millis(1500)));
+                assert_eq!(test_suite.memorylimit.as_deref(), Some("1024 MB"));
+                assert_eq!(test_suite.cases.len(), 1);
+                assert_eq!(&*test_suite.cases[0].r#in, "3\n1 2 3\n");
+                assert_eq!(test_suite.cases[0].out.as_deref(), Some("6\n"));
+            }
+            _ => panic!("expected a batch test suite"),
+        }
+    }
+
+    #[test]
+    fn task_page() {
+        let res = |status, location: Option<&str>, body: &'static str| {
+            let mut res = http::Response::builder()
+                .status(status)
+                .url(Url::parse("https://atcoder.jp/contests/practice/tasks/practice_1").unwrap());
+            if let Some(location) = location {
+                res = res.header("Location", location);
+            }
+            reqwest::blocking::Response::from(res.body(body).unwrap())
+        };
+
+        let err = super::task_page(res(
+            302,
+            Some("/login?continue=https%3A%2F%2Fatcoder.jp%2Fcontests%2Fpractice%2Ftasks%2Fpractice_1"),
+            "",
+        ))
+        .unwrap_err();
+        assert_eq!(
+            Some(&LoginRequired(PlatformKind::Atcoder)),
+            err.downcast_ref::<LoginRequired>(),
+        );
+
+        let err = super::task_page(res(302, Some("/contests/practice"), "")).unwrap_err();
+        assert!(err.downcast_ref::<LoginRequired>().is_none());
+
+        let err = super::task_page(res(200, None, "<html></html>")).unwrap_err();
+        assert_eq!(
+            Some(&LoginRequired(PlatformKind::Atcoder)),
+            err.downcast_ref::<LoginRequired>(),
+        );
+
+        let html =
+            r#"<select id="select-lang"><option value="4006">Python (3.8.2)</option></select>"#;
+        assert!(super::task_page(res(200, None, html)).is_ok());
+    }
+}
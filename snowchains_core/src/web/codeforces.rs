@@ -2,10 +2,11 @@ use crate::{
     testsuite::{BatchTestSuite, Match, PartialBatchTestCase, TestSuite},
     web::{
         codeforces::api::SessionMutExt as _, CookieStorage, Exec, Login, LoginOutcome, Participate,
-        ParticipateOutcome, Platform, ProblemInContest, ProblemsInContest, ResponseExt as _,
-        RetrieveLanguages, RetrieveLanguagesOutcome, RetrieveTestCases, RetrieveTestCasesOutcome,
-        RetrieveTestCasesOutcomeProblem, RetrieveTestCasesOutcomeProblemContest, Session,
-        SessionMut, Shell, Submit, SubmitOutcome,
+        ParticipateOutcome, Platform, PlatformKind, ProblemInContest, ProblemsInContest,
+        ResponseExt as _, RetrieveLanguages, RetrieveLanguagesOutcome, RetrieveTestCases,
+        RetrieveTestCasesOutcome, RetrieveTestCasesOutcomeProblem,
+        RetrieveTestCasesOutcomeProblemContest, Session, SessionMut, Shell, Submit, SubmitError,
+        SubmitOutcome,
     },
 };
 use anyhow::{bail, Context as _};
@@ -14,10 +15,12 @@ use indexmap::{indexmap, IndexMap};
 use itertools::Itertools as _;
 use maplit::btreemap;
 use once_cell::sync::Lazy;
+use reqwest::header;
 use scraper::{ElementRef, Html, Node, Selector};
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     convert::Infallible,
+    fmt,
     marker::PhantomData,
     time::Duration,
 };
@@ -25,7 +28,21 @@ use url::Url;
 
 pub fn contest_id_from_url(url: &Url) -> anyhow::Result<u64> {
     let (contest_id, _) = parse_problem_url(url)?;
-    Ok(contest_id)
+    Ok(contest_id.id())
+}
+
+/// Returns the URL that the code for `target` is posted to.
+pub fn submit_url(target: &ProblemInContest) -> anyhow::Result<Url> {
+    let contest_id = match target {
+        ProblemInContest::Index { contest, .. } => parse_contest_id(contest)?,
+        ProblemInContest::Url { url } => parse_problem_url(url)?.0,
+    };
+    Ok(contest_id.url("/submit"))
+}
+
+/// Returns the URL of `rest` (e.g. `/standings`) in `contest`.
+pub fn contest_url(contest: &str, rest: &str) -> anyhow::Result<Url> {
+    Ok(parse_contest_id(contest)?.url(rest))
 }
 
 static BASE_URL: Lazy<Url> = lazy_url!("https://codeforces.com");
@@ -73,10 +90,18 @@ impl<S: Shell> Exec<Login<Self, S>> for Codeforces<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
-        let sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
         let (outcome, _) = login(sess, username_and_password)?;
         Ok(outcome)
     }
@@ -94,12 +119,20 @@ impl<S: Shell> Exec<Participate<Self, S>> for Codeforces<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
         let contest = parse_contest_id(&contest)?;
-        let sess = Session::new(timeout, Some(cookie_storage), shell)?;
-        let (outcome, _, _) = participate(sess, username_and_password, contest)?;
+        let sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
+        let (outcome, _, _) = participate(sess, username_and_password, &contest)?;
         Ok(outcome)
     }
 }
@@ -116,17 +149,25 @@ impl<S: Shell> Exec<RetrieveLanguages<Self, S>> for Codeforces<'_> {
                 },
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
         let contest = parse_contest_id(&contest)?;
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
 
-        participate(&mut sess, username_and_password, contest)?;
+        participate(&mut sess, username_and_password, &contest)?;
 
         let names_by_id = sess
-            .get(url!("/contest/{}/submit", contest))
+            .get(contest.url("/submit"))
             .colorize_status_code(&[200], (), ..)
             .send()?
             .ensure_status(&[200])?
@@ -150,6 +191,10 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Codeforces<'_> {
             full: _,
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
+            request_interval,
+            skip_problems,
             shell,
         } = args;
 
@@ -167,11 +212,19 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Codeforces<'_> {
             }
         };
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
+        sess.request_interval = request_interval;
         let mut outcome = RetrieveTestCasesOutcome { problems: vec![] };
 
         for (contest, problems) in targets {
-            let (_, contest_name, _) = participate(&mut sess, &mut username_and_password, contest)?;
+            let (_, contest_name, _) =
+                participate(&mut sess, &mut username_and_password, &contest)?;
 
             let mut problem_indices = problems.map(|ps| {
                 ps.iter()
@@ -180,48 +233,68 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Codeforces<'_> {
                     .collect::<BTreeSet<_>>()
             });
 
+            let contest_url = contest.url("");
+
             let contest = &RetrieveTestCasesOutcomeProblemContest {
                 id: contest.to_string(),
                 display_name: contest_name,
-                url: url!("/contest/{}", contest),
-                submissions_url: url!("/contest/{}/my", contest),
+                url: contest_url.clone(),
+                submissions_url: contest.url("/my"),
+                standings_url: contest.url("/standings"),
             };
 
-            outcome.problems.extend(
-                sess.get(url!("/contest/{}", contest.id))
-                    .colorize_status_code(&[200], (), ..)
-                    .send()?
-                    .ensure_status(&[200])?
-                    .html()?
-                    .extract_problems()?
-                    .into_iter()
-                    .map(|(index, display_name, url)| {
-                        if let Some(problem_indices) = &mut problem_indices {
-                            if !problem_indices.remove(&index) {
-                                return Ok(None);
-                            }
+            let problems = sess
+                .get(contest_url)
+                .colorize_status_code(&[200], (), ..)
+                .send()?
+                .ensure_status(&[200])?
+                .html()?
+                .extract_problems()?
+                .into_iter()
+                .filter(|(index, _, _)| match &mut problem_indices {
+                    Some(problem_indices) => problem_indices.remove(index),
+                    None => true,
+                })
+                .filter(|(index, _, _)| !super::is_skipped(&skip_problems, index))
+                .collect::<Vec<_>>();
+
+            let htmls = super::download_with_progress(
+                sess.shell().progress_draw_target(),
+                problems
+                    .iter()
+                    .map(|(index, _, url)| {
+                        let mut req = sess.async_client().get(url.clone());
+                        let cookie_header = sess.cookie_header(url);
+                        if !cookie_header.is_empty() {
+                            req = req.header(header::COOKIE, cookie_header);
                         }
-
-                        let test_suite = sess
-                            .get(url.clone())
-                            .colorize_status_code(&[200], (), ..)
-                            .send()?
-                            .html()?
-                            .extract_test_cases()?;
-
-                        Ok(Some(RetrieveTestCasesOutcomeProblem {
-                            contest: Some(contest.clone()),
-                            index,
-                            url,
-                            screen_name: None,
-                            display_name,
-                            test_suite,
-                            text_files: indexmap!(),
-                        }))
+                        (index.clone(), req)
                     })
-                    .flat_map(Result::transpose)
-                    .collect::<anyhow::Result<Vec<_>>>()?,
-            );
+                    .collect(),
+                sess.request_interval,
+            )?;
+
+            for ((index, display_name, url), html) in problems.into_iter().zip_eq(htmls) {
+                let test_suite =
+                    match html.and_then(|h| Html::parse_document(&h).extract_test_cases()) {
+                        Ok(test_suite) => test_suite,
+                        Err(err) => {
+                            sess.shell().warn(format!("{}: {:?}", index, err))?;
+                            continue;
+                        }
+                    };
+
+                outcome.problems.push(RetrieveTestCasesOutcomeProblem {
+                    contest: Some(contest.clone()),
+                    index,
+                    url,
+                    screen_name: None,
+                    display_name,
+                    score: None,
+                    test_suite,
+                    text_files: indexmap!(),
+                });
+            }
 
             if let Some(problem_indices) = problem_indices {
                 if !problem_indices.is_empty() {
@@ -249,8 +322,12 @@ impl<S: Shell> Exec<Submit<Self, S>> for Codeforces<'_> {
             language_id,
             code,
             watch_submission,
+            watch_timeout: _,
+            warn_if_contest_ended: _,
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             mut shell,
         } = args;
 
@@ -263,11 +340,18 @@ impl<S: Shell> Exec<Submit<Self, S>> for Codeforces<'_> {
             ProblemInContest::Url { url } => parse_problem_url(&url)?,
         };
 
-        let mut sess = Session::new(timeout, Some(cookie_storage), shell)?;
+        let mut sess = Session::new(
+            timeout,
+            user_agent.as_deref(),
+            &headers,
+            Some(cookie_storage),
+            shell,
+        )?;
 
-        let (_, _, handle) = participate(&mut sess, username_and_password, contest_id)?;
+        let (_, _, handle) = participate(&mut sess, username_and_password, &contest_id)?;
 
-        let (_, problems, _) = sess.api_contest_standings(contest_id, None, None, "", "", false)?;
+        let (_, problems, _) =
+            sess.api_contest_standings(contest_id.id(), None, None, "", "", false)?;
 
         let problem = problems
             .into_iter()
@@ -276,7 +360,7 @@ impl<S: Shell> Exec<Submit<Self, S>> for Codeforces<'_> {
                 format!("No such problem index: {:?}", problem_index.to_uppercase())
             })?;
 
-        let url = url!("/contest/{}/submit", contest_id);
+        let url = contest_id.url("/submit");
 
         let mut payload = sess
             .get(url.clone())
@@ -286,10 +370,11 @@ impl<S: Shell> Exec<Submit<Self, S>> for Codeforces<'_> {
             .html()?
             .extract_hidden_values(static_selector!("form.submit-form"))?;
 
-        payload.insert("contestId".to_owned(), contest_id.to_string());
+        payload.insert("contestId".to_owned(), contest_id.id().to_string());
         payload.insert("submittedProblemIndex".to_owned(), problem.index);
         payload.insert("tabSize".to_owned(), "4".to_owned());
         payload.insert("programTypeId".to_owned(), language_id);
+        let code_len = code.len();
         payload.insert("source".to_owned(), code);
 
         let res = sess
@@ -300,23 +385,31 @@ impl<S: Shell> Exec<Submit<Self, S>> for Codeforces<'_> {
             .ensure_status(&[200, 302])?;
 
         if res.status() == 200 {
-            bail!("Submission rejected");
+            let message = res.html().ok().and_then(|html| html.extract_error());
+            Err(SubmitError::classify(PlatformKind::Codeforces, message, code_len).into())
         } else {
             let submissions_url = res.location_url()?;
 
-            let submissions =
-                sess.api_contest_status(&api_key, &api_secret, contest_id, &handle, 1, Some(1))?;
+            let submissions = sess.api_contest_status(
+                &api_key,
+                &api_secret,
+                contest_id.id(),
+                &handle,
+                1,
+                Some(1),
+            )?;
 
             let submission = submissions
                 .get(0)
                 .with_context(|| "Recieved no submission")?;
 
-            let submission_url = url!("/contest/{}/submission/{}", contest_id, submission.id);
+            let submission_url = contest_id.url(&format!("/submission/{}", submission.id));
 
             Ok(SubmitOutcome {
                 problem_screen_name: None,
                 submission_url,
                 submissions_url,
+                verdict: None,
             })
         }
     }
@@ -354,26 +447,89 @@ pub struct CodeforcesSubmitCredentials<'closures> {
     pub api_secret: String,
 }
 
-fn parse_contest_id(s: &str) -> anyhow::Result<u64> {
-    s.parse().with_context(|| {
+/// A regular round, a gym contest, or a contest in a group.
+#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
+enum ContestId {
+    Contest(u64),
+    Gym(u64),
+    Group { group: String, contest: u64 },
+}
+
+impl ContestId {
+    fn id(&self) -> u64 {
+        match *self {
+            Self::Contest(id) | Self::Gym(id) | Self::Group { contest: id, .. } => id,
+        }
+    }
+
+    fn url(&self, rest: &str) -> Url {
+        let path = match self {
+            Self::Contest(id) => format!("/contest/{}", id),
+            Self::Gym(id) => format!("/gym/{}", id),
+            Self::Group { group, contest } => format!("/group/{}/contest/{}", group, contest),
+        };
+        BASE_URL.join(&(path + rest)).unwrap()
+    }
+}
+
+impl fmt::Display for ContestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contest(id) | Self::Gym(id) => write!(f, "{}", id),
+            Self::Group { group, contest } => write!(f, "group-{}-{}", group, contest),
+        }
+    }
+}
+
+/// Parses `1234`, `gym102000` (or `102000`), or `group-<group code>-<contest ID>`.
+fn parse_contest_id(s: &str) -> anyhow::Result<ContestId> {
+    let parse = || -> Option<_> {
+        if let Some(caps) = static_regex!(r"\A(gym)?([0-9]{1,9})\z").captures(s) {
+            let id = caps[2].parse().ok()?;
+            Some(if caps.get(1).is_some() || is_gym(id) {
+                ContestId::Gym(id)
+            } else {
+                ContestId::Contest(id)
+            })
+        } else {
+            let caps = static_regex!(r"\Agroup-([a-zA-Z0-9]+)-([0-9]{1,9})\z").captures(s)?;
+            Some(ContestId::Group {
+                group: caps[1].to_owned(),
+                contest: caps[2].parse().ok()?,
+            })
+        }
+    };
+
+    parse().with_context(|| {
         format!(
-            "A contest ID for Codeforces must be unsigned integer: {:?}",
+            "A contest ID for Codeforces must be an unsigned integer, `gym<ID>`, or \
+             `group-<group code>-<ID>`: {:?}",
             s,
         )
     })
 }
 
-fn parse_problem_url(url: &Url) -> anyhow::Result<(u64, String)> {
+fn parse_problem_url(url: &Url) -> anyhow::Result<(ContestId, String)> {
     if url.domain() != Some("codeforces.com") {
         bail!("wrong domain. expected `codeforces.com`: {}", url);
     }
 
-    let caps = static_regex!(r"\A/contest/([0-9]{1,5})/problem/(.*)\z")
-        .captures(url.path())
-        .with_context(|| format!("`{}` is not a URL for problem in Codeforces", url))?;
-
-    let contest_id = caps[1].parse().expect("from `[0-9]{1,5}`");
-    let problem_index = caps[2].to_owned();
+    let caps =
+        static_regex!(r"\A/(contest|gym|group/([a-zA-Z0-9]+)/contest)/([0-9]{1,9})/problem/(.*)\z")
+            .captures(url.path())
+            .with_context(|| format!("`{}` is not a URL for problem in Codeforces", url))?;
+
+    let id = caps[3].parse().expect("from `[0-9]{1,9}`");
+    let contest_id = match (&caps[1], caps.get(2)) {
+        ("contest", _) => ContestId::Contest(id),
+        ("gym", _) => ContestId::Gym(id),
+        (_, Some(group)) => ContestId::Group {
+            group: group.as_str().to_owned(),
+            contest: id,
+        },
+        _ => unreachable!(),
+    };
+    let problem_index = caps[4].to_owned();
     Ok((contest_id, problem_index))
 }
 
@@ -428,17 +584,32 @@ fn login(
 fn participate(
     mut sess: impl SessionMut,
     username_and_password: impl FnMut() -> anyhow::Result<(String, String)>,
-    contest_id: u64,
+    contest: &ContestId,
 ) -> anyhow::Result<(ParticipateOutcome, String, String)> {
     let (_, handle) = login(&mut sess, username_and_password)?;
 
+    if let ContestId::Group { .. } = contest {
+        // Group contests are not listed in `contest.list`.
+        ensure_accessible(&mut sess, contest)?;
+        return Ok((
+            ParticipateOutcome::AlreadyParticipated,
+            contest.to_string(),
+            handle,
+        ));
+    }
+
+    let contest_id = contest.id();
+
     let api::Contest { name, phase, .. } = sess
-        .api_contest_list(is_gym(contest_id))?
+        .api_contest_list(matches!(contest, ContestId::Gym(_)))?
         .into_iter()
         .find(|&api::Contest { id, .. }| id == contest_id)
-        .with_context(|| format!("No such contest: `{}`", contest_id))?;
+        .with_context(|| format!("No such contest: `{}`", contest))?;
 
     if phase == api::ContestPhase::Finished {
+        if let ContestId::Gym(_) = contest {
+            ensure_accessible(&mut sess, contest)?;
+        }
         Ok((ParticipateOutcome::ContestIsFinished, name, handle))
     } else {
         let url = url!("/contestRegistration/{}", contest_id);
@@ -460,6 +631,27 @@ fn participate(
     }
 }
 
+/// Gyms and groups redirect to other pages unless the user has joined them.
+fn ensure_accessible(mut sess: impl SessionMut, contest: &ContestId) -> anyhow::Result<()> {
+    let url = contest.url("");
+
+    let status = sess
+        .get(url.clone())
+        .colorize_status_code(&[200], &[302], ..)
+        .send()?
+        .ensure_status(&[200, 302])?
+        .status();
+
+    if status == 302 {
+        bail!(
+            "You do not have access to `{}`. Register for it in browser: {}",
+            contest,
+            url,
+        );
+    }
+    Ok(())
+}
+
 fn is_gym(contest_id: u64) -> bool {
     contest_id >= 100_000
 }
@@ -491,6 +683,14 @@ impl Html {
         Ok(values)
     }
 
+    /// Extracts the error shown under the form (e.g. "You have submitted exactly the same code
+    /// before").
+    fn extract_error(&self) -> Option<String> {
+        self.select(static_selector!("span.error"))
+            .map(|r| r.text().collect::<String>())
+            .find(|s| !s.trim().is_empty())
+    }
+
     fn extract_available_langs(&self) -> anyhow::Result<IndexMap<String, String>> {
         self.select(static_selector!(
             "form.submit-form > table > tbody > tr > td"
@@ -609,6 +809,7 @@ impl Html {
                 name: Some(format!("example{}", i + 1)),
                 r#in: r#in.into(),
                 out: Some(out.into()),
+                alternative_outs: vec![],
                 r#match: None,
                 timelimit: None,
             })
@@ -616,9 +817,13 @@ impl Html {
 
         return Ok(TestSuite::Batch(BatchTestSuite {
             timelimit: Some(timelimit),
-            r#match,
+            memorylimit: None,
+            systemtests: None,
+            r#match: Some(r#match),
             cases,
             extend: vec![],
+            allow_nonzero_exit: false,
+            normalizer: None,
         }));
 
         #[ext]
@@ -913,3 +1118,48 @@ mod api {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ContestId;
+    use url::Url;
+
+    #[test]
+    fn parse_contest_id() {
+        let parse = |s| super::parse_contest_id(s).unwrap();
+
+        assert_eq!(ContestId::Contest(1234), parse("1234"));
+        assert_eq!(ContestId::Gym(102_000), parse("102000"));
+        assert_eq!(ContestId::Gym(102_000), parse("gym102000"));
+        assert_eq!(
+            ContestId::Group {
+                group: "MWSDmqGsZm".to_owned(),
+                contest: 219_158,
+            },
+            parse("group-MWSDmqGsZm-219158"),
+        );
+        assert!(super::parse_contest_id("abc").is_err());
+    }
+
+    #[test]
+    fn parse_problem_url() {
+        let parse = |s| super::parse_problem_url(&Url::parse(s).unwrap()).unwrap();
+
+        let (contest, index) = parse("https://codeforces.com/gym/102000/problem/A");
+        assert_eq!(ContestId::Gym(102_000), contest);
+        assert_eq!("A", index);
+        assert_eq!(
+            "https://codeforces.com/gym/102000/submit",
+            contest.url("/submit").as_str(),
+        );
+
+        let (contest, index) =
+            parse("https://codeforces.com/group/MWSDmqGsZm/contest/219158/problem/B");
+        assert_eq!("group-MWSDmqGsZm-219158", contest.to_string());
+        assert_eq!("B", index);
+        assert_eq!(
+            "https://codeforces.com/group/MWSDmqGsZm/contest/219158",
+            contest.url("").as_str(),
+        );
+    }
+}
@@ -53,14 +53,16 @@ macro_rules! url {
     };
 }
 
+mod aoj;
 mod atcoder;
 mod codeforces;
 mod yukicoder;
 
 pub use crate::web::{
+    aoj::{problem_url as aoj_problem_url, Aoj, AojRetrieveTestCasesTargets},
     atcoder::{
-        contest_id_from_url as atcoder_contest_id, Atcoder, AtcoderLoginCredentials,
-        AtcoderParticipateCredentials, AtcoderParticipateTarget,
+        contest_id_from_url as atcoder_contest_id, submit_url as atcoder_submit_url, Atcoder,
+        AtcoderLoginCredentials, AtcoderParticipateCredentials, AtcoderParticipateTarget,
         AtcoderRetrieveFullTestCasesCredentials, AtcoderRetrieveLanguagesCredentials,
         AtcoderRetrieveLanguagesTarget, AtcoderRetrieveSampleTestCasesCredentials,
         AtcoderRetrieveSubmissionSummariesCredentials, AtcoderRetrieveSubmissionSummariesOutcome,
@@ -68,7 +70,8 @@ pub use crate::web::{
         AtcoderWatchSubmissionsCredentials, AtcoderWatchSubmissionsTarget,
     },
     codeforces::{
-        contest_id_from_url as codeforces_contest_id, Codeforces, CodeforcesLoginCredentials,
+        contest_id_from_url as codeforces_contest_id, contest_url as codeforces_contest_url,
+        submit_url as codeforces_submit_url, Codeforces, CodeforcesLoginCredentials,
         CodeforcesParticipateCredentials, CodeforcesParticipateTarget,
         CodeforcesRetrieveLanguagesCredentials, CodeforcesRetrieveLanguagesTarget,
         CodeforcesRetrieveSampleTestCasesCredentials, CodeforcesSubmitCredentials,
@@ -81,6 +84,7 @@ pub use crate::web::{
 
 use crate::testsuite::TestSuite;
 use anyhow::{anyhow, bail, Context as _};
+use chrono::{DateTime, Local};
 use cookie_store::CookieStore;
 use derivative::Derivative;
 use derive_more::{Display, From};
@@ -112,8 +116,8 @@ use std::{
     ops::{Deref, RangeFull, RangeInclusive},
     path::{Path, PathBuf},
     str,
-    sync::Mutex,
-    time::Duration,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 use strum::EnumString;
 use termcolor::Ansi;
@@ -159,17 +163,21 @@ pub enum PlatformKind {
     Atcoder,
     Codeforces,
     Yukicoder,
+    Aoj,
+    /// Any other judge. Nothing is done over the network. The test suites are written by hand.
+    Other,
 }
 
 impl PlatformKind {
     pub const KEBAB_CASE_VARIANTS: &'static [&'static str] =
-        &["atcoder", "codeforces", "yukicoder"];
+        &["atcoder", "codeforces", "yukicoder", "aoj", "other"];
 
     pub fn from_url(url: &Url) -> anyhow::Result<Self> {
         match url.domain() {
             Some("atcoder.jp") => Ok(Self::Atcoder),
             Some("codeforces.com") => Ok(Self::Codeforces),
             Some("yukicoder.me") => Ok(Self::Yukicoder),
+            Some("onlinejudge.u-aizu.ac.jp") | Some("judge.u-aizu.ac.jp") => Ok(Self::Aoj),
             Some(_) => bail!("unknown domain: {}", url),
             None => bail!("missing domain: {}", url),
         }
@@ -180,6 +188,8 @@ impl PlatformKind {
             Self::Atcoder => "atcoder",
             Self::Codeforces => "codeforces",
             Self::Yukicoder => "yukicoder",
+            Self::Aoj => "aoj",
+            Self::Other => "other",
         }
     }
 
@@ -188,10 +198,138 @@ impl PlatformKind {
             Self::Atcoder => "Atcoder",
             Self::Codeforces => "Codeforces",
             Self::Yukicoder => "Yukicoder",
+            Self::Aoj => "Aoj",
+            Self::Other => "Other",
         }
     }
 }
 
+/// Returned when a service redirects us to its login page in the middle of an action.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[display(
+    fmt = "Not logged in to {}. The session may have expired, so log in again",
+    _0
+)]
+pub struct LoginRequired(pub PlatformKind);
+
+impl std::error::Error for LoginRequired {}
+
+/// Returned when a contest has not begun yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContestNotBegun {
+    pub contest: String,
+    pub start: DateTime<Local>,
+}
+
+impl ContestNotBegun {
+    /// Time left until the contest begins, rounded up to seconds.
+    pub fn remaining(&self) -> Duration {
+        let millis = (self.start - Local::now()).num_milliseconds().max(0) as u64;
+        Duration::from_secs(millis.div_ceil(1000))
+    }
+}
+
+impl fmt::Display for ContestNotBegun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` will begin at {} (in {})",
+            self.contest,
+            self.start,
+            humantime::format_duration(self.remaining()),
+        )
+    }
+}
+
+impl std::error::Error for ContestNotBegun {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLanguageId {
+    pub platform: PlatformKind,
+    pub language_id: String,
+    pub names_by_id: IndexMap<String, String>,
+}
+
+impl fmt::Display for UnknownLanguageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Language ID `{}` is not available on {}. Available ones:",
+            self.language_id, self.platform,
+        )?;
+        for (id, name) in &self.names_by_id {
+            write!(f, "\n  {}: {}", id, name)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownLanguageId {}
+
+/// Returned when a service refuses a submission.
+///
+/// Wrong language IDs and expired sessions are reported as [`UnknownLanguageId`] and
+/// [`LoginRequired`].
+#[derive(Display, Debug, Clone, PartialEq, Eq)]
+pub enum SubmitError {
+    #[display(
+        fmt = "{} rejected the code ({} bytes) as too long. Shorten it (e.g. remove unused library \
+               code) and submit again",
+        platform,
+        len
+    )]
+    CodeTooLong { platform: PlatformKind, len: usize },
+    #[display(
+        fmt = "{} refused to compile the code: {}. Check that `languageId` matches the code",
+        platform,
+        message
+    )]
+    CompileError {
+        platform: PlatformKind,
+        message: String,
+    },
+    #[display(
+        fmt = "You have already submitted exactly the same code to {}. Change the code to submit \
+               it again",
+        platform
+    )]
+    Duplicate { platform: PlatformKind },
+    #[display(
+        fmt = "{} rejected the submission: {}. Open the submit page in a browser to see why",
+        platform,
+        "message.as_deref().unwrap_or(\"(no message)\")"
+    )]
+    Other {
+        platform: PlatformKind,
+        message: Option<String>,
+    },
+}
+
+impl SubmitError {
+    /// Chooses the variant from the error message on the page or in the response.
+    fn classify(platform: PlatformKind, message: Option<String>, len: usize) -> Self {
+        let message = message
+            .map(|s| s.split_whitespace().join(" "))
+            .filter(|s| !s.is_empty());
+        let lowercase = message.as_deref().unwrap_or("").to_lowercase();
+
+        if lowercase.contains("same code") {
+            Self::Duplicate { platform }
+        } else if lowercase.contains("too long") || lowercase.contains("長すぎ") {
+            Self::CodeTooLong { platform, len }
+        } else if lowercase.contains("compil") || lowercase.contains("コンパイル") {
+            Self::CompileError {
+                platform,
+                message: message.unwrap_or_default(),
+            }
+        } else {
+            Self::Other { platform, message }
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
 pub trait Exec<A>: Platform {
     type Output;
     fn exec(args: A) -> anyhow::Result<Self::Output>;
@@ -201,6 +339,8 @@ pub struct Login<P: Platform, S: Shell> {
     pub credentials: P::LoginCredentials,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
     pub shell: S,
 }
 
@@ -221,6 +361,8 @@ pub struct Participate<P: Platform, S: Shell> {
     pub credentials: P::ParticipateCredentials,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
     pub shell: S,
 }
 
@@ -251,6 +393,8 @@ pub struct RetrieveLanguages<P: Platform, S: Shell> {
     pub credentials: P::RetrieveLanguagesCredentials,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
     pub shell: S,
 }
 
@@ -294,11 +438,19 @@ pub struct RetrieveTestCases<P: Platform, S: Shell> {
     pub full: Option<RetrieveFullTestCases<P>>,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
+    /// Minimum interval between two consecutive requests
+    pub request_interval: Option<Duration>,
+    /// Indexes of the problems not to retrieve, compared case-insensitively
+    pub skip_problems: BTreeSet<String>,
     pub shell: S,
 }
 
 pub struct RetrieveFullTestCases<P: Platform> {
     pub credentials: P::RetrieveFullTestCasesCredentials,
+    /// Only records the number of the test cases in `systemtests` without downloading them.
+    pub count_only: bool,
 }
 
 #[non_exhaustive]
@@ -315,6 +467,7 @@ pub struct RetrieveTestCasesOutcomeProblem {
     pub url: Url,
     pub screen_name: Option<String>,
     pub display_name: String,
+    pub score: Option<u64>,
     pub test_suite: TestSuite,
     pub text_files: IndexMap<String, RetrieveTestCasesOutcomeProblemTextFiles>,
 }
@@ -326,6 +479,7 @@ pub struct RetrieveTestCasesOutcomeProblemContest {
     pub display_name: String,
     pub url: Url,
     pub submissions_url: Url,
+    pub standings_url: Url,
 }
 
 pub struct RetrieveSubmissionSummaries<P: Platform, S: Shell> {
@@ -333,6 +487,8 @@ pub struct RetrieveSubmissionSummaries<P: Platform, S: Shell> {
     pub credentials: P::RetrieveSubmissionSummariesCredentials,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
     pub shell: S,
 }
 
@@ -348,6 +504,8 @@ pub struct WatchSubmissions<P: Platform, S: Shell> {
     pub credentials: P::WatchSubmissionsCredentials,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
     pub shell: S,
 }
 
@@ -372,8 +530,14 @@ pub struct Submit<P: Platform, S: Shell> {
     pub language_id: String,
     pub code: String,
     pub watch_submission: bool,
+    /// Gives up watching the submission after this duration.
+    pub watch_timeout: Option<Duration>,
+    /// Warns before submitting if the contest has already ended. Only for AtCoder.
+    pub warn_if_contest_ended: bool,
     pub cookie_storage: P::CookieStorage,
     pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+    pub headers: IndexMap<String, String>,
     pub shell: S,
 }
 
@@ -383,6 +547,8 @@ pub struct SubmitOutcome {
     pub problem_screen_name: Option<String>,
     pub submission_url: Url,
     pub submissions_url: Url,
+    /// Final verdict (e.g. `"AC"`) if the submission was watched until it was judged.
+    pub verdict: Option<String>,
 }
 
 impl SubmitOutcome {
@@ -616,18 +782,38 @@ struct Session<S> {
     async_client: reqwest::Client,
     blocking_client: reqwest::blocking::Client,
     cookie_storage: Option<CookieStorage>,
+    request_interval: Option<Duration>,
+    last_request: Option<Instant>,
     shell: S,
 }
 
 impl<S: Shell> Session<S> {
     fn new(
         timeout: Option<Duration>,
+        user_agent: Option<&str>,
+        headers: &IndexMap<String, String>,
         cookie_storage: Option<CookieStorage>,
         shell: S,
     ) -> anyhow::Result<Self> {
+        let user_agent = user_agent.unwrap_or(USER_AGENT);
+
+        let headers = headers
+            .iter()
+            .map(|(name, value)| {
+                let name = header::HeaderName::from_bytes(name.as_ref())
+                    .with_context(|| format!("Invalid header name: {:?}", name))?;
+                let value = header::HeaderValue::from_str(value)
+                    .with_context(|| format!("Invalid value for `{}`: {:?}", name, value))?;
+                Ok((name, value))
+            })
+            .collect::<anyhow::Result<header::HeaderMap>>()?;
+
         macro_rules! client(($builder:path) => {{
             let client = $builder()
-                .user_agent(USER_AGENT)
+                .user_agent(user_agent)
+                .default_headers(headers.clone())
+                .gzip(true)
+                .deflate(true)
                 .cookie_store(false)
                 .redirect(Policy::none());
 
@@ -645,10 +831,16 @@ impl<S: Shell> Session<S> {
             async_client,
             blocking_client,
             cookie_storage,
+            request_interval: None,
+            last_request: None,
             shell,
         });
 
-        static USER_AGENT: &str = "snowchains <https://github.com/qryxip/snowchains>";
+        static USER_AGENT: &str = concat!(
+            "snowchains_core/",
+            env!("CARGO_PKG_VERSION"),
+            " <https://github.com/qryxip/snowchains>",
+        );
 
         trait DummyMethod: Sized {
             fn cookie_store(self, _: bool) -> Self {
@@ -659,6 +851,20 @@ impl<S: Shell> Session<S> {
         impl DummyMethod for reqwest::ClientBuilder {}
         impl DummyMethod for reqwest::blocking::ClientBuilder {}
     }
+
+    /// Stores a cookie pasted by the user as if `url` had responded with `Set-Cookie: <set_cookie>`.
+    fn insert_cookie(&mut self, set_cookie: &str, url: &Url) -> anyhow::Result<()> {
+        if let Some(CookieStorage {
+            cookie_store,
+            on_update,
+        }) = &mut self.cookie_storage
+        {
+            let cookie = cookie_store::Cookie::parse(set_cookie, url)?.into_owned();
+            cookie_store.insert(cookie, url)?;
+            (on_update)(cookie_store)?;
+        }
+        Ok(())
+    }
 }
 
 trait SessionMut: Sized {
@@ -804,15 +1010,24 @@ impl<S: Shell> SessionRequestBuilder<'_, S> {
             todo!();
         }
 
+        if let (Some(interval), Some(last_request)) = (sess.request_interval, sess.last_request) {
+            if let Some(rest) = interval.checked_sub(last_request.elapsed()) {
+                std::thread::sleep(rest);
+            }
+        }
+        sess.last_request = Some(Instant::now());
+
         let cookie_header = sess.cookie_header(&url);
         if !cookie_header.is_empty() {
             inner = inner.header(header::COOKIE, cookie_header);
         }
 
         let req = inner.build()?;
+        log::debug!("{} {}", req.method(), req.url());
         sess.shell.on_request(&req)?;
 
         let res = sess.blocking_client.execute(req)?;
+        log::debug!("{} {}", res.status(), res.url());
         sess.shell
             .on_response(&res, colorize_status_code(res.status()))?;
 
@@ -825,6 +1040,7 @@ impl<S: Shell> SessionRequestBuilder<'_, S> {
                 let set_cookie = str::from_utf8(set_cookie.as_bytes())
                     .map_err(|e| anyhow!("{}: {}", e, &url))?;
                 let cookie = cookie_store::Cookie::parse(set_cookie, &url)?.into_owned();
+                log::trace!("Set-Cookie: {} (from {})", cookie.name(), url);
                 cookie_store.insert(cookie, &url)?;
             }
 
@@ -971,16 +1187,35 @@ where
     }
 }
 
+fn is_skipped(skip_problems: &BTreeSet<String>, index: &str) -> bool {
+    skip_problems.iter().any(|p| p.eq_ignore_ascii_case(index))
+}
+
+/// How many times [`download_with_progress`] retries a download interrupted mid-transfer.
+///
+/// If the server advertises `Accept-Ranges: bytes`, the retry resumes from where the transfer stopped.
+const DOWNLOAD_RETRIES: usize = 3;
+
+/// How many downloads [`download_with_progress`] runs at once.
+const PARALLEL_DOWNLOADS: usize = 4;
+
+/// Downloads the targets in parallel, returning the contents (or the error) for each target.
+///
+/// `request_interval` is applied across all the downloads, not per download.
 // https://github.com/rust-lang/rust-clippy/issues/5991
 #[allow(clippy::needless_collect)]
 fn download_with_progress(
     draw_target: ProgressDrawTarget,
     dl_targets: Vec<(String, reqwest::RequestBuilder)>,
-) -> anyhow::Result<Vec<String>> {
+    request_interval: Option<Duration>,
+) -> anyhow::Result<Vec<anyhow::Result<String>>> {
     let rt = Runtime::new()?;
     let mp = MultiProgress::with_draw_target(draw_target);
     let name_width = dl_targets.iter().map(|(s, _)| s.width()).max().unwrap_or(0);
 
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(PARALLEL_DOWNLOADS));
+    let last_request = Arc::new(tokio::sync::Mutex::new(None::<tokio::time::Instant>));
+
     let handles = dl_targets
         .into_iter()
         .map(|(name, req)| {
@@ -988,48 +1223,116 @@ fn download_with_progress(
             pb.set_style(progress_style("{prefix:.bold} Waiting..."));
             pb.set_prefix(&align_left(&name, name_width));
 
+            let semaphore = semaphore.clone();
+            let last_request = last_request.clone();
+
             rt.spawn(async move {
-                let res = req.send().await?;
+                let _permit = semaphore.acquire_owned().await?;
 
-                tokio::task::block_in_place(|| {
-                    if let Some(content_len) = res.content_length() {
-                        pb.set_length(content_len);
+                let mut content = vec![];
+                let mut content_len = None;
+                let mut accepts_ranges = false;
+
+                for retries_left in (0..=DOWNLOAD_RETRIES).rev() {
+                    let mut req = req
+                        .try_clone()
+                        .with_context(|| "Could not clone the request")?;
+
+                    if let Some(request_interval) = request_interval {
+                        let mut last_request = last_request.lock().await;
+                        if let Some(last_request) = *last_request {
+                            tokio::time::sleep_until(last_request + request_interval).await;
+                        }
+                        *last_request = Some(tokio::time::Instant::now());
                     }
 
-                    pb.set_style(progress_style(
-                        "{prefix:.bold} {bytes:9} {bytes_per_sec:11} {elapsed_precise} {bar} \
-                         {percent}%",
-                    ));
-                });
-
-                let mut content = vec![];
-                let mut stream = res.bytes_stream();
+                    let resuming = !content.is_empty() && accepts_ranges;
+                    if resuming {
+                        req = req.header(header::RANGE, format!("bytes={}-", content.len()));
+                    }
 
-                while let Some(chunk) = stream.next().await {
-                    let chunk = chunk?;
+                    let res = req.send().await?;
+                    log::debug!("{} {}", res.status(), res.url());
 
-                    content.extend_from_slice(chunk.as_ref());
+                    let resumed = resuming && res.status() == StatusCode::PARTIAL_CONTENT;
+                    if resuming && !resumed {
+                        log::debug!("The server ignored `Range`. Downloading from the start");
+                    }
+                    if !resumed {
+                        content.clear();
+                        content_len = res.content_length();
+                        // A body decoded from gzip has no `Content-Length`, and its ranges do not
+                        // correspond to the bytes we have received.
+                        accepts_ranges = content_len.is_some()
+                            && res
+                                .headers()
+                                .get(header::ACCEPT_RANGES)
+                                .is_some_and(|v| v == "bytes");
+                    }
 
                     tokio::task::block_in_place(|| {
-                        pb.inc(chunk.len().try_into().unwrap_or(u64::MAX));
+                        if let Some(content_len) = content_len {
+                            pb.set_length(content_len);
+                        }
+                        pb.set_position(content.len().try_into().unwrap_or(u64::MAX));
+
+                        pb.set_style(progress_style(
+                            "{prefix:.bold} {bytes:9} {bytes_per_sec:11} {elapsed_precise} {bar} \
+                             {percent}%",
+                        ));
                     });
+
+                    let mut stream = res.bytes_stream();
+                    let mut interrupted = None;
+
+                    while let Some(chunk) = stream.next().await {
+                        let chunk = match chunk {
+                            Ok(chunk) => chunk,
+                            Err(err) => {
+                                interrupted = Some(err);
+                                break;
+                            }
+                        };
+
+                        content.extend_from_slice(chunk.as_ref());
+
+                        tokio::task::block_in_place(|| {
+                            pb.inc(chunk.len().try_into().unwrap_or(u64::MAX));
+                        });
+                    }
+
+                    match interrupted {
+                        None => break,
+                        Some(err) if retries_left == 0 => return Err(err.into()),
+                        Some(err) => log::debug!("Interrupted: {}. Retrying", err),
+                    }
                 }
 
                 tokio::task::block_in_place(|| pb.finish_at_current_pos());
 
-                reqwest::Result::Ok(content)
+                if let Some(content_len) = content_len {
+                    if content.len() as u64 != content_len {
+                        bail!(
+                            "Expected {} bytes (`Content-Length`), got {} bytes",
+                            content_len,
+                            content.len(),
+                        );
+                    }
+                }
+
+                Ok::<_, anyhow::Error>(content)
             })
         })
         .collect::<Vec<_>>();
 
     mp.join()?;
 
-    return handles
+    return Ok(handles
         .into_iter()
         .map(|handle| {
             String::from_utf8(rt.block_on(handle)??).with_context(|| "Invalid UTF-8 content")
         })
-        .collect();
+        .collect());
 
     fn progress_style(template: &str) -> ProgressStyle {
         ProgressStyle::default_bar().template(template)
@@ -1040,3 +1343,69 @@ fn download_with_progress(
         s.chars().chain(itertools::repeat_n(' ', spaces)).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ResponseExt as _, Session, SessionMut as _, Shell};
+    use flate2::{write::GzEncoder, Compression};
+    use indexmap::IndexMap;
+    use std::{
+        io::{self, Read as _, Write as _},
+        net::TcpListener,
+        thread,
+    };
+
+    #[test]
+    fn recv_gzipped_html() -> anyhow::Result<()> {
+        let mut body = GzEncoder::new(vec![], Compression::default());
+        body.write_all(br#"<html><body><p id="greeting">Hello</p></body></html>"#)?;
+        let body = body.finish()?;
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/", listener.local_addr()?).parse()?;
+
+        let server = thread::spawn(move || -> io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+
+            let mut head = vec![];
+            let mut buf = [0; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                head.extend_from_slice(&buf[..n]);
+            }
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/html\r\n\
+                 Content-Encoding: gzip\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\
+                 \r\n",
+                body.len(),
+            )?;
+            stream.write_all(&body)?;
+            Ok(String::from_utf8_lossy(&head).into_owned())
+        });
+
+        let mut sess = Session::new(None, None, &IndexMap::new(), None, NoShell)?;
+        let html = sess.get(url).send()?.html()?;
+
+        let head = server.join().unwrap()?;
+        assert!(head.to_ascii_lowercase().contains("accept-encoding: gzip"));
+        assert_eq!(
+            "Hello",
+            html.select(static_selector!("#greeting"))
+                .flat_map(|p| p.text())
+                .collect::<String>(),
+        );
+        return Ok(());
+
+        struct NoShell;
+
+        impl Shell for NoShell {}
+    }
+}
@@ -4,11 +4,11 @@ use crate::{
         TestSuite,
     },
     web::{
-        yukicoder::api::SessionMutExt as _, Exec, Platform, ResponseExt as _,
+        yukicoder::api::SessionMutExt as _, Exec, Platform, PlatformKind, ResponseExt as _,
         RetrieveFullTestCases, RetrieveLanguages, RetrieveLanguagesOutcome, RetrieveTestCases,
         RetrieveTestCasesOutcome, RetrieveTestCasesOutcomeProblem,
         RetrieveTestCasesOutcomeProblemContest, RetrieveTestCasesOutcomeProblemTextFiles, Session,
-        SessionMut, Shell, Submit, SubmitOutcome,
+        SessionMut, Shell, Submit, SubmitError, SubmitOutcome,
     },
 };
 use anyhow::{bail, Context as _};
@@ -62,10 +62,12 @@ impl<S: Shell> Exec<RetrieveLanguages<Self, S>> for Yukicoder {
             credentials: (),
             cookie_storage: (),
             timeout,
+            user_agent,
+            headers,
             shell,
         } = args;
 
-        let names_by_id = Session::new(timeout, None, shell)?
+        let names_by_id = Session::new(timeout, user_agent.as_deref(), &headers, None, shell)?
             .get_available_language()?
             .into_iter()
             .map(|api::Language { id, name, ver }| (id, format!("{} ({})", name, ver)))
@@ -85,15 +87,21 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Yukicoder {
             full,
             cookie_storage: (),
             timeout,
+            user_agent,
+            headers,
+            request_interval,
+            skip_problems,
             shell,
         } = args;
 
-        let mut sess = Session::new(timeout, None, shell)?;
+        let mut sess = Session::new(timeout, user_agent.as_deref(), &headers, None, shell)?;
+        sess.request_interval = request_interval;
 
-        let mut outcome = retrieve_samples(&mut sess, targets)?;
+        let mut outcome = retrieve_samples(&mut sess, targets, &skip_problems)?;
 
         if let Some(RetrieveFullTestCases {
             credentials: YukicoderRetrieveFullTestCasesCredentials { api_key },
+            count_only,
         }) = full
         {
             for outcome_problem in &mut outcome.problems {
@@ -104,8 +112,35 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Yukicoder {
                     .parse()
                     .expect("should be integer");
 
-                let in_file_names =
-                    sess.get_test_case_files_by_problem_id(&api_key, problem_id, api::Which::In)?;
+                // The test cases of a problem are only accessible after solving it. Keep the
+                // samples for the others.
+                let in_file_names = match sess.get_test_case_files_by_problem_id(
+                    &api_key,
+                    problem_id,
+                    api::Which::In,
+                )? {
+                    Some(in_file_names) => in_file_names,
+                    None => {
+                        sess.shell.warn(format!(
+                            "Could not access the test cases of the problem ID {}. Saving only \
+                             the samples (solve the problem to unlock them)",
+                            problem_id,
+                        ))?;
+                        continue;
+                    }
+                };
+
+                if in_file_names.is_empty() {
+                    bail!("No test case files found for the problem ID {}", problem_id);
+                }
+
+                if let TestSuite::Batch(test_suite) = &mut outcome_problem.test_suite {
+                    test_suite.systemtests = Some(in_file_names.len());
+                }
+
+                if count_only {
+                    continue;
+                }
 
                 let in_contents = super::download_with_progress(
                     sess.shell.progress_draw_target(),
@@ -121,10 +156,19 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Yukicoder {
                             Ok((format!("in/{}", file_name), req))
                         })
                         .collect::<Result<_, url::ParseError>>()?,
-                )?;
+                    sess.request_interval,
+                )?
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?;
 
-                let out_file_names =
-                    sess.get_test_case_files_by_problem_id(&api_key, problem_id, api::Which::Out)?;
+                let out_file_names = sess
+                    .get_test_case_files_by_problem_id(&api_key, problem_id, api::Which::Out)?
+                    .with_context(|| {
+                        format!(
+                            "Could not access `out` files of the problem ID {}",
+                            problem_id
+                        )
+                    })?;
 
                 let out_contents = super::download_with_progress(
                     sess.shell.progress_draw_target(),
@@ -140,7 +184,10 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Yukicoder {
                             Ok((format!("out/{}", file_name), req))
                         })
                         .collect::<Result<_, url::ParseError>>()?,
-                )?;
+                    sess.request_interval,
+                )?
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?;
 
                 for (name, r#in) in in_file_names.into_iter().zip_eq(in_contents) {
                     outcome_problem.text_files.insert(
@@ -152,6 +199,16 @@ impl<S: Shell> Exec<RetrieveTestCases<Self, S>> for Yukicoder {
                 for (name, out) in out_file_names.into_iter().zip_eq(out_contents) {
                     if let Some(text_files) = outcome_problem.text_files.get_mut(&name) {
                         text_files.out = Some(out);
+                    } else {
+                        sess.shell
+                            .warn(format!("`out/{}` has no corresponding input", name))?;
+                    }
+                }
+
+                for (name, text_files) in &outcome_problem.text_files {
+                    if text_files.out.is_none() {
+                        sess.shell
+                            .warn(format!("`in/{}` has no corresponding output", name))?;
                     }
                 }
             }
@@ -171,8 +228,12 @@ impl<S: Shell> Exec<Submit<Self, S>> for Yukicoder {
             language_id,
             code,
             watch_submission,
+            watch_timeout: _,
+            warn_if_contest_ended: _,
             cookie_storage: (),
             timeout,
+            user_agent,
+            headers,
             mut shell,
         } = args;
 
@@ -180,7 +241,7 @@ impl<S: Shell> Exec<Submit<Self, S>> for Yukicoder {
             shell.warn("`watch_submissions` in yukicoder is not yet supported")?;
         }
 
-        let mut sess = Session::new(timeout, None, shell)?;
+        let mut sess = Session::new(timeout, user_agent.as_deref(), &headers, None, shell)?;
 
         let problem_id = match target.parse()? {
             Either::Left(url) => match parse_problem_url(&url)? {
@@ -222,9 +283,14 @@ impl<S: Shell> Exec<Submit<Self, S>> for Yukicoder {
                 problem_screen_name: Some(problem_id.to_string()),
                 submission_url: url!("/submissions/{}", submission_id),
                 submissions_url: url!("/problems/{}/submissions?my_submission=enabled", problem_id),
+                verdict: None,
             }),
             Err((status_code, message)) => {
-                bail!("Submission rejected: ({}, {:?})", status_code, message);
+                let message = format!("{} ({})", message, status_code);
+                Err(
+                    SubmitError::classify(PlatformKind::Yukicoder, Some(message), code.len())
+                        .into(),
+                )
             }
         }
     }
@@ -249,6 +315,14 @@ pub enum YukicoderSubmitTarget {
 }
 
 impl YukicoderSubmitTarget {
+    /// Returns the URL of the problem, or of the contest if the problem is given by its index.
+    pub fn url(&self) -> Url {
+        match self {
+            Self::Url(url) => url.clone(),
+            Self::Contest(contest_id, _) => url!("/contests/{}", contest_id),
+        }
+    }
+
     pub fn from_problem_no(problem_no: &str) -> Self {
         Self::Url(
             format!(
@@ -312,9 +386,31 @@ fn parse_problem_url(url: &Url) -> anyhow::Result<Either<u64, u64>> {
     }
 }
 
+/// Parses a time limit such as "1ケース 2.000秒", "1ケース 5秒", or "1ケース 500ミリ秒".
+fn parse_timelimit(text: &str) -> Option<Duration> {
+    let caps = static_regex!(r"([0-9]{1,5})(\.([0-9]{1,9}))?\s*(ミリ)?秒").captures(text)?;
+
+    let int = caps[1].parse::<u64>().ok()?;
+    let frac = caps.get(3).map_or("", |m| m.as_str());
+
+    if caps.get(4).is_some() {
+        return Some(Duration::from_millis(int));
+    }
+
+    let nanos = format!("{:0<9}", frac).parse::<u32>().ok()?;
+    Some(Duration::new(int, nanos))
+}
+
+/// Parses a memory limit such as "メモリ制限 : 512 MB".
+fn parse_memorylimit(text: &str) -> Option<String> {
+    let caps = static_regex!(r"メモリ制限\s*:\s*([0-9]+(\.[0-9]+)?\s*[KMG]i?B)").captures(text)?;
+    Some(caps[1].to_owned())
+}
+
 fn retrieve_samples(
     mut sess: impl SessionMut,
     targets: YukicoderRetrieveTestCasesTargets,
+    skip_problems: &BTreeSet<String>,
 ) -> anyhow::Result<RetrieveTestCasesOutcome> {
     let mut outcome = RetrieveTestCasesOutcome { problems: vec![] };
 
@@ -323,6 +419,10 @@ fn retrieve_samples(
             for problem_no in &problem_nos {
                 let problem_no = parse_problem_no(problem_no)?;
 
+                if super::is_skipped(skip_problems, &problem_no.to_string()) {
+                    continue;
+                }
+
                 let (url, test_suite) = retrieve_samples(&mut sess, problem_no)?;
                 let api::Problem {
                     problem_id, title, ..
@@ -334,6 +434,7 @@ fn retrieve_samples(
                     url,
                     screen_name: Some(problem_id.to_string()),
                     display_name: title.clone(),
+                    score: None,
                     test_suite,
                     text_files: indexmap!(),
                 });
@@ -375,6 +476,7 @@ fn retrieve_samples(
                 display_name: name,
                 url: url!("/contests/{}", contest_id),
                 submissions_url: url!("/contests/{}/submissions?my_submission=enabled", contest_id),
+                standings_url: url!("/contests/{}/table", contest_id),
             };
 
             let mut not_found = problem_indexes;
@@ -388,6 +490,10 @@ fn retrieve_samples(
                     }
                 }
 
+                if super::is_skipped(skip_problems, &index.to_string()) {
+                    continue;
+                }
+
                 let api::Problem { no, title, .. } = sess.get_problem_by_problem_id(problem_id)?;
                 let (url, test_suite) = retrieve_samples(&mut sess, no)?;
 
@@ -397,6 +503,7 @@ fn retrieve_samples(
                     url,
                     screen_name: Some(problem_id.to_string()),
                     display_name: title,
+                    score: None,
                     test_suite,
                     text_files: indexmap!(),
                 });
@@ -427,6 +534,7 @@ fn retrieve_samples(
                     url,
                     screen_name: Some(problem_id.to_string()),
                     display_name: title.clone(),
+                    score: None,
                     test_suite,
                     text_files: indexmap!(),
                 });
@@ -454,23 +562,18 @@ fn retrieve_samples(
     }
 }
 
-#[ext]
+#[ext(HtmlExt)]
 impl Html {
     fn extract_samples(&self) -> anyhow::Result<TestSuite> {
-        let (timelimit, kind) = self
+        let texts = self
             .select(static_selector!("#content > div"))
             .flat_map(|r| r.text())
-            .find_map(|text| {
-                let timelimit = {
-                    let caps = static_regex!(r"([0-9]{1,3})\.([0-9]{3})秒").captures(text)?;
-
-                    let secs = caps[1].parse::<u64>().unwrap();
-                    let millis = caps[2].parse::<u64>().unwrap();
-
-                    Duration::from_millis(1000 * secs + millis)
-                };
+            .collect::<Vec<_>>();
 
-                let kind = if text.contains("標準ジャッジ問題") {
+        let kind = texts
+            .iter()
+            .find_map(|text| {
+                Some(if text.contains("標準ジャッジ問題") {
                     Kind::Regular
                 } else if text.contains("スペシャルジャッジ問題") {
                     Kind::Special
@@ -492,12 +595,15 @@ impl Html {
                     }
                 } else {
                     return None;
-                };
-
-                Some((timelimit, kind))
+                })
             })
             .with_context(|| "Could not parse the page")?;
 
+        // Usually "1ケース 2.000秒", but some problems (e.g. No.188 and No.192) are written
+        // differently.
+        let timelimit = texts.iter().find_map(|text| parse_timelimit(text));
+        let memorylimit = texts.iter().find_map(|text| parse_memorylimit(text));
+
         let test_suite = match kind {
             Kind::Regular | Kind::Special | Kind::Floating { .. } => {
                 let r#match = if let Kind::Floating {
@@ -514,10 +620,14 @@ impl Html {
                 };
 
                 let mut test_suite = BatchTestSuite {
-                    timelimit: Some(timelimit),
-                    r#match,
+                    timelimit,
+                    memorylimit,
+                    systemtests: None,
+                    r#match: Some(r#match),
                     cases: vec![],
                     extend: vec![],
+                    allow_nonzero_exit: false,
+                    normalizer: None,
                 };
 
                 for (i, paragraph) in self
@@ -539,6 +649,7 @@ impl Html {
                                 }
                                 _ => None,
                             },
+                            alternative_outs: vec![],
                             timelimit: None,
                             r#match: None,
                         });
@@ -549,9 +660,7 @@ impl Html {
 
                 TestSuite::Batch(test_suite)
             }
-            Kind::Reactive => TestSuite::Interactive(InteractiveTestSuite {
-                timelimit: Some(timelimit),
-            }),
+            Kind::Reactive => TestSuite::Interactive(InteractiveTestSuite { timelimit }),
         };
 
         return Ok(test_suite);
@@ -605,21 +714,21 @@ mod api {
             token: &str,
             problem_id: u64,
             which: Which,
-        ) -> anyhow::Result<Vec<String>> {
+        ) -> anyhow::Result<Option<Vec<String>>> {
             let url = BASE_URL.join(&format!("problems/{}/file/{}", problem_id, which))?;
 
             let res = self
                 .get(url)
                 .bearer_auth(token)
-                .colorize_status_code(&[200], (), ..)
+                .colorize_status_code(&[200], &[403, 404], ..)
                 .send()?
-                .ensure_status(&[200, 404])?;
+                .ensure_status(&[200, 403, 404])?;
 
             if res.status() == 200 {
-                res.json().map_err(Into::into)
+                res.json().map(Some).map_err(Into::into)
             } else {
-                let res = res.json::<serde_json::Value>()?;
-                bail!("{}", serde_json::to_string_pretty(&res).unwrap());
+                log::debug!("{}", res.text()?);
+                Ok(None)
             }
         }
 
@@ -832,3 +941,74 @@ mod api {
         pub(super) problem_id_list: Vec<u64>,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::testsuite::TestSuite;
+    use std::time::Duration;
+
+    #[test]
+    fn extract_samples() -> anyhow::Result<()> {
+        use super::HtmlExt as _;
+
+        let extract = |header: &str| -> anyhow::Result<_> {
+            let html = scraper::Html::parse_document(&format!(
+                r#"<!DOCTYPE html>
+<html>
+<body>
+<div id="content">
+  <div>{}</div>
+  <div class="block">
+    <div class="sample">
+      <div class="paragraph"><pre>1 2</pre><pre>3</pre></div>
+    </div>
+  </div>
+</div>
+</body>
+</html>"#,
+                header,
+            ));
+            match html.extract_samples()? {
+                TestSuite::Batch(suite) => Ok((suite.timelimit, suite.memorylimit, suite.cases)),
+                _ => unreachable!(),
+            }
+        };
+
+        let (timelimit, memorylimit, cases) =
+            extract("実行時間制限 : 1ケース 2.000秒 / メモリ制限 : 512 MB / 標準ジャッジ問題")?;
+        assert_eq!(Some(Duration::from_secs(2)), timelimit);
+        assert_eq!(Some("512 MB"), memorylimit.as_deref());
+        assert_eq!(1, cases.len());
+
+        // No.188
+        let (timelimit, memorylimit, _) =
+            extract("実行時間制限 : 1ケース 1秒 / メモリ制限 : 256 MB / 標準ジャッジ問題")?;
+        assert_eq!(Some(Duration::from_secs(1)), timelimit);
+        assert_eq!(Some("256 MB"), memorylimit.as_deref());
+
+        // No.192
+        let (timelimit, memorylimit, _) = extract(
+            "実行時間制限 : 1ケース 10.5秒 / メモリ制限 : 1.5 GiB / スペシャルジャッジ問題",
+        )?;
+        assert_eq!(Some(Duration::from_millis(10_500)), timelimit);
+        assert_eq!(Some("1.5 GiB"), memorylimit.as_deref());
+
+        let (timelimit, memorylimit, _) = extract("標準ジャッジ問題")?;
+        assert_eq!(None, timelimit);
+        assert_eq!(None, memorylimit);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_timelimit() {
+        assert_eq!(
+            Some(Duration::from_millis(500)),
+            super::parse_timelimit("1ケース 500ミリ秒"),
+        );
+        assert_eq!(
+            Some(Duration::from_millis(2_250)),
+            super::parse_timelimit("1ケース 2.25 秒"),
+        );
+        assert_eq!(None, super::parse_timelimit("1ケース"));
+    }
+}
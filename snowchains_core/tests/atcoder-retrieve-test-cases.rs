@@ -6,6 +6,7 @@ use snowchains_core::web::{
     RetrieveTestCases, StatusCodeColor,
 };
 use std::{
+    collections::BTreeSet,
     fmt,
     io::{self, Read as _},
     time::Duration,
@@ -136,6 +137,10 @@ fn test(
             on_update: Box::new(|_| Ok(())),
         },
         timeout: TIMEOUT,
+        user_agent: None,
+        headers: Default::default(),
+        request_interval: None,
+        skip_problems: BTreeSet::new(),
         shell: Shell(&mut messages),
     })?;
 
@@ -8,6 +8,7 @@ use snowchains_core::web::{
     RetrieveTestCases, StatusCodeColor,
 };
 use std::{
+    collections::BTreeSet,
     fmt,
     io::{self, Read as _},
     time::Duration,
@@ -97,6 +98,10 @@ fn test(
             on_update: Box::new(|_| Ok(())),
         },
         timeout: TIMEOUT,
+        user_agent: None,
+        headers: Default::default(),
+        request_interval: None,
+        skip_problems: BTreeSet::new(),
         shell: Shell(&mut messages),
     })?;
 
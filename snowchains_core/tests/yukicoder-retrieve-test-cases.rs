@@ -5,6 +5,7 @@ use snowchains_core::web::{
     RetrieveTestCases, StatusCodeColor, Yukicoder, YukicoderRetrieveTestCasesTargets,
 };
 use std::{
+    collections::BTreeSet,
     fmt,
     io::{self, Read as _},
     time::Duration,
@@ -83,6 +84,10 @@ fn test(target: Target) -> anyhow::Result<()> {
         full: None,
         cookie_storage: (),
         timeout: TIMEOUT,
+        user_agent: None,
+        headers: Default::default(),
+        request_interval: None,
+        skip_problems: BTreeSet::new(),
         shell: Shell(&mut messages),
     })?;
 
@@ -0,0 +1,78 @@
+use snowchains_core::{color_spec, web::PlatformKind};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::{Color, WriteColor};
+
+#[derive(StructOpt, Debug)]
+pub struct OptConfigCheck {
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    #[structopt(short, long, value_name("STRING"))]
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptConfigCheck,
+    ctx: crate::Context<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptConfigCheck {
+        config,
+        color: _,
+        service,
+        contest,
+        problem,
+    } = opt;
+
+    let crate::Context {
+        cwd,
+        shell: crate::shell::Shell { mut stderr, .. },
+    } = ctx;
+
+    let (path, language_names) = crate::config::check(
+        &cwd,
+        config.as_deref(),
+        service,
+        contest.as_deref(),
+        problem.as_deref(),
+    )?;
+
+    for name in language_names {
+        stderr.set_color(color_spec!(Bold))?;
+        write!(stderr, "languages.{}:", name)?;
+        stderr.reset()?;
+        write!(stderr, " ")?;
+        stderr.set_color(color_spec!(Fg(Color::Green)))?;
+        writeln!(stderr, "OK")?;
+        stderr.reset()?;
+    }
+
+    writeln!(stderr, "`{}` is valid", path)?;
+    stderr.flush()?;
+
+    Ok(())
+}
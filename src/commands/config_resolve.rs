@@ -0,0 +1,150 @@
+use itertools::Itertools as _;
+use snowchains_core::web::PlatformKind;
+use std::{io::Write, path::PathBuf};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+
+#[derive(StructOpt, Debug)]
+pub struct OptConfigResolve {
+    /// Resolves for `Release` mode
+    #[structopt(long)]
+    pub release: bool,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Language name
+    #[structopt(short, long, value_name("STRING"))]
+    pub language: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptConfigResolve,
+    ctx: crate::Context<impl Sized, impl Write, impl Sized>,
+) -> anyhow::Result<()> {
+    let OptConfigResolve {
+        release,
+        config,
+        color: _,
+        service,
+        contest,
+        language,
+        problem,
+    } = opt;
+
+    let crate::Context {
+        cwd,
+        shell: crate::shell::Shell { mut stdout, .. },
+    } = ctx;
+
+    let (
+        crate::config::Target {
+            service,
+            contest,
+            problem,
+            ..
+        },
+        crate::config::Language {
+            src,
+            srcs,
+            transpile,
+            compile,
+            run,
+            runArgs: run_args,
+            languageId: language_id,
+            languageIds: language_ids,
+            ..
+        },
+        base_dir,
+    ) = crate::config::target_and_language(
+        &cwd,
+        config.as_deref(),
+        service,
+        contest.as_deref(),
+        problem.as_deref(),
+        language.as_deref(),
+        if release {
+            crate::config::Mode::Release
+        } else {
+            crate::config::Mode::Debug
+        },
+    )?;
+
+    let srcs = crate::judge::src_paths(&base_dir, &src, &srcs.unwrap_or_default())?;
+
+    let run = match run {
+        crate::config::Command::Args(args) => {
+            let (program, args) = args.split_first().map_or(("", &[][..]), |(p, a)| (p, a));
+            let args = args
+                .iter()
+                .chain(run_args.iter().flatten())
+                .collect::<Vec<_>>();
+            crate::judge::shell_escape_args(program, &args)
+        }
+        crate::config::Command::Script(crate::config::Script {
+            program, extension, ..
+        }) => format!("`{}` with a `.{}` script", program, extension),
+    };
+
+    let language_id = crate::config::language_id(service, language_ids, language_id)?;
+
+    let test_suite =
+        crate::judge::test_suite_path(&base_dir, service, contest.as_deref(), &problem);
+
+    let mut rows = vec![("src", srcs[0].display().to_string())];
+    if srcs.len() > 1 {
+        let srcs = srcs[1..].iter().map(|p| p.display()).join(" ");
+        rows.push(("srcs", srcs));
+    }
+    if let Some(transpile) = &transpile {
+        let output = crate::judge::build_output(&base_dir, transpile);
+        rows.push(("transpile.output", output.display().to_string()));
+    }
+    if let Some(compile) = &compile {
+        let output = crate::judge::build_output(&base_dir, compile);
+        rows.push(("compile.output", output.display().to_string()));
+    }
+    rows.push(("run", run));
+    if let Some(language_id) = language_id {
+        rows.push(("language ID", language_id));
+    }
+    rows.push(("working directory", base_dir.display().to_string()));
+    rows.push(("test suite", test_suite.display().to_string()));
+
+    let width = rows.iter().map(|(k, _)| k.len()).max().unwrap_or(0) + 1;
+    for (key, value) in rows {
+        writeln!(
+            stdout,
+            "{:<width$} {}",
+            format!("{}:", key),
+            value,
+            width = width
+        )?;
+    }
+    stdout.flush().map_err(Into::into)
+}
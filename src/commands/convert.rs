@@ -0,0 +1,385 @@
+use anyhow::{bail, ensure, Context as _};
+use snowchains_core::{
+    color_spec,
+    testsuite::{Additional, BatchTestSuite, Match, PartialBatchTestCase, TestSuite},
+    web::PlatformKind,
+};
+use std::{
+    io::{Cursor, Write as _},
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+use strum::{EnumString, EnumVariantNames, VariantNames as _};
+use termcolor::{Color, WriteColor};
+use zip::{write::FileOptions, ZipWriter};
+
+#[derive(StructOpt, Debug)]
+pub struct OptConvert {
+    /// Format to read
+    #[structopt(long, possible_values(SuiteFormat::VARIANTS))]
+    pub from: SuiteFormat,
+
+    /// Format to write
+    #[structopt(long, possible_values(SuiteFormat::VARIANTS))]
+    pub to: SuiteFormat,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    #[structopt(short, long, value_name("STRING"))]
+    pub problem: Option<String>,
+}
+
+/// Formats of test suites.
+#[derive(EnumVariantNames, EnumString, strum::Display, Debug, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum SuiteFormat {
+    /// `<problem>.yml`
+    Yaml,
+    /// `<problem>.json`
+    Json,
+    /// `<problem>.yml.gz`
+    #[strum(to_string = "yaml.gz")]
+    YamlGz,
+    /// `<problem>.json.gz`
+    #[strum(to_string = "json.gz")]
+    JsonGz,
+    /// `<problem>.toml`
+    Toml,
+    /// `<problem>.toml.gz`
+    #[strum(to_string = "toml.gz")]
+    TomlGz,
+    /// `<problem>/in/*.txt` and `<problem>/out/*.txt`, referred from `<problem>.yml`
+    Txt,
+    /// `<problem>.zip` that contains `in/*.txt` and `out/*.txt`
+    Zip,
+}
+
+pub(crate) fn run(
+    opt: OptConvert,
+    ctx: crate::Context<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptConvert {
+        from,
+        to,
+        config,
+        color: _,
+        service,
+        contest,
+        problem,
+    } = opt;
+
+    let crate::Context { cwd, mut shell } = ctx;
+
+    ensure!(from != to, "`--from` and `--to` are the same");
+
+    let (detected, workspace) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
+
+    let service = service
+        .map(Ok)
+        .or_else(|| detected.parse_service().transpose())
+        .with_context(|| {
+            "`service` was not detected. To specify it, add `--service` to the arguments"
+        })??;
+    let contest = contest.or(detected.contest);
+    let problem = problem
+        .or(detected.problem)
+        .with_context(|| "`problem` was not detected. Specify it in the arguments")?;
+
+    let dir = crate::judge::test_suite_path(&workspace, service, contest.as_deref(), &problem)
+        .parent()
+        .expect("should have a parent")
+        .to_owned();
+
+    let find = |exts: &[&str]| {
+        exts.iter()
+            .map(|ext| dir.join(&problem).with_extension(ext))
+            .find(|p| p.exists())
+            .unwrap_or_else(|| dir.join(&problem).with_extension(exts[0]))
+    };
+
+    let yaml_path = find(&["yml", "yaml"]);
+    let yaml_gz_path = find(&["yml.gz", "yaml.gz"]);
+    let json_path = dir.join(&problem).with_extension("json");
+    let json_gz_path = dir.join(&problem).with_extension("json.gz");
+    let toml_path = dir.join(&problem).with_extension("toml");
+    let toml_gz_path = dir.join(&problem).with_extension("toml.gz");
+    let txt_dir = dir.join(&problem);
+    let zip_path = dir.join(&problem).with_extension("zip");
+
+    let file_path = |format| match format {
+        SuiteFormat::Yaml => &yaml_path,
+        SuiteFormat::Json => &json_path,
+        SuiteFormat::YamlGz => &yaml_gz_path,
+        SuiteFormat::JsonGz => &json_gz_path,
+        SuiteFormat::Toml => &toml_path,
+        SuiteFormat::TomlGz => &toml_gz_path,
+        SuiteFormat::Txt | SuiteFormat::Zip => unreachable!(),
+    };
+
+    let test_suite = match from {
+        SuiteFormat::Yaml
+        | SuiteFormat::Json
+        | SuiteFormat::YamlGz
+        | SuiteFormat::JsonGz
+        | SuiteFormat::Toml
+        | SuiteFormat::TomlGz => {
+            let path = file_path(from);
+            ensure!(path.exists(), "`{}` does not exist", path.display());
+            crate::fs::read_json_or_yaml(path)?
+        }
+        SuiteFormat::Txt => {
+            let in_dir = txt_dir.join("in");
+            ensure!(in_dir.exists(), "`{}` does not exist", in_dir.display());
+
+            let mut test_suite = if yaml_path.exists() {
+                crate::fs::read_yaml(&yaml_path)?
+            } else {
+                TestSuite::Batch(BatchTestSuite {
+                    timelimit: None,
+                    memorylimit: None,
+                    systemtests: None,
+                    r#match: Some(Match::Lines),
+                    cases: vec![],
+                    extend: vec![],
+                    allow_nonzero_exit: false,
+                    normalizer: None,
+                })
+            };
+
+            if let TestSuite::Batch(BatchTestSuite { cases, extend, .. }) = &mut test_suite {
+                extend.retain(|a| !matches!(a, Additional::Text { .. }));
+                cases.extend(read_txt_files(&txt_dir)?);
+            }
+            test_suite
+        }
+        SuiteFormat::Zip => {
+            ensure!(zip_path.exists(), "`{}` does not exist", zip_path.display());
+
+            // Only the settings are taken from `<problem>.yml`.
+            let mut test_suite = if yaml_path.exists() {
+                crate::fs::read_yaml(&yaml_path)?
+            } else {
+                TestSuite::Batch(BatchTestSuite {
+                    timelimit: None,
+                    memorylimit: None,
+                    systemtests: None,
+                    r#match: Some(Match::Lines),
+                    cases: vec![],
+                    extend: vec![],
+                    allow_nonzero_exit: false,
+                    normalizer: None,
+                })
+            };
+
+            if let TestSuite::Batch(BatchTestSuite { cases, extend, .. }) = &mut test_suite {
+                extend.retain(|a| !matches!(a, Additional::Text { .. }));
+                *cases = super::import::read_zip(&zip_path, ZIP_IN, ZIP_OUT)?;
+            }
+            test_suite
+        }
+    };
+
+    let path = match to {
+        SuiteFormat::Yaml
+        | SuiteFormat::Json
+        | SuiteFormat::YamlGz
+        | SuiteFormat::JsonGz
+        | SuiteFormat::Toml
+        | SuiteFormat::TomlGz => {
+            let path = file_path(to).clone();
+            crate::fs::write_test_suite(&path, &test_suite, true)?;
+            path
+        }
+        SuiteFormat::Txt => {
+            let mut test_suite = test_suite;
+            let test_suite_mut = match &mut test_suite {
+                TestSuite::Batch(test_suite) => test_suite,
+                _ => bail!("Only `Batch` test suites can be converted to `txt`"),
+            };
+            ensure_no_alternative_outs(&test_suite_mut.cases, to)?;
+
+            for (i, case) in test_suite_mut.cases.drain(..).enumerate() {
+                let name = case.name.unwrap_or_else(|| (i + 1).to_string());
+                let txt_path = |dir: &str| txt_dir.join(dir).join(&name).with_extension("txt");
+                crate::fs::write(txt_path("in"), &*case.r#in, true)?;
+                if let Some(out) = case.out {
+                    crate::fs::write(txt_path("out"), &*out, true)?;
+                }
+            }
+
+            let text = Additional::Text {
+                path: format!("./{}", problem).into(),
+                r#in: "/in/*.txt".to_owned(),
+                out: "/out/*.txt".to_owned(),
+                timelimit: None,
+                r#match: None,
+            };
+            if !test_suite_mut.extend.contains(&text) {
+                test_suite_mut.extend.push(text);
+            }
+
+            crate::fs::write(&yaml_path, test_suite.to_yaml_pretty(), true)?;
+            txt_dir
+        }
+        SuiteFormat::Zip => {
+            let cases = match &test_suite {
+                TestSuite::Batch(test_suite) => test_suite
+                    .cases_with_text_files(&dir)
+                    .with_context(|| "Could not convert `extend` to `zip`")?,
+                _ => bail!("Only `Batch` test suites can be converted to `zip`"),
+            };
+            ensure_no_alternative_outs(&cases, to)?;
+            crate::fs::write(&zip_path, write_zip(&cases)?, true)?;
+            zip_path
+        }
+    };
+
+    shell.stderr.set_color(color_spec!(Bold))?;
+    write!(shell.stderr, "{}:", problem)?;
+    shell.stderr.reset()?;
+    write!(shell.stderr, " Converted to ")?;
+    shell.stderr.set_color(color_spec!(Fg(Color::Cyan)))?;
+    write!(shell.stderr, "{}", path.display())?;
+    shell.stderr.reset()?;
+    writeln!(shell.stderr)?;
+    shell.stderr.flush().map_err(Into::into)
+}
+
+/// Text files hold one output for each test case, so the other acceptable outputs would be lost.
+fn ensure_no_alternative_outs(
+    cases: &[PartialBatchTestCase],
+    to: SuiteFormat,
+) -> anyhow::Result<()> {
+    if let Some((i, case)) = cases
+        .iter()
+        .enumerate()
+        .find(|(_, case)| !case.alternative_outs.is_empty())
+    {
+        bail!(
+            "Test case {} has multiple acceptable outputs, which cannot be converted to `{}`",
+            case.name.clone().unwrap_or_else(|| (i + 1).to_string()),
+            to,
+        );
+    }
+    Ok(())
+}
+
+/// Globs for the entries of `<problem>.zip`, which [`write_zip`] writes.
+const ZIP_IN: &str = "in/*.txt";
+const ZIP_OUT: &str = "out/*.txt";
+
+/// Writes the test cases as `in/<name>.txt` and `out/<name>.txt` in a zip archive. Unnamed test
+/// cases are named by their 1-based indices.
+fn write_zip(cases: &[PartialBatchTestCase]) -> anyhow::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    for (i, case) in cases.iter().enumerate() {
+        let name = case.name.clone().unwrap_or_else(|| (i + 1).to_string());
+        zip.start_file(format!("in/{}.txt", name), FileOptions::default())?;
+        zip.write_all(case.r#in.as_bytes())?;
+        if let Some(out) = &case.out {
+            zip.start_file(format!("out/{}.txt", name), FileOptions::default())?;
+            zip.write_all(out.as_bytes())?;
+        }
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Reads `<dir>/in/*.txt` and the corresponding `<dir>/out/*.txt` in the order of the file names.
+fn read_txt_files(dir: &Path) -> anyhow::Result<Vec<PartialBatchTestCase>> {
+    let mut in_paths = std::fs::read_dir(dir.join("in"))
+        .with_context(|| format!("Could not read `{}`", dir.join("in").display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    in_paths.retain(|p| p.extension() == Some("txt".as_ref()));
+    in_paths.sort();
+
+    in_paths
+        .into_iter()
+        .map(|in_path| {
+            let name = in_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .with_context(|| format!("Invalid file name: `{}`", in_path.display()))?
+                .to_owned();
+            let out_path = dir.join("out").join(&name).with_extension("txt");
+            let out = if out_path.exists() {
+                Some(crate::fs::read_to_string(&out_path)?.into())
+            } else {
+                None
+            };
+            Ok(PartialBatchTestCase {
+                name: Some(name),
+                r#in: crate::fs::read_to_string(&in_path)?.into(),
+                out,
+                alternative_outs: vec![],
+                timelimit: None,
+                r#match: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use snowchains_core::testsuite::PartialBatchTestCase;
+
+    #[test]
+    fn write_and_read_zip() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-convert-test-")
+            .tempdir()?;
+
+        let case = |name: Option<&str>, r#in: &str, out: Option<&str>| PartialBatchTestCase {
+            name: name.map(ToOwned::to_owned),
+            r#in: r#in.into(),
+            out: out.map(Into::into),
+            alternative_outs: vec![],
+            timelimit: None,
+            r#match: None,
+        };
+
+        let path = tempdir.path().join("a.zip");
+        std::fs::write(
+            &path,
+            super::write_zip(&[
+                case(Some("sample_01"), "1\n", Some("2\n")),
+                case(None, "3\n", None),
+            ])?,
+        )?;
+
+        assert_eq!(
+            vec![
+                case(Some("2"), "3\n", None),
+                case(Some("sample_01"), "1\n", Some("2\n")),
+            ],
+            super::super::import::read_zip(&path, super::ZIP_IN, super::ZIP_OUT)?,
+        );
+
+        tempdir.close().map_err(Into::into)
+    }
+}
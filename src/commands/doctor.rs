@@ -0,0 +1,137 @@
+use anyhow::bail;
+use itertools::Itertools as _;
+use snowchains_core::{color_spec, web::PlatformKind};
+use std::{env, path::PathBuf};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::{Color, WriteColor};
+
+#[derive(StructOpt, Debug)]
+pub struct OptDoctor {
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    #[structopt(short, long, value_name("STRING"))]
+    pub problem: Option<String>,
+
+    /// Language name whose missing programs make this command fail (the detected one by default)
+    #[structopt(short, long, value_name("STRING"))]
+    pub language: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptDoctor,
+    ctx: crate::Context<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptDoctor {
+        config,
+        color: _,
+        service,
+        contest,
+        problem,
+        language,
+    } = opt;
+
+    let crate::Context {
+        cwd,
+        shell: crate::shell::Shell { mut stderr, .. },
+    } = ctx;
+
+    let (base_dir, detected_language, languages) = crate::config::languages_for_target(
+        &cwd,
+        config.as_deref(),
+        service,
+        contest.as_deref(),
+        problem.as_deref(),
+    )?;
+
+    let default_language = language.or(detected_language);
+    let path = env::var_os("PATH");
+
+    let mut default_language_missing = vec![];
+
+    // Only checks that the programs can be found. Nothing is executed.
+    for (name, language) in &languages {
+        let missing = language
+            .programs()
+            .into_iter()
+            .filter(|(_, program)| which::which_in(program, path.as_ref(), &base_dir).is_err())
+            .collect::<Vec<_>>();
+
+        stderr.set_color(color_spec!(Bold))?;
+        write!(stderr, "languages.{}:", name)?;
+        stderr.reset()?;
+        write!(stderr, " ")?;
+
+        if missing.is_empty() {
+            stderr.set_color(color_spec!(Fg(Color::Green)))?;
+            writeln!(stderr, "OK")?;
+        } else {
+            stderr.set_color(color_spec!(Fg(Color::Red)))?;
+            write!(stderr, "missing")?;
+            stderr.reset()?;
+            writeln!(
+                stderr,
+                " {}",
+                missing
+                    .iter()
+                    .format_with(", ", |(field, program), f| f(&format_args!(
+                        "`{}` ({})",
+                        program, field,
+                    ))),
+            )?;
+
+            if default_language.as_ref() == Some(name) {
+                default_language_missing = missing.iter().map(|(_, p)| p.to_string()).collect();
+            }
+        }
+        stderr.reset()?;
+    }
+
+    stderr.flush()?;
+
+    if let Some(default_language) = &default_language {
+        if !languages.contains_key(default_language) {
+            bail!(
+                "The language `{}` not found. Expected one of [{}]",
+                default_language,
+                languages.keys().join(", "),
+            );
+        }
+    }
+
+    if !default_language_missing.is_empty() {
+        bail!(
+            "Missing programs for `{}`: {}",
+            default_language.unwrap_or_default(),
+            default_language_missing
+                .iter()
+                .format_with(", ", |p, f| f(&format_args!("`{}`", p))),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use anyhow::{bail, ensure, Context as _};
+use globset::{Glob, GlobMatcher};
+use snowchains_core::{
+    color_spec,
+    testsuite::{BatchTestSuite, PartialBatchTestCase, TestSuite},
+    web::PlatformKind,
+};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Read as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::{Color, WriteColor};
+use zip::ZipArchive;
+
+#[derive(StructOpt, Debug)]
+pub struct OptImport {
+    /// Zip archive of test cases to import
+    #[structopt(long, value_name("PATH"))]
+    pub zip: PathBuf,
+
+    /// Glob for the input files in the archive
+    #[structopt(long, value_name("GLOB"), default_value("{**/in/*,**/test_in/*}"))]
+    pub r#in: String,
+
+    /// Glob for the expected output files in the archive
+    #[structopt(long, value_name("GLOB"), default_value("{**/out/*,**/test_out/*}"))]
+    pub out: String,
+
+    /// Discards the existing test cases
+    #[structopt(long)]
+    pub overwrite: bool,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptImport,
+    ctx: crate::Context<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptImport {
+        zip,
+        r#in,
+        out,
+        overwrite,
+        config,
+        color: _,
+        service,
+        contest,
+        problem,
+    } = opt;
+
+    let crate::Context { cwd, mut shell } = ctx;
+
+    let (detected, workspace) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
+
+    let service = service
+        .map(Ok)
+        .or_else(|| detected.parse_service().transpose())
+        .with_context(|| {
+            "`service` was not detected. To specify it, add `--service` to the arguments"
+        })??;
+    let contest = contest.or(detected.contest);
+    let problem = problem
+        .or(detected.problem)
+        .with_context(|| "`problem` was not detected. Specify it in the arguments")?;
+
+    let zip = cwd.join(zip.strip_prefix(".").unwrap_or(&zip));
+    let cases = read_zip(&zip, &r#in, &out)?;
+    ensure!(
+        !cases.is_empty(),
+        "No files in `{}` matched `--in {}`",
+        zip.display(),
+        r#in,
+    );
+    let num_cases = cases.len();
+
+    let path = crate::judge::test_suite_path(&workspace, service, contest.as_deref(), &problem);
+
+    let test_suite = if !overwrite && path.exists() {
+        match crate::fs::read_json_or_yaml(&path)
+            .with_context(|| "Could not merge the test cases. To discard them, add `--overwrite`")?
+        {
+            // Keeps the settings of the existing one.
+            TestSuite::Batch(BatchTestSuite {
+                timelimit,
+                memorylimit,
+                systemtests,
+                r#match,
+                cases: old_cases,
+                extend,
+                allow_nonzero_exit,
+                normalizer,
+            }) => {
+                let new = TestSuite::Batch(BatchTestSuite {
+                    timelimit,
+                    memorylimit,
+                    systemtests,
+                    r#match,
+                    cases,
+                    extend: vec![],
+                    allow_nonzero_exit,
+                    normalizer,
+                });
+                let old = TestSuite::Batch(BatchTestSuite {
+                    timelimit: None,
+                    memorylimit: None,
+                    systemtests: None,
+                    r#match: None,
+                    cases: old_cases,
+                    extend,
+                    allow_nonzero_exit: false,
+                    normalizer: None,
+                });
+                new.merge(old)
+            }
+            _ => bail!("`{}` is not a `Batch` test suite", path.display()),
+        }
+    } else {
+        TestSuite::Batch(BatchTestSuite {
+            timelimit: None,
+            memorylimit: None,
+            systemtests: None,
+            r#match: None,
+            cases,
+            extend: vec![],
+            allow_nonzero_exit: false,
+            normalizer: None,
+        })
+    };
+
+    crate::fs::write_test_suite(&path, &test_suite, true)?;
+
+    shell.stderr.set_color(color_spec!(Bold))?;
+    write!(shell.stderr, "{}:", problem)?;
+    shell.stderr.reset()?;
+    write!(
+        shell.stderr,
+        " Imported {} test case{} to ",
+        num_cases,
+        if num_cases == 1 { "" } else { "s" },
+    )?;
+    shell.stderr.set_color(color_spec!(Fg(Color::Cyan)))?;
+    write!(shell.stderr, "{}", path.display())?;
+    shell.stderr.reset()?;
+    writeln!(shell.stderr)?;
+    shell.stderr.flush().map_err(Into::into)
+}
+
+/// Pairs the files in the archive that match `r#in` and `out` by their file stems, sorted by the
+/// names.
+pub(crate) fn read_zip(
+    path: &Path,
+    r#in: &str,
+    out: &str,
+) -> anyhow::Result<Vec<PartialBatchTestCase>> {
+    let glob = |glob: &str, flag: &str| -> anyhow::Result<GlobMatcher> {
+        Ok(Glob::new(glob)
+            .with_context(|| format!("Invalid `{}`", flag))?
+            .compile_matcher())
+    };
+    let (in_glob, out_glob) = (glob(r#in, "--in")?, glob(out, "--out")?);
+
+    let mut archive = File::open(path)
+        .map_err(Into::into)
+        .and_then(|file| ZipArchive::new(file).map_err(anyhow::Error::from))
+        .with_context(|| format!("Could not open `{}`", path.display()))?;
+
+    let mut cases = BTreeMap::<_, (Option<Arc<str>>, Option<Arc<str>>)>::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+
+        let entry_name = file.name().to_owned();
+
+        let is_in = if in_glob.is_match(&entry_name) {
+            true
+        } else if out_glob.is_match(&entry_name) {
+            false
+        } else {
+            continue;
+        };
+
+        let name = Path::new(&entry_name)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .with_context(|| format!("Could not read `{}` in the archive", entry_name))?;
+
+        let (in_entry, out_entry) = cases.entry(name.clone()).or_default();
+        let entry = if is_in { in_entry } else { out_entry };
+        ensure!(entry.is_none(), "Duplicated name: {:?}", name);
+        *entry = Some(content.into());
+    }
+
+    cases
+        .into_iter()
+        .map(|(name, (r#in, out))| {
+            let r#in = r#in.with_context(|| format!("No input file for {:?}", name))?;
+            Ok(PartialBatchTestCase {
+                name: Some(name),
+                r#in,
+                out,
+                alternative_outs: vec![],
+                timelimit: None,
+                r#match: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write as _};
+    use zip::{write::FileOptions, ZipWriter};
+
+    #[test]
+    fn read_zip() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-import-test-")
+            .tempdir()?;
+
+        let path = tempdir.path().join("testcase.zip");
+        let mut zip = ZipWriter::new(File::create(&path)?);
+        for (name, content) in &[
+            ("abc/in/sample_01.txt", "1\n"),
+            ("abc/out/sample_01.txt", "2\n"),
+            ("abc/in/01.txt", "3\n"),
+            ("abc/README.md", ""),
+        ] {
+            zip.start_file(*name, FileOptions::default())?;
+            zip.write_all(content.as_ref())?;
+        }
+        zip.finish()?;
+
+        let cases = super::read_zip(&path, "{**/in/*,**/test_in/*}", "{**/out/*,**/test_out/*}")?;
+        assert_eq!(2, cases.len());
+        assert_eq!(Some("01"), cases[0].name.as_deref());
+        assert_eq!(None, cases[0].out);
+        assert_eq!(Some("sample_01"), cases[1].name.as_deref());
+        assert_eq!("1\n", &*cases[1].r#in);
+        assert_eq!(Some("2\n"), cases[1].out.as_deref());
+
+        assert!(super::read_zip(&path, "**/test_in/*", "**/test_out/*")?.is_empty());
+        // `out/01.txt` would have no input.
+        assert!(super::read_zip(&path, "**/out/*", "**/in/*").is_err());
+
+        tempdir.close().map_err(Into::into)
+    }
+}
@@ -1,10 +1,19 @@
 use crate::config;
-use human_size::Size;
-use snowchains_core::web::PlatformKind;
-use std::path::PathBuf;
+use snowchains_core::{color_spec, web::PlatformKind};
+use std::{
+    collections::HashSet,
+    io::BufRead,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 use structopt::StructOpt;
 use strum::VariantNames as _;
-use termcolor::WriteColor;
+use termcolor::{Color, WriteColor};
 
 #[derive(StructOpt, Debug)]
 pub struct OptJudge {
@@ -17,8 +26,76 @@ pub struct OptJudge {
     pub testcases: Option<Vec<String>>,
 
     /// Display limit
-    #[structopt(long, value_name("SIZE"), default_value("4KiB"))]
-    pub display_limit: Size,
+    #[structopt(long, value_name("SIZE|none"), default_value("4KiB"))]
+    pub display_limit: crate::DisplayLimit,
+
+    /// Maximum size of stderr of the program and the output of checkers to keep
+    #[structopt(long, value_name("SIZE|none"), default_value("16MiB"))]
+    pub capture_limit: crate::DisplayLimit,
+
+    /// Streams stderr of the program to the terminal instead of capturing it
+    #[structopt(long)]
+    pub show_stderr: bool,
+
+    /// Tests only the test cases that failed in the last run
+    #[structopt(long, conflicts_with("testcases"))]
+    pub only_failed: bool,
+
+    /// Stops at the first failing test case
+    #[structopt(long)]
+    pub fail_fast: bool,
+
+    /// Runs only the first N test cases
+    #[structopt(long, value_name("N"))]
+    pub max_cases: Option<usize>,
+
+    /// Prints elapsed times with microseconds (e.g. "0.234 ms")
+    #[structopt(long)]
+    pub precise_time: bool,
+
+    /// Compiles the code even if it is up to date
+    #[structopt(long)]
+    pub force_compile: bool,
+
+    /// Does not compile the code and runs the existing binary
+    #[structopt(long, conflicts_with("force-compile"))]
+    pub no_compile: bool,
+
+    /// Value of `$seed` in the run command (random by default)
+    #[structopt(long, value_name("INTEGER"))]
+    pub seed: Option<u64>,
+
+    /// Output format of the result
+    #[structopt(
+        long,
+        possible_values(crate::JudgeFormat::VARIANTS),
+        default_value("human")
+    )]
+    pub format: crate::JudgeFormat,
+
+    /// Prints `RESULT passed=<n> failed=<n> total=<n>` (and `skipped=<n>` with `--max-cases`) at the end
+    #[structopt(long)]
+    pub summary: bool,
+
+    /// Runs the program once with an input read from stdin instead of the test suite
+    #[structopt(long, conflicts_with_all(&["testcases", "only-failed", "watch"]))]
+    pub stdin: bool,
+
+    /// Runs the program once with the input in this file instead of the test suite
+    #[structopt(
+        long,
+        value_name("PATH"),
+        conflicts_with_all(&["testcases", "only-failed", "stdin"])
+    )]
+    pub input: Option<PathBuf>,
+
+    /// Expected output for `--input`
+    #[structopt(long, value_name("PATH"), requires("input"))]
+    pub expected: Option<PathBuf>,
+
+    /// Re-runs on every change of the source file or the test suite
+    #[structopt(long)]
+    pub watch: bool,
 
     /// Path to `snowchains.dhall`
     #[structopt(long)]
@@ -55,12 +132,27 @@ pub struct OptJudge {
 
 pub(crate) fn run(
     opt: OptJudge,
-    ctx: crate::Context<impl Sized, impl WriteColor, impl WriteColor>,
+    ctx: crate::Context<impl BufRead, impl WriteColor, impl WriteColor>,
 ) -> anyhow::Result<()> {
     let OptJudge {
         release,
         testcases,
         display_limit,
+        capture_limit,
+        show_stderr,
+        only_failed,
+        fail_fast,
+        max_cases,
+        precise_time,
+        force_compile,
+        no_compile,
+        seed,
+        format,
+        summary,
+        stdin,
+        input,
+        expected,
+        watch,
         config,
         color: _,
         service,
@@ -69,66 +161,197 @@ pub(crate) fn run(
         problem,
     } = opt;
 
-    let crate::Context { cwd, shell } = ctx;
+    let crate::Context { cwd, mut shell } = ctx;
 
-    let progress_draw_target = shell.progress_draw_target();
+    let stdin_input = if stdin {
+        Some(shell.stdin.read_to_string()?)
+    } else {
+        None
+    };
 
-    let crate::shell::Shell {
-        stdout,
-        stderr,
-        stdin_process_redirection,
-        stdout_process_redirection,
-        stderr_process_redirection,
-        ..
-    } = shell;
+    let input_and_expected = input.map(|input| {
+        let resolve = |p: PathBuf| cwd.join(p.strip_prefix(".").unwrap_or(&p));
+        (resolve(input), expected.map(resolve))
+    });
 
-    let (
-        config::Target {
+    let test_case_names = testcases.map(|ss| ss.into_iter().collect::<HashSet<_>>());
+
+    let judge = |shell: &mut crate::shell::Shell<_, _, _>| -> anyhow::Result<_> {
+        let (
+            config::Target {
+                service,
+                contest,
+                problem,
+                ..
+            },
+            config::Language {
+                src,
+                srcs,
+                transpile,
+                compile,
+                run,
+                runEnv: run_env,
+                runArgs: run_args,
+                bundler: _,
+                replace: _,
+                includeDirs: _,
+                languageId: _,
+                languageIds: _,
+                template: _,
+            },
+            base_dir,
+        ) = config::target_and_language(
+            &cwd,
+            config.as_deref(),
+            service,
+            contest.as_deref(),
+            problem.as_deref(),
+            language.as_deref(),
+            if release {
+                config::Mode::Release
+            } else {
+                config::Mode::Debug
+            },
+        )?;
+
+        let srcs = crate::judge::src_paths(&base_dir, &src, &srcs.unwrap_or_default())?;
+
+        let mut watched = srcs.clone();
+        watched.push(crate::judge::test_suite_path(
+            &base_dir,
+            service,
+            contest.as_deref(),
+            &problem,
+        ));
+        if let Some((input, expected)) = &input_and_expected {
+            watched.push(input.clone());
+            watched.extend(expected.clone());
+        }
+
+        let progress_draw_target = shell.progress_draw_target();
+
+        let result = crate::judge::judge(crate::judge::Args {
+            stdout: &mut shell.stdout,
+            stderr: &mut shell.stderr,
+            stdin_process_redirection: shell.stdin_process_redirection,
+            stdout_process_redirection: shell.stdout_process_redirection,
+            stderr_process_redirection: shell.stderr_process_redirection,
+            progress_draw_target,
+            base_dir,
             service,
             contest,
             problem,
-            ..
-        },
-        config::Language {
-            src,
+            srcs,
             transpile,
             compile,
             run,
-            languageId: _,
-        },
-        base_dir,
-    ) = config::target_and_language(
-        &cwd,
-        config.as_deref(),
-        service,
-        contest.as_deref(),
-        problem.as_deref(),
-        language.as_deref(),
-        if release {
-            config::Mode::Release
-        } else {
-            config::Mode::Debug
-        },
-    )?;
-
-    let test_case_names = testcases.map(|ss| ss.into_iter().collect());
-
-    crate::judge::judge(crate::judge::Args {
-        stdout,
-        stderr,
-        stdin_process_redirection,
-        stdout_process_redirection,
-        stderr_process_redirection,
-        progress_draw_target,
-        base_dir,
-        service,
-        contest,
-        problem,
-        src,
-        transpile,
-        compile,
-        run,
-        test_case_names,
-        display_limit,
-    })
+            run_env: run_env.unwrap_or_default(),
+            run_args: run_args.unwrap_or_default(),
+            default_match: config::testfiles(&cwd, config.as_deref())?.default_match(),
+            test_case_names: test_case_names.clone(),
+            stdin_input: stdin_input.clone(),
+            input_and_expected: input_and_expected.clone(),
+            display_limit,
+            capture_limit,
+            show_stderr,
+            format,
+            summary,
+            only_failed,
+            fail_fast,
+            max_cases,
+            precise_time,
+            force_compile,
+            no_compile,
+            seed,
+            hooks: config::hooks(&cwd, config.as_deref())?,
+        });
+
+        Ok((watched, result))
+    };
+
+    if !watch {
+        let (_, result) = judge(&mut shell)?;
+        return result;
+    }
+
+    // Exits after the current iteration instead.
+    let _guard = crate::ctrl_c::Guard::new();
+    let interrupted = Arc::new(AtomicBool::new(false));
+    thread::spawn({
+        let interrupted = interrupted.clone();
+        move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("could not build a Tokio runtime");
+            if rt.block_on(tokio::signal::ctrl_c()).is_ok() {
+                interrupted.store(true, Ordering::SeqCst);
+            }
+        }
+    });
+
+    loop {
+        // Clears the screen.
+        fwdansi::write_ansi(&mut shell.stderr, b"\x1b[2J\x1b[H")?;
+
+        let (watched, result) = judge(&mut shell)?;
+
+        if let Err(err) = result {
+            shell.stderr.set_color(color_spec!(Bold, Fg(Color::Red)))?;
+            write!(shell.stderr, "Error:")?;
+            shell.stderr.reset()?;
+            writeln!(shell.stderr, " {:?}", err)?;
+        }
+
+        writeln!(shell.stderr)?;
+        shell.stderr.set_color(color_spec!(Bold))?;
+        write!(shell.stderr, "Watching for changes...")?;
+        shell.stderr.reset()?;
+        writeln!(shell.stderr, " (Ctrl-C to exit)")?;
+        shell.stderr.flush()?;
+
+        if !wait_for_changes(&watched, &interrupted) {
+            return Ok(());
+        }
+    }
+}
+
+/// Polls the modification times of `paths` until they change and stop changing.
+///
+/// Returns `false` if interrupted.
+fn wait_for_changes(paths: &[PathBuf], interrupted: &AtomicBool) -> bool {
+    const INTERVAL: Duration = Duration::from_millis(200);
+
+    let mtimes = || {
+        paths
+            .iter()
+            .map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+            .collect::<Vec<_>>()
+    };
+
+    let initial = mtimes();
+
+    let mut last = loop {
+        if interrupted.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(INTERVAL);
+        let current = mtimes();
+        if current != initial {
+            break current;
+        }
+    };
+
+    // Waits for rapid successive writes to settle.
+    loop {
+        if interrupted.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(INTERVAL);
+        let current = mtimes();
+        if current == last {
+            return true;
+        }
+        last = current;
+    }
 }
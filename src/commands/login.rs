@@ -1,3 +1,4 @@
+use anyhow::ensure;
 use serde::Serialize;
 use snowchains_core::web::{
     Atcoder, AtcoderLoginCredentials, Codeforces, CodeforcesLoginCredentials, CookieStorage, Login,
@@ -6,6 +7,7 @@ use snowchains_core::web::{
 use std::{
     cell::RefCell,
     io::{BufRead, Write},
+    path::PathBuf,
 };
 use structopt::StructOpt;
 use strum::VariantNames as _;
@@ -17,6 +19,14 @@ pub struct OptLogin {
     #[structopt(long)]
     pub json: bool,
 
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
     /// Coloring
     #[structopt(
         long,
@@ -47,30 +57,45 @@ pub(crate) fn run(
 ) -> anyhow::Result<()> {
     let OptLogin {
         json,
+        http_timeout,
+        config,
         color: _,
         service,
     } = opt;
 
-    let crate::Context { cwd: _, mut shell } = ctx;
+    let crate::Context { cwd, mut shell } = ctx;
 
     let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
 
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
 
     let outcome = match service {
         PlatformKind::Atcoder => {
             let shell = RefCell::new(&mut shell);
 
+            let mut username_and_password =
+                crate::web::credentials::atcoder_username_and_password(&shell);
+
             let credentials = AtcoderLoginCredentials {
-                username_and_password: &mut crate::web::credentials::atcoder_username_and_password(
-                    &shell,
-                ),
+                // An empty username means the account signs in with Google or GitHub.
+                username_and_password: &mut || {
+                    let (username, password) = username_and_password()?;
+                    ensure!(!username.is_empty(), "No username given");
+                    Ok((username, password))
+                },
+                revel_session: Some(&mut crate::web::credentials::atcoder_revel_session(&shell)),
             };
 
             Atcoder::exec(Login {
                 credentials,
                 cookie_storage,
                 timeout,
+                user_agent,
+                headers,
                 shell: &shell,
             })
         }
@@ -86,10 +111,14 @@ pub(crate) fn run(
                 credentials,
                 cookie_storage,
                 timeout,
+                user_agent,
+                headers,
                 shell: &shell,
             })
         }
-        PlatformKind::Yukicoder => unreachable!("should be filtered by `possible_values`"),
+        PlatformKind::Yukicoder | PlatformKind::Aoj | PlatformKind::Other => {
+            unreachable!("should be filtered by `possible_values`")
+        }
     }?;
 
     let message = if json {
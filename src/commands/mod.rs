@@ -1,7 +1,16 @@
+pub(crate) mod config_check;
+pub(crate) mod config_resolve;
+pub(crate) mod convert;
+pub(crate) mod doctor;
+pub(crate) mod import;
 pub(crate) mod init;
 pub(crate) mod judge;
 pub(crate) mod login;
+pub(crate) mod modify_match;
+pub(crate) mod new;
+pub(crate) mod open;
 pub(crate) mod participate;
+pub(crate) mod results;
 pub(crate) mod retrieve_languages;
 pub(crate) mod retrieve_submission_summaries;
 pub(crate) mod retrieve_testcases;
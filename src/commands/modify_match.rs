@@ -0,0 +1,134 @@
+use anyhow::{bail, ensure, Context as _};
+use snowchains_core::{
+    color_spec,
+    testsuite::{BatchTestSuite, Match, PositiveFinite, TestSuite},
+    web::PlatformKind,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use strum::{EnumString, EnumVariantNames, VariantNames as _};
+use termcolor::{Color, WriteColor};
+
+#[derive(StructOpt, Debug)]
+pub struct OptModifyMatch {
+    /// Relative error for `float`
+    #[structopt(long, value_name("FLOAT"))]
+    pub relative_error: Option<PositiveFinite<f64>>,
+
+    /// Absolute error for `float`
+    #[structopt(long, value_name("FLOAT"))]
+    pub absolute_error: Option<PositiveFinite<f64>>,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    #[structopt(short, long, value_name("STRING"))]
+    pub problem: Option<String>,
+
+    /// New `match`
+    #[structopt(possible_values(MatchKind::VARIANTS))]
+    pub kind: MatchKind,
+}
+
+#[derive(EnumVariantNames, EnumString, strum::Display, Debug, Clone, Copy)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MatchKind {
+    Exact,
+    SplitWhitespace,
+    Lines,
+    Float,
+    Binary,
+}
+
+pub(crate) fn run(
+    opt: OptModifyMatch,
+    ctx: crate::Context<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptModifyMatch {
+        relative_error,
+        absolute_error,
+        config,
+        color: _,
+        service,
+        contest,
+        problem,
+        kind,
+    } = opt;
+
+    let crate::Context { cwd, mut shell } = ctx;
+
+    let new_match = match kind {
+        MatchKind::Exact => Match::Exact,
+        MatchKind::SplitWhitespace => Match::SplitWhitespace,
+        MatchKind::Lines => Match::Lines,
+        MatchKind::Binary => Match::Binary,
+        MatchKind::Float => Match::Float {
+            relative_error,
+            absolute_error,
+        },
+    };
+
+    ensure!(
+        matches!(kind, MatchKind::Float) || relative_error.is_none() && absolute_error.is_none(),
+        "`--relative-error` and `--absolute-error` are only for `float`",
+    );
+
+    let (detected, workspace) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
+
+    let service = service
+        .map(Ok)
+        .or_else(|| detected.parse_service().transpose())
+        .with_context(|| {
+            "`service` was not detected. To specify it, add `--service` to the arguments"
+        })??;
+    let contest = contest.or(detected.contest);
+    let problem = problem
+        .or(detected.problem)
+        .with_context(|| "`problem` was not detected. Specify it in the arguments")?;
+
+    let path = crate::judge::test_suite_path(&workspace, service, contest.as_deref(), &problem);
+    ensure!(path.exists(), "`{}` does not exist", path.display());
+
+    let mut test_suite = crate::fs::read_json_or_yaml::<TestSuite, _>(&path)?;
+
+    match &mut test_suite {
+        TestSuite::Batch(BatchTestSuite { r#match, .. }) => *r#match = Some(new_match),
+        _ => bail!("`{}` is not a `Batch` test suite", path.display()),
+    }
+
+    crate::fs::write_test_suite(&path, &test_suite, false)?;
+
+    shell.stderr.set_color(color_spec!(Bold))?;
+    write!(shell.stderr, "{}:", problem)?;
+    shell.stderr.reset()?;
+    write!(shell.stderr, " Set `match` to ")?;
+    shell.stderr.set_color(color_spec!(Fg(Color::Cyan)))?;
+    write!(shell.stderr, "{}", kind)?;
+    shell.stderr.reset()?;
+    writeln!(shell.stderr, " in {}", path.display())?;
+    shell.stderr.flush().map_err(Into::into)
+}
@@ -0,0 +1,138 @@
+use crate::{config::Template, web::CaseConversions};
+use anyhow::{bail, Context as _};
+use snowchains_core::web::PlatformKind;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::WriteColor;
+
+#[derive(StructOpt, Debug)]
+pub struct OptNew {
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Language name
+    #[structopt(short, long, value_name("STRING"))]
+    pub language: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptNew,
+    ctx: crate::Context<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptNew {
+        config,
+        color: _,
+        service,
+        contest,
+        language,
+        problem,
+    } = opt;
+
+    let crate::Context {
+        cwd,
+        shell: crate::shell::Shell { mut stderr, .. },
+    } = ctx;
+
+    let (
+        crate::config::Target { problem, .. },
+        crate::config::Language { src, template, .. },
+        base_dir,
+    ) = crate::config::target_and_language(
+        &cwd,
+        config.as_deref(),
+        service,
+        contest.as_deref(),
+        problem.as_deref(),
+        language.as_deref(),
+        crate::config::Mode::Debug,
+    )?;
+
+    let template = match template {
+        Some(Template::Path(path)) => {
+            crate::fs::read_to_string(base_dir.join(path.strip_prefix("./").unwrap_or(&path)))?
+        }
+        Some(Template::Content(content)) => content,
+        None => bail!("`template` is not set for the language"),
+    };
+
+    let path = base_dir.join(src.strip_prefix("./").unwrap_or(&src));
+
+    if path.exists() {
+        bail!("`{}` already exists", path.display());
+    }
+
+    crate::fs::write(&path, render(&template, &problem), true)
+        .with_context(|| "Failed to create the source file from `template`")?;
+
+    writeln!(stderr, "Wrote `{}`", path.display())?;
+    stderr.flush()?;
+
+    Ok(())
+}
+
+/// Replaces `{lower}`, `{upper}`, `{snake}`, `{kebab}`, `{mixed}`, and `{pascal}` with the case
+/// conversions of `problem`. Other braces are left as they are.
+fn render(template: &str, problem: &str) -> String {
+    let CaseConversions {
+        lower,
+        upper,
+        snake,
+        kebab,
+        mixed,
+        pascal,
+        ..
+    } = CaseConversions::new(problem);
+
+    [
+        ("{lower}", lower),
+        ("{upper}", upper),
+        ("{snake}", snake),
+        ("{kebab}", kebab),
+        ("{mixed}", mixed),
+        ("{pascal}", pascal),
+    ]
+    .iter()
+    .fold(template.to_owned(), |acc, (placeholder, value)| {
+        acc.replace(placeholder, value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn render() {
+        assert_eq!(
+            "struct TwoSum {}\n// two_sum two-sum twoSum TWO-SUM two-sum {kebab_case}\n",
+            super::render(
+                "struct {pascal} {}\n// {snake} {kebab} {mixed} {upper} {lower} {kebab_case}\n",
+                "two-sum",
+            ),
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::{bail, Context as _};
+use snowchains_core::web::PlatformKind;
+use std::{
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::WriteColor;
+use url::Url;
+
+#[derive(StructOpt, Debug)]
+pub struct OptOpen {
+    /// Opens your submissions in the contest instead
+    #[structopt(long)]
+    pub submissions: bool,
+
+    /// Opens the standings of the contest instead
+    #[structopt(long, conflicts_with("submissions"))]
+    pub standings: bool,
+
+    /// Prints the URL to stdout instead of opening it
+    #[structopt(long)]
+    pub print_url: bool,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(
+        short,
+        long,
+        value_name("SERVICE"),
+        possible_values(PlatformKind::KEBAB_CASE_VARIANTS)
+    )]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+
+    /// Problem index (e.g. "a", "b", "c")
+    pub problem: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptOpen,
+    ctx: crate::Context<impl BufRead, impl Write, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptOpen {
+        submissions,
+        standings,
+        print_url,
+        config,
+        color: _,
+        service,
+        contest,
+        problem,
+    } = opt;
+
+    let crate::Context { cwd, mut shell } = ctx;
+
+    let crate::config::Target {
+        service,
+        contest,
+        problem,
+        alias,
+        ..
+    } = crate::config::target(
+        &cwd,
+        config.as_deref(),
+        service,
+        contest.as_deref(),
+        problem.as_deref(),
+    )?;
+
+    // Services know the problem by the name the user typed, not by what it is aliased to.
+    let problem = alias.unwrap_or(problem);
+
+    let page = if submissions {
+        Page::Submissions
+    } else if standings {
+        Page::Standings
+    } else {
+        Page::Problem
+    };
+    let url = page_url(service, contest.as_deref(), &problem, page)?;
+
+    if print_url {
+        writeln!(shell.stdout, "{}", url)?;
+        return shell.stdout.flush().map_err(Into::into);
+    }
+
+    writeln!(shell.stderr, "Opening {}", url)?;
+    shell.stderr.flush()?;
+    crate::web::open_in_browser(&url)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Page {
+    Problem,
+    Submissions,
+    Standings,
+}
+
+/// Builds the URL from the target alone, so that nothing is fetched.
+fn page_url(
+    service: PlatformKind,
+    contest: Option<&str>,
+    problem: &str,
+    page: Page,
+) -> anyhow::Result<Url> {
+    let require_contest = || {
+        contest.with_context(|| format!("The problem `{}` does not belong to a contest", problem))
+    };
+
+    let url = match (service, page) {
+        (PlatformKind::Atcoder, page) => {
+            let contest = require_contest()?.to_lowercase();
+            let rest = match page {
+                // Most of the task screen names are `<contest with `_`s>_<index>`.
+                Page::Problem => format!(
+                    "/tasks/{}_{}",
+                    contest.replace('-', "_"),
+                    problem.to_lowercase(),
+                ),
+                Page::Submissions => "/submissions/me".to_owned(),
+                Page::Standings => "/standings".to_owned(),
+            };
+            Url::parse(&format!("https://atcoder.jp/contests/{}{}", contest, rest))?
+        }
+        (PlatformKind::Codeforces, page) => {
+            let rest = match page {
+                Page::Problem => format!("/problem/{}", problem.to_uppercase()),
+                Page::Submissions => "/my".to_owned(),
+                Page::Standings => "/standings".to_owned(),
+            };
+            snowchains_core::web::codeforces_contest_url(require_contest()?, &rest)?
+        }
+        (PlatformKind::Yukicoder, Page::Problem) => match contest {
+            // Problems in a contest are only known by their indexes, so open the contest instead.
+            Some(contest) => Url::parse(&format!("https://yukicoder.me/contests/{}", contest))?,
+            None => Url::parse(&format!("https://yukicoder.me/problems/no/{}", problem))?,
+        },
+        (PlatformKind::Yukicoder, Page::Submissions) => Url::parse(&format!(
+            "https://yukicoder.me/contests/{}/submissions?my_submission=enabled",
+            require_contest()?,
+        ))?,
+        (PlatformKind::Yukicoder, Page::Standings) => Url::parse(&format!(
+            "https://yukicoder.me/contests/{}/table",
+            require_contest()?
+        ))?,
+        (PlatformKind::Aoj, Page::Problem) => snowchains_core::web::aoj_problem_url(problem),
+        (PlatformKind::Aoj, _) => bail!("AOJ problems do not belong to contests"),
+        (PlatformKind::Other, _) => bail!("`other` is a local service and has no pages"),
+    };
+    Ok(url)
+}
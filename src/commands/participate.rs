@@ -1,3 +1,4 @@
+use anyhow::bail;
 use serde::Serialize;
 use snowchains_core::web::{
     Atcoder, AtcoderParticipateCredentials, AtcoderParticipateTarget, CookieStorage, Participate,
@@ -6,6 +7,7 @@ use snowchains_core::web::{
 use std::{
     cell::RefCell,
     io::{BufRead, Write},
+    path::PathBuf,
 };
 use structopt::StructOpt;
 use strum::VariantNames as _;
@@ -17,6 +19,14 @@ pub struct OptParticipate {
     #[structopt(long)]
     pub json: bool,
 
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
     /// Coloring
     #[structopt(
         long,
@@ -26,7 +36,7 @@ pub struct OptParticipate {
     pub color: crate::ColorChoice,
 
     /// Target platform
-    #[structopt(possible_value("atcoder"))]
+    #[structopt(possible_values(PlatformKind::KEBAB_CASE_VARIANTS))]
     pub service: PlatformKind,
 
     /// Contest ID
@@ -54,15 +64,38 @@ pub(crate) fn run(
 ) -> anyhow::Result<()> {
     let OptParticipate {
         json,
+        http_timeout,
+        config,
         color: _,
-        service: _,
+        service,
         contest,
     } = opt;
 
-    let crate::Context { cwd: _, mut shell } = ctx;
+    let crate::Context { cwd, mut shell } = ctx;
+
+    match service {
+        PlatformKind::Atcoder => {}
+        PlatformKind::Codeforces => bail!(
+            "`participate` for Codeforces is not implemented yet. Register on the website instead"
+        ),
+        PlatformKind::Yukicoder => {
+            writeln!(shell.stderr, "yukicoder has no participate step.")?;
+            shell.stderr.flush()?;
+            return Ok(());
+        }
+        PlatformKind::Aoj => {
+            writeln!(shell.stderr, "AOJ has no participate step.")?;
+            shell.stderr.flush()?;
+            return Ok(());
+        }
+        PlatformKind::Other => bail!("`other` is a local service and has no contests"),
+    }
 
     let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
 
     let kind = {
         let shell = RefCell::new(&mut shell);
@@ -80,6 +113,8 @@ pub(crate) fn run(
             credentials,
             cookie_storage,
             timeout,
+            user_agent,
+            headers,
             shell: &shell,
         })?
     };
@@ -0,0 +1,131 @@
+use anyhow::{bail, Context as _};
+use snowchains_core::web::{
+    Atcoder, AtcoderRetrieveSubmissionSummariesCredentials,
+    AtcoderRetrieveSubmissionSummariesTarget, CookieStorage, PlatformKind,
+    RetrieveSubmissionSummaries,
+};
+use std::{cell::RefCell, io::BufRead, path::PathBuf};
+use structopt::StructOpt;
+use strum::VariantNames as _;
+use termcolor::WriteColor;
+
+#[derive(StructOpt, Debug)]
+pub struct OptResults {
+    /// Shows only the submissions for the problem (e.g. "a", "b", "c")
+    #[structopt(short, long, value_name("STRING"))]
+    pub problem: Option<String>,
+
+    /// Shows at most this number of the latest submissions
+    #[structopt(long, value_name("NUMBER"))]
+    pub limit: Option<usize>,
+
+    /// Prints JSON data
+    #[structopt(long)]
+    pub json: bool,
+
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
+    /// Coloring
+    #[structopt(
+        long,
+        possible_values(crate::ColorChoice::VARIANTS),
+        default_value("auto")
+    )]
+    pub color: crate::ColorChoice,
+
+    /// Platform
+    #[structopt(short, long, value_name("SERVICE"), possible_value("atcoder"))]
+    pub service: Option<PlatformKind>,
+
+    /// Contest ID
+    #[structopt(short, long, value_name("STRING"))]
+    pub contest: Option<String>,
+}
+
+pub(crate) fn run(
+    opt: OptResults,
+    ctx: crate::Context<impl BufRead, impl WriteColor, impl WriteColor>,
+) -> anyhow::Result<()> {
+    let OptResults {
+        problem,
+        limit,
+        json,
+        http_timeout,
+        config,
+        color: _,
+        service,
+        contest,
+    } = opt;
+
+    let crate::Context { cwd, mut shell } = ctx;
+
+    let (detected_target, _) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
+
+    let service = service
+        .map(Ok)
+        .or_else(|| detected_target.parse_service().transpose())
+        .with_context(|| {
+            "`service` was not detected. To specify it, add `--service` to the arguments"
+        })??;
+
+    let contest = contest.or(detected_target.contest);
+
+    let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
+
+    match service {
+        PlatformKind::Atcoder => {
+            let mut outcome = {
+                let shell = RefCell::new(&mut shell);
+
+                let target = AtcoderRetrieveSubmissionSummariesTarget {
+                    contest: contest.with_context(|| "`contest` is required for AtCoder")?,
+                };
+
+                let credentials = AtcoderRetrieveSubmissionSummariesCredentials {
+                    username_and_password:
+                        &mut crate::web::credentials::atcoder_username_and_password(&shell),
+                };
+
+                Atcoder::exec(RetrieveSubmissionSummaries {
+                    target,
+                    credentials,
+                    cookie_storage,
+                    timeout,
+                    user_agent,
+                    headers,
+                    shell: &shell,
+                })?
+            };
+
+            if let Some(problem) = &problem {
+                outcome.retain_problem(problem);
+            }
+            if let Some(limit) = limit {
+                outcome.truncate(limit);
+            }
+
+            if json {
+                writeln!(shell.stdout, "{}", outcome.to_json())?;
+                shell.stdout.flush()?;
+            } else {
+                outcome.print_pretty(&mut shell.stdout)?;
+            }
+            Ok(())
+        }
+        PlatformKind::Codeforces | PlatformKind::Yukicoder | PlatformKind::Aoj => {
+            bail!("`results` is only supported for AtCoder")
+        }
+        PlatformKind::Other => bail!("`other` is a local service and has no submissions"),
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Context as _;
+use anyhow::{bail, Context as _};
 use snowchains_core::web::{
     Atcoder, AtcoderRetrieveLanguagesCredentials, AtcoderRetrieveLanguagesTarget, Codeforces,
     CodeforcesRetrieveLanguagesCredentials, CodeforcesRetrieveLanguagesTarget, CookieStorage,
@@ -19,6 +19,10 @@ pub struct OptRetrieveLanguages {
     #[structopt(long)]
     pub json: bool,
 
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
     /// Path to `snowchains.dhall`
     #[structopt(long)]
     pub config: Option<PathBuf>,
@@ -55,6 +59,7 @@ pub(crate) fn run(
 ) -> anyhow::Result<()> {
     let OptRetrieveLanguages {
         json,
+        http_timeout,
         config,
         color: _,
         service,
@@ -64,7 +69,8 @@ pub(crate) fn run(
 
     let crate::Context { cwd, mut shell } = ctx;
 
-    let (detected_target, _) = crate::config::detect_target(&cwd, config.as_deref())?;
+    let (detected_target, _) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
 
     let service = service
         .map(Ok)
@@ -78,7 +84,11 @@ pub(crate) fn run(
 
     let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
 
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
 
     let outcome =
         match service {
@@ -99,6 +109,8 @@ pub(crate) fn run(
                     credentials,
                     cookie_storage,
                     timeout,
+                    user_agent,
+                    headers,
                     shell: &shell,
                 })
             }
@@ -119,6 +131,8 @@ pub(crate) fn run(
                     credentials,
                     cookie_storage,
                     timeout,
+                    user_agent,
+                    headers,
                     shell: &shell,
                 })
             }
@@ -127,8 +141,12 @@ pub(crate) fn run(
                 credentials: (),
                 cookie_storage: (),
                 timeout,
+                user_agent,
+                headers,
                 shell: &mut shell,
             }),
+            PlatformKind::Aoj => bail!("`retrieve languages` for AOJ is not implemented"),
+            PlatformKind::Other => bail!("`other` is a local service and has no languages"),
         }?;
 
     if json {
@@ -1,4 +1,4 @@
-use anyhow::Context as _;
+use anyhow::{bail, Context as _};
 use snowchains_core::web::{
     Atcoder, AtcoderRetrieveSubmissionSummariesCredentials,
     AtcoderRetrieveSubmissionSummariesTarget, CookieStorage, PlatformKind,
@@ -15,6 +15,10 @@ use termcolor::WriteColor;
 
 #[derive(StructOpt, Debug)]
 pub struct OptRetrieveSubmissionSummaries {
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
     /// Path to `snowchains.dhall`
     #[structopt(long)]
     pub config: Option<PathBuf>,
@@ -41,6 +45,7 @@ pub(crate) fn run(
     ctx: crate::Context<impl BufRead, impl Write, impl WriteColor>,
 ) -> anyhow::Result<()> {
     let OptRetrieveSubmissionSummaries {
+        http_timeout,
         config,
         color: _,
         service,
@@ -49,7 +54,8 @@ pub(crate) fn run(
 
     let crate::Context { cwd, mut shell } = ctx;
 
-    let (detected_target, _) = crate::config::detect_target(&cwd, config.as_deref())?;
+    let (detected_target, _) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
 
     let service = service
         .map(Ok)
@@ -61,7 +67,10 @@ pub(crate) fn run(
     let contest = contest.or(detected_target.contest);
 
     let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
 
     match service {
         PlatformKind::Atcoder => {
@@ -82,6 +91,8 @@ pub(crate) fn run(
                     credentials,
                     cookie_storage,
                     timeout,
+                    user_agent,
+                    headers,
                     shell: &shell,
                 })?
             };
@@ -96,5 +107,7 @@ pub(crate) fn run(
         PlatformKind::Yukicoder => {
             todo!("`retrieve submission-summaries` for yukicoder is not implemented");
         }
+        PlatformKind::Aoj => bail!("`retrieve submission-summaries` is not supported for AOJ"),
+        PlatformKind::Other => bail!("`other` is a local service and has no submissions"),
     }
 }
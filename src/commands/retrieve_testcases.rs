@@ -1,22 +1,28 @@
 use crate::web::CaseConversions;
-use anyhow::Context as _;
+use anyhow::{bail, Context as _};
+use indexmap::IndexMap;
+use itertools::Itertools as _;
 use maplit::btreeset;
 use serde::Serialize;
 use snowchains_core::{
     color_spec,
     testsuite::{Additional, BatchTestSuite, TestSuite},
     web::{
-        Atcoder, AtcoderRetrieveFullTestCasesCredentials,
+        Aoj, AojRetrieveTestCasesTargets, Atcoder, AtcoderRetrieveFullTestCasesCredentials,
         AtcoderRetrieveSampleTestCasesCredentials, Codeforces,
-        CodeforcesRetrieveSampleTestCasesCredentials, CookieStorage, PlatformKind,
-        ProblemsInContest, RetrieveFullTestCases, RetrieveTestCases, Yukicoder,
+        CodeforcesRetrieveSampleTestCasesCredentials, ContestNotBegun, CookieStorage, PlatformKind,
+        ProblemsInContest, RetrieveFullTestCases, RetrieveTestCases, RetrieveTestCasesOutcome,
+        RetrieveTestCasesOutcomeProblemTextFiles, Yukicoder,
         YukicoderRetrieveFullTestCasesCredentials, YukicoderRetrieveTestCasesTargets,
     },
 };
 use std::{
     cell::RefCell,
+    collections::BTreeSet,
     io::{BufRead, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
 };
 use structopt::StructOpt;
 use strum::VariantNames as _;
@@ -29,10 +35,38 @@ pub struct OptRetrieveTestcases {
     #[structopt(long)]
     pub full: bool,
 
+    /// Records the number of the full test cases without downloading them
+    #[structopt(long, conflicts_with("full"))]
+    pub count: bool,
+
+    /// Overwrites the test suites instead of merging the test cases you added
+    #[structopt(long)]
+    pub overwrite: bool,
+
+    /// Skips the problems that already have test suites
+    #[structopt(long)]
+    pub skip_existing: bool,
+
+    /// Saves test suites even if they have no test cases
+    #[structopt(long)]
+    pub allow_empty: bool,
+
+    /// Waits for the contest to begin if it has not yet
+    #[structopt(long)]
+    pub wait: bool,
+
     /// Prints JSON data
     #[structopt(long)]
     pub json: bool,
 
+    /// Prints the URLs of the problems to stdout
+    #[structopt(long, conflicts_with("json"))]
+    pub print_url: bool,
+
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
     /// Path to `snowchains.dhall`
     #[structopt(long)]
     pub config: Option<PathBuf>,
@@ -61,6 +95,31 @@ pub struct OptRetrieveTestcases {
     /// Problem indexes (e.g. "a", "b", "c")
     #[structopt(short, long, value_name("STRING"))]
     pub problems: Option<Vec<String>>,
+
+    /// Contest IDs to download at once (e.g. "abc300,abc301")
+    #[structopt(
+        long,
+        value_name("STRING"),
+        use_delimiter(true),
+        conflicts_with_all(&["contest", "problems"])
+    )]
+    pub contests: Option<Vec<String>>,
+
+    /// File that lists contest IDs line by line
+    #[structopt(
+        long,
+        value_name("PATH"),
+        conflicts_with_all(&["contest", "problems", "contests"])
+    )]
+    pub contests_file: Option<PathBuf>,
+
+    /// Downloads only the problem at this URL (e.g. "https://atcoder.jp/contests/abc300/tasks/abc300_a")
+    #[structopt(
+        long,
+        value_name("URL"),
+        conflicts_with_all(&["service", "contest", "problems", "contests", "contests-file"])
+    )]
+    pub url: Option<Url>,
 }
 
 #[derive(Debug, Serialize)]
@@ -89,6 +148,7 @@ struct OutcomeProblem {
     url: Url,
     screen_name: Option<String>,
     display_name: String,
+    score: Option<u64>,
     test_suite: OutcomeProblemTestSuite,
 }
 
@@ -104,17 +164,35 @@ pub(crate) fn run(
 ) -> anyhow::Result<()> {
     let OptRetrieveTestcases {
         full,
+        count,
+        overwrite,
+        skip_existing,
+        allow_empty,
+        wait,
         json,
+        print_url,
+        http_timeout,
         config,
         color: _,
         service,
         contest,
         problems,
+        contests,
+        contests_file,
+        url,
     } = opt;
 
     let crate::Context { cwd, mut shell } = ctx;
 
-    let (detected_target, workspace) = crate::config::detect_target(&cwd, config.as_deref())?;
+    let (service, contest, problems) = if let Some(url) = &url {
+        let (service, contest, problem) = crate::web::parse_problem_url(url)?;
+        (Some(service), Some(contest), Some(vec![problem]))
+    } else {
+        (service, contest, problems)
+    };
+
+    let (detected_target, workspace) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
 
     let service = service
         .map(Ok)
@@ -125,111 +203,233 @@ pub(crate) fn run(
 
     let contest = contest.or(detected_target.contest);
 
+    let crate::config::Testfiles {
+        layout, gitignore, ..
+    } = crate::config::testfiles(&cwd, config.as_deref())?;
+
+    if gitignore == Some(true) {
+        add_to_gitignore(&workspace)?;
+    }
+
     let problems = match (problems.as_deref().unwrap_or(&[]), &detected_target.problem) {
         ([], None) => None,
         ([], Some(problem)) => Some(btreeset!(problem.clone())),
         (problems, _) => Some(problems.iter().cloned().collect()),
     };
 
-    let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let user_agent = session.user_agent();
+    let headers = session.headers()?;
+    let request_interval = session.request_interval()?;
+    let timeout = session.timeout(http_timeout)?;
 
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let options = DownloadOptions {
+        service,
+        full,
+        count,
+        overwrite,
+        skip_existing,
+        allow_empty,
+        wait,
+        json,
+        print_url,
+        layout,
+        workspace: &workspace,
+        user_agent: &user_agent,
+        headers: &headers,
+        request_interval,
+        timeout,
+    };
 
-    let outcome = match service {
-        PlatformKind::Atcoder => {
-            let shell = RefCell::new(&mut shell);
+    let contests = if let Some(contests_file) = contests_file {
+        let contests_file = cwd.join(contests_file.strip_prefix(".").unwrap_or(&contests_file));
+        let contests = crate::fs::read_to_string(contests_file)?
+            .lines()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        Some(contests)
+    } else {
+        contests
+    };
 
-            let targets = {
-                let contest = contest
-                    .clone()
-                    .with_context(|| "`contest` is required for AtCoder")?;
-                ProblemsInContest::Indexes { contest, problems }
-            };
+    let contests = if let Some(contests) = contests {
+        contests
+    } else {
+        return download(&mut shell, &options, contest, problems);
+    };
 
-            let credentials = AtcoderRetrieveSampleTestCasesCredentials {
-                username_and_password: &mut crate::web::credentials::atcoder_username_and_password(
-                    &shell,
-                ),
-            };
+    let mut failed = vec![];
 
-            let full = if full {
-                Some(RetrieveFullTestCases {
-                    credentials: AtcoderRetrieveFullTestCasesCredentials {
-                        dropbox_access_token: crate::web::credentials::dropbox_access_token()?,
-                    },
-                })
-            } else {
-                None
-            };
+    for contest in &contests {
+        shell.stderr.set_color(color_spec!(Bold))?;
+        writeln!(shell.stderr, "{}", contest)?;
+        shell.stderr.reset()?;
+        shell.stderr.flush()?;
 
-            Atcoder::exec(RetrieveTestCases {
-                targets,
-                credentials,
-                full,
-                cookie_storage,
-                timeout,
-                shell: &shell,
-            })
+        let result = download(&mut shell, &options, Some(contest.clone()), None);
+
+        if let Err(err) = result {
+            shell.warn(format!("{}: {:?}", contest, err))?;
+            failed.push(contest);
         }
-        PlatformKind::Codeforces => {
-            let shell = RefCell::new(&mut shell);
+    }
 
-            let targets = {
-                let contest = contest
-                    .clone()
-                    .with_context(|| "`contest` is required for Codeforces")?;
-                ProblemsInContest::Indexes { contest, problems }
-            };
+    writeln!(shell.stderr)?;
+    shell.stderr.set_color(color_spec!(Bold))?;
+    write!(shell.stderr, "Downloaded:")?;
+    shell.stderr.reset()?;
+    write!(shell.stderr, " ")?;
+    shell.stderr.set_color(color_spec!(Fg(Color::Green)))?;
+    write!(shell.stderr, "{} succeeded", contests.len() - failed.len())?;
+    shell.stderr.reset()?;
+    write!(shell.stderr, ", ")?;
+    let color = if failed.is_empty() {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    shell.stderr.set_color(color_spec!(Fg(color)))?;
+    write!(shell.stderr, "{} failed", failed.len())?;
+    shell.stderr.reset()?;
+    if !failed.is_empty() {
+        write!(shell.stderr, " ({})", failed.iter().format(", "))?;
+    }
+    writeln!(shell.stderr)?;
+    shell.stderr.flush()?;
 
-            let credentials = CodeforcesRetrieveSampleTestCasesCredentials {
-                username_and_password:
-                    &mut crate::web::credentials::codeforces_username_and_password(&shell),
-            };
+    if !failed.is_empty() {
+        bail!("Failed to download {} contest(s)", failed.len());
+    }
+    Ok(())
+}
 
-            Codeforces::exec(RetrieveTestCases {
-                targets,
-                credentials,
-                full: None,
-                cookie_storage,
-                timeout,
-                shell: &shell,
-            })
-        }
-        PlatformKind::Yukicoder => {
-            let targets = if let Some(contest) = &contest {
-                YukicoderRetrieveTestCasesTargets::Contest(contest.clone(), problems)
-            } else {
-                let nos = problems
-                    .with_context(|| "`contest` or `problem`s are required for yukicoder")?
-                    .iter()
-                    .map(|s| s.parse())
-                    .collect::<Result<_, _>>()
-                    .with_context(|| "`problem`s for yukicoder must be unsigned integer")?;
-                YukicoderRetrieveTestCasesTargets::ProblemNos(nos)
-            };
+/// Appends `/.snowchains/tests/` to `<workspace>/.gitignore` unless it is already ignored.
+fn add_to_gitignore(workspace: &Path) -> anyhow::Result<()> {
+    const ENTRY: &str = "/.snowchains/tests/";
 
-            let full = if full {
-                Some(RetrieveFullTestCases {
-                    credentials: YukicoderRetrieveFullTestCasesCredentials {
-                        api_key: crate::web::credentials::yukicoder_api_key(&mut shell)?,
-                    },
-                })
-            } else {
-                None
-            };
+    let path = workspace.join(".gitignore");
 
-            let shell = RefCell::new(&mut shell);
+    let content = if path.exists() {
+        crate::fs::read_to_string(&path)?
+    } else {
+        "".to_owned()
+    };
 
-            Yukicoder::exec(RetrieveTestCases {
-                targets,
-                credentials: (),
-                full,
-                cookie_storage: (),
-                timeout,
-                shell,
-            })
+    let ignored = content.lines().map(str::trim).any(|line| {
+        let line = line.trim_start_matches('/').trim_end_matches('/');
+        line == ".snowchains" || line == ".snowchains/tests"
+    });
+
+    if !ignored {
+        let mut content = content;
+        if !(content.is_empty() || content.ends_with('\n')) {
+            content += "\n";
+        }
+        content += ENTRY;
+        content += "\n";
+        crate::fs::write(&path, content, false)?;
+    }
+    Ok(())
+}
+
+/// Settings shared by every contest that `download` is called for.
+#[derive(Clone, Copy)]
+struct DownloadOptions<'a> {
+    service: PlatformKind,
+    full: bool,
+    count: bool,
+    overwrite: bool,
+    skip_existing: bool,
+    allow_empty: bool,
+    wait: bool,
+    json: bool,
+    print_url: bool,
+    layout: crate::config::TestfilesLayout,
+    workspace: &'a Path,
+    user_agent: &'a str,
+    headers: &'a IndexMap<String, String>,
+    request_interval: Option<Duration>,
+    timeout: Option<Duration>,
+}
+
+fn download(
+    shell: &mut crate::shell::Shell<impl BufRead, impl Write, impl WriteColor>,
+    options: &DownloadOptions<'_>,
+    contest: Option<String>,
+    problems: Option<BTreeSet<String>>,
+) -> anyhow::Result<()> {
+    let DownloadOptions {
+        service,
+        full,
+        count,
+        overwrite,
+        skip_existing,
+        allow_empty,
+        wait,
+        json,
+        print_url,
+        layout,
+        workspace,
+        user_agent,
+        headers,
+        request_interval,
+        timeout,
+    } = *options;
+
+    let test_suite_dir = workspace
+        .join(".snowchains")
+        .join("tests")
+        .join(service.to_kebab_case_str())
+        .join(contest.as_deref().unwrap_or(""));
+
+    let skip_problems = if skip_existing {
+        existing_problems(&test_suite_dir)?
+    } else {
+        BTreeSet::new()
+    };
+    let skipped = skip_problems
+        .iter()
+        .filter(|p| match &problems {
+            Some(problems) => problems.iter().any(|q| q.eq_ignore_ascii_case(p)),
+            None => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let outcome = loop {
+        let cookie_storage =
+            CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
+
+        let result = retrieve(
+            shell,
+            service,
+            contest.as_deref(),
+            problems.clone(),
+            full,
+            count,
+            skip_problems.clone(),
+            cookie_storage,
+            timeout,
+            Some(user_agent.to_owned()),
+            headers.clone(),
+            request_interval,
+        );
+
+        match result {
+            Err(err) if wait => match err.downcast_ref::<ContestNotBegun>() {
+                Some(not_begun) => {
+                    writeln!(shell.stderr, "{}. Waiting...", not_begun)?;
+                    shell.stderr.flush()?;
+                    // The problems are not always public at the very second.
+                    thread::sleep(not_begun.remaining() + Duration::from_secs(1));
+                }
+                None => return Err(err),
+            },
+            result => break result?,
         }
-    }?;
+    };
 
     let mut acc = Outcome {
         contest: outcome
@@ -262,20 +462,49 @@ pub(crate) fn run(
         url,
         screen_name,
         display_name,
+        score,
         mut test_suite,
-        text_files,
+        mut text_files,
         ..
     } in outcome.problems
     {
         let index = CaseConversions::new(index);
 
-        let path = workspace
-            .join(".snowchains")
-            .join("tests")
-            .join(service.to_kebab_case_str())
-            .join(contest.as_deref().unwrap_or(""))
-            .join(&index.kebab)
-            .with_extension("yml");
+        let path = crate::judge::existing_test_suite_file(&test_suite_dir, &index.kebab);
+
+        if !allow_empty
+            && text_files.is_empty()
+            && matches!(
+                &test_suite,
+                TestSuite::Batch(BatchTestSuite { cases, extend, systemtests: None, .. })
+                    if cases.is_empty() && extend.is_empty()
+            )
+        {
+            shell.warn(format!(
+                "{}: Found no test cases. Not saved. The problem page may have been changed or \
+                 be shown in a language the scraper does not expect. To save the empty test \
+                 suite anyway, add `--allow-empty`",
+                index.original,
+            ))?;
+            continue;
+        }
+
+        if layout == crate::config::TestfilesLayout::Directory && text_files.is_empty() {
+            if let TestSuite::Batch(BatchTestSuite { cases, .. }) = &mut test_suite {
+                text_files = cases
+                    .drain(..)
+                    .enumerate()
+                    .map(|(i, case)| {
+                        let name = case.name.unwrap_or_else(|| (i + 1).to_string());
+                        let text_files = RetrieveTestCasesOutcomeProblemTextFiles {
+                            r#in: case.r#in.to_string(),
+                            out: case.out.map(|out| out.to_string()),
+                        };
+                        (name, text_files)
+                    })
+                    .collect();
+            }
+        }
 
         let txt_path = |dir_file_name: &str, txt_file_name: &str| -> _ {
             path.with_file_name(&index.kebab)
@@ -284,9 +513,7 @@ pub(crate) fn run(
                 .with_extension("txt")
         };
 
-        for (name, snowchains_core::web::RetrieveTestCasesOutcomeProblemTextFiles { r#in, out }) in
-            &text_files
-        {
+        for (name, RetrieveTestCasesOutcomeProblemTextFiles { r#in, out }) in &text_files {
             crate::fs::write(txt_path("in", name), &r#in, true)?;
             if let Some(out) = out {
                 crate::fs::write(txt_path("out", name), out, true)?;
@@ -307,7 +534,14 @@ pub(crate) fn run(
             }
         }
 
-        crate::fs::write(&path, test_suite.to_yaml_pretty(), true)?;
+        if !overwrite && path.exists() {
+            let old = crate::fs::read_json_or_yaml(&path).with_context(|| {
+                "Could not merge the test cases. To discard them, add `--overwrite`"
+            })?;
+            test_suite = test_suite.merge(old);
+        }
+
+        crate::fs::write_test_suite(&path, &test_suite, true)?;
 
         shell.stderr.set_color(color_spec!(Bold))?;
         write!(shell.stderr, "{}:", index.original)?;
@@ -322,8 +556,12 @@ pub(crate) fn run(
             write!(
                 shell.stderr,
                 "{}",
-                path.with_file_name(format!("{{{index}.yml, {index}/}}", index = index.kebab))
-                    .display(),
+                path.with_file_name(format!(
+                    "{{{}, {}/}}",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    index.kebab,
+                ))
+                .display(),
             )
         }?;
         shell.stderr.reset()?;
@@ -346,14 +584,32 @@ pub(crate) fn run(
         write!(shell.stderr, "{}", msg)?;
         shell.stderr.reset()?;
 
+        if let Some(score) = score {
+            write!(shell.stderr, ", {} points", score)?;
+        }
+
+        if let TestSuite::Batch(BatchTestSuite {
+            systemtests: Some(systemtests),
+            ..
+        }) = &test_suite
+        {
+            write!(shell.stderr, ", {} system test cases", systemtests)?;
+        }
+
         writeln!(shell.stderr, ")")?;
         shell.stderr.flush()?;
 
+        if print_url {
+            writeln!(shell.stdout, "{}", url)?;
+            shell.stdout.flush()?;
+        }
+
         acc.problems.push(OutcomeProblem {
             index,
             url,
             screen_name,
             display_name,
+            score,
             test_suite: OutcomeProblemTestSuite {
                 path: path
                     .into_os_string()
@@ -364,6 +620,22 @@ pub(crate) fn run(
         });
     }
 
+    if skip_existing {
+        shell.stderr.set_color(color_spec!(Bold))?;
+        write!(
+            shell.stderr,
+            "Fetched {}, skipped {}",
+            acc.problems.len(),
+            skipped.len()
+        )?;
+        shell.stderr.reset()?;
+        if !skipped.is_empty() {
+            write!(shell.stderr, " ({})", skipped.iter().format(", "))?;
+        }
+        writeln!(shell.stderr)?;
+        shell.stderr.flush()?;
+    }
+
     if json {
         writeln!(shell.stdout, "{}", acc.to_json())?;
         shell.stdout.flush()?;
@@ -371,3 +643,210 @@ pub(crate) fn run(
 
     Ok(())
 }
+
+/// Retrieves the test cases with the core library without saving them.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn retrieve(
+    shell: &mut crate::shell::Shell<impl BufRead, impl Write, impl WriteColor>,
+    service: PlatformKind,
+    contest: Option<&str>,
+    problems: Option<BTreeSet<String>>,
+    full: bool,
+    count: bool,
+    skip_problems: BTreeSet<String>,
+    cookie_storage: CookieStorage,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    headers: IndexMap<String, String>,
+    request_interval: Option<Duration>,
+) -> anyhow::Result<RetrieveTestCasesOutcome> {
+    match service {
+        PlatformKind::Atcoder => {
+            let shell = RefCell::new(&mut *shell);
+
+            let targets = {
+                let contest = contest
+                    .map(ToOwned::to_owned)
+                    .with_context(|| "`contest` is required for AtCoder")?;
+                ProblemsInContest::Indexes { contest, problems }
+            };
+
+            let credentials = AtcoderRetrieveSampleTestCasesCredentials {
+                username_and_password: &mut crate::web::credentials::atcoder_username_and_password(
+                    &shell,
+                ),
+            };
+
+            let full = if full || count {
+                Some(RetrieveFullTestCases {
+                    credentials: AtcoderRetrieveFullTestCasesCredentials {
+                        dropbox_access_token: crate::web::credentials::dropbox_access_token()?,
+                    },
+                    count_only: count,
+                })
+            } else {
+                None
+            };
+
+            Atcoder::exec(RetrieveTestCases {
+                targets,
+                credentials,
+                full,
+                cookie_storage,
+                timeout,
+                user_agent,
+                headers,
+                request_interval,
+                skip_problems,
+                shell: &shell,
+            })
+        }
+        PlatformKind::Codeforces => {
+            let shell = RefCell::new(&mut *shell);
+
+            let targets = {
+                let contest = contest
+                    .map(ToOwned::to_owned)
+                    .with_context(|| "`contest` is required for Codeforces")?;
+                ProblemsInContest::Indexes { contest, problems }
+            };
+
+            let credentials = CodeforcesRetrieveSampleTestCasesCredentials {
+                username_and_password:
+                    &mut crate::web::credentials::codeforces_username_and_password(&shell),
+            };
+
+            Codeforces::exec(RetrieveTestCases {
+                targets,
+                credentials,
+                full: None,
+                cookie_storage,
+                timeout,
+                user_agent,
+                headers,
+                request_interval,
+                skip_problems,
+                shell: &shell,
+            })
+        }
+        PlatformKind::Yukicoder => {
+            let targets = if let Some(contest) = contest {
+                YukicoderRetrieveTestCasesTargets::Contest(contest.to_owned(), problems)
+            } else {
+                let nos = problems
+                    .with_context(|| "`contest` or `problem`s are required for yukicoder")?
+                    .iter()
+                    .map(|s| s.parse())
+                    .collect::<Result<_, _>>()
+                    .with_context(|| "`problem`s for yukicoder must be unsigned integer")?;
+                YukicoderRetrieveTestCasesTargets::ProblemNos(nos)
+            };
+
+            let full = if full || count {
+                Some(RetrieveFullTestCases {
+                    credentials: YukicoderRetrieveFullTestCasesCredentials {
+                        api_key: crate::web::credentials::yukicoder_api_key(shell)?,
+                    },
+                    count_only: count,
+                })
+            } else {
+                None
+            };
+
+            let shell = RefCell::new(&mut *shell);
+
+            Yukicoder::exec(RetrieveTestCases {
+                targets,
+                credentials: (),
+                full,
+                cookie_storage: (),
+                timeout,
+                user_agent,
+                headers,
+                request_interval,
+                skip_problems,
+                shell,
+            })
+        }
+        PlatformKind::Aoj => {
+            if full || count {
+                bail!("`--full` and `--count` are not supported for AOJ");
+            }
+
+            let targets = AojRetrieveTestCasesTargets::ProblemIds(
+                problems.with_context(|| "`problem`s are required for AOJ")?,
+            );
+
+            let shell = RefCell::new(&mut *shell);
+
+            Aoj::exec(RetrieveTestCases {
+                targets,
+                credentials: (),
+                full: None,
+                cookie_storage: (),
+                timeout,
+                user_agent,
+                headers,
+                request_interval,
+                skip_problems,
+                shell,
+            })
+        }
+        PlatformKind::Other => bail!(
+            "`other` is a local service. Write the test suites in `.snowchains/tests/other/` by hand",
+        ),
+    }
+}
+
+/// Returns the names of the test suites in `dir` (e.g. `a` for `a.yml`).
+fn existing_problems(dir: &Path) -> anyhow::Result<BTreeSet<String>> {
+    if !dir.exists() {
+        return Ok(BTreeSet::new());
+    }
+
+    let mut problems = BTreeSet::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Could not read `{}`", dir.display()))?
+    {
+        let path = entry?.path();
+        if let Some(file_name) = path.file_name().and_then(|s| s.to_str()) {
+            let stem = crate::judge::TEST_SUITE_EXTENSIONS
+                .iter()
+                .find_map(|ext| file_name.strip_suffix(ext)?.strip_suffix('.'));
+            if let Some(stem) = stem {
+                problems.insert(stem.to_owned());
+            }
+        }
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    #[test]
+    fn add_to_gitignore() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-retrieve-testcases-test-")
+            .tempdir()?;
+        let gitignore = tempdir.path().join(".gitignore");
+
+        super::add_to_gitignore(tempdir.path())?;
+        super::add_to_gitignore(tempdir.path())?;
+        assert_eq!("/.snowchains/tests/\n", fs::read_to_string(&gitignore)?);
+
+        fs::write(&gitignore, "/target")?;
+        super::add_to_gitignore(tempdir.path())?;
+        assert_eq!(
+            "/target\n/.snowchains/tests/\n",
+            fs::read_to_string(&gitignore)?,
+        );
+
+        fs::write(&gitignore, ".snowchains\n")?;
+        super::add_to_gitignore(tempdir.path())?;
+        assert_eq!(".snowchains\n", fs::read_to_string(&gitignore)?);
+
+        tempdir.close().map_err(Into::into)
+    }
+}
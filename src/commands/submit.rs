@@ -1,15 +1,32 @@
 use crate::config;
-use anyhow::{bail, Context as _};
-use human_size::Size;
-use snowchains_core::web::{
-    Atcoder, AtcoderSubmitCredentials, Codeforces, CodeforcesSubmitCredentials, CookieStorage,
-    PlatformKind, ProblemInContest, Submit, Yukicoder, YukicoderSubmitCredentials,
-    YukicoderSubmitTarget,
+use anyhow::{anyhow, bail, ensure, Context as _};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use snowchains_core::{
+    color_spec,
+    web::{
+        Atcoder, AtcoderSubmitCredentials, Codeforces, CodeforcesSubmitCredentials, CookieStorage,
+        PlatformKind, ProblemInContest, Submit, Yukicoder, YukicoderSubmitCredentials,
+        YukicoderSubmitTarget,
+    },
+};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    env,
+    ffi::OsString,
+    io::{self, BufRead, Write as _},
+    iter,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::{Duration, SystemTime},
 };
-use std::{cell::RefCell, env, io::BufRead, iter, path::PathBuf};
 use structopt::StructOpt;
 use strum::VariantNames as _;
-use termcolor::WriteColor;
+use termcolor::{Color, WriteColor};
+use url::Url;
 
 #[derive(StructOpt, Debug)]
 pub struct OptSubmit {
@@ -17,6 +34,14 @@ pub struct OptSubmit {
     #[structopt(long)]
     pub no_watch: bool,
 
+    /// Waits for the verdict and fails unless it is AC
+    #[structopt(long, conflicts_with("no-watch"))]
+    pub wait_for_accept: bool,
+
+    /// Seconds to wait for the verdict with `--wait-for-accept`
+    #[structopt(long, value_name("SECONDS"), default_value("600"))]
+    pub verdict_timeout: u64,
+
     /// Do not `compile` the code
     #[structopt(long)]
     pub no_judge: bool,
@@ -29,13 +54,45 @@ pub struct OptSubmit {
     #[structopt(long)]
     pub json: bool,
 
+    /// Prints the URL of the submission to stdout
+    #[structopt(long, conflicts_with("json"))]
+    pub print_url: bool,
+
+    /// Prints what would be submitted and the code to stdout without submitting it
+    #[structopt(long, conflicts_with_all(&["json", "print-url", "wait-for-accept"]))]
+    pub dry_run: bool,
+
+    /// Submits even if the code is identical to the last submission to the problem
+    #[structopt(long)]
+    pub force: bool,
+
+    /// Does not warn about submitting to a contest that has already ended
+    #[structopt(long)]
+    pub quiet: bool,
+
     /// Test for only the test cases
     #[structopt(long, value_name("NAME"))]
     pub testcases: Option<Vec<String>>,
 
     /// Display limit for the test
-    #[structopt(long, value_name("SIZE"), default_value("4KiB"))]
-    pub display_limit: Size,
+    #[structopt(long, value_name("SIZE|none"), default_value("4KiB"))]
+    pub display_limit: crate::DisplayLimit,
+
+    /// Submits this file instead of `src` (implies `--no-judge`)
+    #[structopt(long, value_name("PATH"))]
+    pub file: Option<PathBuf>,
+
+    /// Submits to the problem at this URL (e.g. "https://atcoder.jp/contests/abc300/tasks/abc300_a")
+    #[structopt(
+        long,
+        value_name("URL"),
+        conflicts_with_all(&["service", "contest", "problem"])
+    )]
+    pub url: Option<Url>,
+
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
 
     /// Path to `snowchains.dhall`
     #[structopt(long)]
@@ -76,11 +133,20 @@ pub(crate) fn run(
 ) -> anyhow::Result<()> {
     let OptSubmit {
         no_watch,
+        wait_for_accept,
+        verdict_timeout,
         no_judge,
         debug,
         json,
+        print_url,
+        dry_run,
+        force,
+        quiet,
         testcases,
         display_limit,
+        file,
+        url,
+        http_timeout,
         config,
         color,
         service,
@@ -91,19 +157,35 @@ pub(crate) fn run(
 
     let crate::Context { cwd, mut shell } = ctx;
 
+    let (service, contest, problem) = if let Some(url) = &url {
+        let (service, contest, problem) = crate::web::parse_problem_url(url)?;
+        (Some(service), Some(contest), Some(problem))
+    } else {
+        (service, contest, problem)
+    };
+
     let (
         config::Target {
             service,
             contest,
             problem,
+            alias,
             mode: _,
         },
         config::Language {
             src,
+            srcs,
             transpile,
             compile: _,
             run: _,
+            runEnv: _,
+            runArgs: _,
+            bundler,
+            replace,
+            includeDirs: include_dirs,
             languageId: language_id,
+            languageIds: language_ids,
+            template: _,
         },
         base_dir,
     ) = config::target_and_language(
@@ -120,15 +202,38 @@ pub(crate) fn run(
         },
     )?;
 
-    let code = crate::fs::read_to_string(base_dir.join(&src))?;
-    let language_id = language_id.with_context(|| "Missing `languageId`")?;
+    if wait_for_accept && service != PlatformKind::Atcoder {
+        bail!("`--wait-for-accept` is only supported for AtCoder");
+    }
 
-    if no_judge {
+    if service == PlatformKind::Other {
+        bail!("`other` is a local service. There is nowhere to submit to");
+    }
+
+    if service == PlatformKind::Aoj && !dry_run {
+        bail!("`submit` for AOJ is not implemented yet. Submit on the website instead");
+    }
+
+    let code = if let Some(file) = &file {
+        let file = cwd.join(file.strip_prefix(".").unwrap_or(file));
+        if !file.is_file() {
+            bail!("`{}` is not a file", file.display());
+        }
+        crate::fs::read_to_string(file)?
+    } else {
+        crate::fs::read_to_string(base_dir.join(&src))?
+    };
+    let language_id = config::language_id(service, language_ids, language_id)?
+        .with_context(|| format!("Missing `languageIds.{}` or `languageId`", service))?;
+
+    if file.is_some() {
+        // `--file` bypasses `src`, so there is nothing to transpile or test.
+    } else if no_judge {
         if let Some(transpile) = &transpile {
             crate::judge::transpile(
                 &mut shell.stderr,
                 &base_dir,
-                &src,
+                &crate::judge::src_paths(&base_dir, &src, &srcs.unwrap_or_default())?,
                 transpile,
                 shell.stdin_process_redirection,
                 shell.stdout_process_redirection,
@@ -163,23 +268,158 @@ pub(crate) fn run(
             .status()?;
 
         if !status.success() {
-            bail!("`snowchains j ...` failed ({})", status);
+            let err = anyhow!("`snowchains j ...` failed ({})", status);
+            return Err(match crate::Failure::from_exit_code(status.code()) {
+                Some(failure) => err.context(failure),
+                None => err,
+            });
+        }
+    }
+
+    let code = match &replace {
+        Some(replace) => {
+            config::Replace::apply(replace, &code).with_context(|| "Failed to apply `replace`")?
+        }
+        None => code,
+    };
+
+    let code = match (&bundler, &file) {
+        (Some(bundler), None) => bundle(bundler, &base_dir, &code, &mut shell)
+            .with_context(|| "Failed to bundle the code with `bundler`")?,
+        _ => code,
+    };
+
+    let code = match (&include_dirs, &file) {
+        (Some(include_dirs), None) => {
+            let include_dirs = include_dirs
+                .iter()
+                .map(|dir| base_dir.join(dir.strip_prefix("./").unwrap_or(dir)))
+                .collect::<Vec<_>>();
+            let src = base_dir.join(src.strip_prefix("./").unwrap_or(&src));
+            expand_includes(&code, &src, &include_dirs)
+                .with_context(|| "Failed to expand `#include`s")?
+        }
+        _ => code,
+    };
+
+    let code = match config::submit_normalize_newlines(&cwd, config.as_deref())? {
+        Some(newline) => newline.normalize(&code),
+        None => code,
+    };
+
+    if let Some(max_bytes) = config::submit_max_bytes(&cwd, config.as_deref(), service)? {
+        ensure!(
+            code.len() as u64 <= max_bytes,
+            "The code is {} bytes, which exceeds `submit.maxBytes.{}` ({} bytes). Shorten the code \
+             (e.g. remove unused library code from `bundler`'s output) or raise the limit",
+            code.len(),
+            service.to_kebab_case_str(),
+            max_bytes,
+        );
+    }
+
+    let last_submission_path = base_dir
+        .join(".snowchains")
+        .join("last_submission")
+        .join(service.to_kebab_case_str())
+        .join(contest.as_deref().unwrap_or(""))
+        .join(&problem)
+        .with_extension("json");
+
+    let code_sha256 = format!("{:x}", Sha256::digest(code.as_ref()));
+
+    if !(dry_run || force) && last_submission_path.exists() {
+        let LastSubmission {
+            sha256,
+            submitted_at,
+        } = crate::fs::read_json(&last_submission_path)?;
+
+        ensure!(
+            sha256 != code_sha256,
+            "The code is identical to the last one submitted to `{}` (at {}). Pass `--force` to \
+             submit it anyway",
+            problem,
+            submitted_at,
+        );
+    }
+
+    // Services know the problem by the name the user typed, not by what it is aliased to.
+    let problem = alias.unwrap_or(problem);
+
+    if dry_run {
+        let path = match &file {
+            Some(file) => cwd.join(file.strip_prefix(".").unwrap_or(file)),
+            None => base_dir.join(src.strip_prefix("./").unwrap_or(&src)),
+        };
+
+        let target = || -> anyhow::Result<_> {
+            Ok(if let Some(url) = url.clone() {
+                ProblemInContest::Url { url }
+            } else {
+                ProblemInContest::Index {
+                    contest: contest
+                        .clone()
+                        .with_context(|| format!("`contest` is required for {}", service))?,
+                    problem: problem.clone(),
+                }
+            })
+        };
+
+        let url = match service {
+            PlatformKind::Atcoder => snowchains_core::web::atcoder_submit_url(&target()?)?,
+            PlatformKind::Codeforces => snowchains_core::web::codeforces_submit_url(&target()?)?,
+            PlatformKind::Yukicoder => if let Some(contest) = &contest {
+                YukicoderSubmitTarget::Contest(contest.clone(), problem.clone())
+            } else {
+                YukicoderSubmitTarget::from_problem_no(&problem)
+            }
+            .url(),
+            PlatformKind::Aoj => snowchains_core::web::aoj_problem_url(&problem),
+            PlatformKind::Other => unreachable!(),
+        };
+
+        for (name, value) in &[
+            ("File:", path.display().to_string()),
+            ("Language ID:", language_id.clone()),
+            ("URL:", url.to_string()),
+            ("Size:", format!("{} bytes", code.len())),
+        ] {
+            shell
+                .stderr
+                .set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+            write!(shell.stderr, "{}", name)?;
+            shell.stderr.reset()?;
+            writeln!(shell.stderr, " {}", value)?;
         }
+        writeln!(shell.stderr, "Not submitted because of `--dry-run`.")?;
+        shell.stderr.flush()?;
+
+        write!(shell.stdout, "{}", code)?;
+        return shell.stdout.flush().map_err(Into::into);
     }
 
     let watch_submission = !no_watch;
+    let watch_timeout = Some(Duration::from_secs(verdict_timeout)).filter(|_| wait_for_accept);
 
     let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
 
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
 
     let outcome = match service {
         PlatformKind::Atcoder => {
             let shell = RefCell::new(&mut shell);
 
-            let target = ProblemInContest::Index {
-                contest: contest.with_context(|| "`contest` is required for AtCoder")?,
-                problem,
+            let target = if let Some(url) = url {
+                ProblemInContest::Url { url }
+            } else {
+                ProblemInContest::Index {
+                    contest: contest.with_context(|| "`contest` is required for AtCoder")?,
+                    problem,
+                }
             };
 
             let credentials = AtcoderSubmitCredentials {
@@ -194,8 +434,12 @@ pub(crate) fn run(
                 language_id,
                 code,
                 watch_submission,
+                watch_timeout,
+                warn_if_contest_ended: !quiet,
                 cookie_storage,
                 timeout,
+                user_agent,
+                headers,
                 shell: &shell,
             })
         }
@@ -223,8 +467,12 @@ pub(crate) fn run(
                 language_id,
                 code,
                 watch_submission,
+                watch_timeout,
+                warn_if_contest_ended: !quiet,
                 cookie_storage,
                 timeout,
+                user_agent,
+                headers,
                 shell: &shell,
             })
         }
@@ -247,17 +495,242 @@ pub(crate) fn run(
                 language_id,
                 code,
                 watch_submission,
+                watch_timeout,
+                warn_if_contest_ended: !quiet,
                 cookie_storage: (),
                 timeout,
+                user_agent,
+                headers,
                 shell,
             })
         }
+        PlatformKind::Aoj => {
+            bail!("`submit` for AOJ is not implemented yet. Submit on the website instead")
+        }
+        PlatformKind::Other => unreachable!(),
     }?;
 
+    let last_submission = LastSubmission {
+        sha256: code_sha256,
+        submitted_at: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+    };
+    crate::fs::write_json(&last_submission_path, last_submission, true)?;
+
     if json {
         write!(shell.stdout, "{}", outcome.to_json())?;
         shell.stdout.flush()?;
+    } else if print_url {
+        writeln!(shell.stdout, "{}", outcome.submission_url)?;
+        shell.stdout.flush()?;
+    }
+
+    if wait_for_accept {
+        match outcome.verdict.as_deref() {
+            Some("AC") => {}
+            Some(verdict) => {
+                return Err(
+                    anyhow!("The verdict is {}", verdict).context(crate::Failure::NotAccepted)
+                );
+            }
+            None => {
+                return Err(anyhow!("Could not get the verdict in {}s", verdict_timeout)
+                    .context(crate::Failure::VerdictUnknown));
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Saved to `.snowchains/last_submission/` after each submission.
+#[derive(Deserialize, Serialize)]
+struct LastSubmission {
+    sha256: String,
+    submitted_at: String,
+}
+
+/// Inlines `#include "..."` recursively. `<...>` ones are left as they are.
+///
+/// A header is searched for in the directory of the including file, then in `include_dirs`. Each
+/// header is inlined only once.
+fn expand_includes(code: &str, src: &Path, include_dirs: &[PathBuf]) -> anyhow::Result<String> {
+    let include = Regex::new(r#"\A\s*#\s*include\s*"([^"]+)""#).unwrap();
+    let mut expanded = "".to_owned();
+    let mut visited = HashSet::new();
+    expand(
+        code,
+        src,
+        include_dirs,
+        &include,
+        &mut visited,
+        &mut expanded,
+    )?;
+    return Ok(expanded);
+
+    fn expand(
+        code: &str,
+        path: &Path,
+        include_dirs: &[PathBuf],
+        include: &Regex,
+        visited: &mut HashSet<PathBuf>,
+        expanded: &mut String,
+    ) -> anyhow::Result<()> {
+        for line in code.lines() {
+            let header = match include.captures(line) {
+                Some(caps) => caps[1].to_owned(),
+                None => {
+                    *expanded += line;
+                    *expanded += "\n";
+                    continue;
+                }
+            };
+
+            let header_path = path
+                .parent()
+                .into_iter()
+                .chain(include_dirs.iter().map(Deref::deref))
+                .map(|dir| dir.join(&header))
+                .find(|p| p.is_file())
+                .with_context(|| {
+                    format!(
+                        "`{}` not found (included from `{}`)",
+                        header,
+                        path.display()
+                    )
+                })?;
+
+            let header_path = header_path
+                .canonicalize()
+                .with_context(|| format!("Could not canonicalize `{}`", header_path.display()))?;
+
+            if visited.insert(header_path.clone()) {
+                let content = crate::fs::read_to_string(&header_path)?;
+                expand(
+                    &content,
+                    &header_path,
+                    include_dirs,
+                    include,
+                    visited,
+                    expanded,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs `bundler` in `base_dir` with `code` as its stdin, and returns its stdout.
+fn bundle(
+    bundler: &config::Command,
+    base_dir: &Path,
+    code: &str,
+    shell: &mut crate::shell::Shell<impl Sized, impl Sized, impl WriteColor>,
+) -> anyhow::Result<String> {
+    let mut tempfile = None;
+
+    let (program, args): (OsString, Vec<OsString>) = match bundler {
+        config::Command::Args(args) => {
+            let program = args.first().with_context(|| "`bundler` is empty")?;
+            (program.into(), args[1..].iter().map(Into::into).collect())
+        }
+        config::Command::Script(config::Script {
+            program,
+            extension,
+            content,
+        }) => {
+            let mut file = tempfile::Builder::new()
+                .prefix("snowchains-bundler")
+                .suffix(&format!(".{}", extension))
+                .tempfile()?;
+            file.write_all(content.as_ref())?;
+            file.flush()?;
+            let args = vec![file.path().into()];
+            tempfile = Some(file);
+            (program.into(), args)
+        }
+    };
+
+    shell.stderr.set_color(color_spec!(Bold))?;
+    write!(shell.stderr, "Bundling...")?;
+    shell.stderr.reset()?;
+    writeln!(shell.stderr)?;
+    shell.stderr.flush()?;
+
+    let mut child = std::process::Command::new(&program)
+        .args(&args)
+        .current_dir(base_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr((shell.stderr_process_redirection)())
+        .spawn()
+        .with_context(|| format!("Could not execute {:?}", program))?;
+
+    let mut stdin = child.stdin.take().expect("should be piped");
+    let output = crossbeam_utils::thread::scope(|scope| {
+        let written = scope.spawn(move |_| stdin.write_all(code.as_ref()));
+        let output = child.wait_with_output()?;
+        // Bundlers that read the files in `base_dir` do not read the input at all.
+        match written.join().expect("should not panic") {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err),
+            _ => Ok(output),
+        }
+    })
+    .expect("should not panic")?;
+
+    if let Some(tempfile) = tempfile {
+        tempfile.close()?;
+    }
+
+    if !output.status.success() {
+        bail!("{:?} failed ({})", program, output.status);
+    }
+
+    String::from_utf8(output.stdout).with_context(|| "The output is not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    #[test]
+    fn expand_includes() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-submit-test-")
+            .tempdir()?;
+        let include_dir = tempdir.path().join("include");
+        fs::create_dir_all(include_dir.join("lib"))?;
+        fs::write(
+            include_dir.join("lib").join("a.hpp"),
+            "#include \"b.hpp\"\nint a;\n",
+        )?;
+        fs::write(include_dir.join("lib").join("b.hpp"), "int b;\n")?;
+        fs::write(tempdir.path().join("local.hpp"), "int local;\n")?;
+
+        let code = r#"#include <iostream>
+#include "local.hpp"
+#include "lib/a.hpp"
+#include "lib/b.hpp"
+int main() {}
+"#;
+
+        let expanded = super::expand_includes(
+            code,
+            &tempdir.path().join("main.cpp"),
+            std::slice::from_ref(&include_dir),
+        )?;
+
+        assert_eq!(
+            "#include <iostream>\nint local;\nint b;\nint a;\nint main() {}\n",
+            expanded,
+        );
+
+        assert!(super::expand_includes(
+            "#include \"nonexistent.hpp\"\n",
+            &tempdir.path().join("main.cpp"),
+            &[include_dir],
+        )
+        .is_err());
+
+        tempdir.close().map_err(Into::into)
+    }
+}
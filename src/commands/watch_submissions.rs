@@ -1,4 +1,4 @@
-use anyhow::Context as _;
+use anyhow::{bail, Context as _};
 use snowchains_core::web::{
     Atcoder, AtcoderWatchSubmissionsCredentials, AtcoderWatchSubmissionsTarget, CookieStorage,
     PlatformKind, WatchSubmissions,
@@ -10,6 +10,10 @@ use termcolor::WriteColor;
 
 #[derive(StructOpt, Debug)]
 pub struct OptWatchSubmissions {
+    /// Timeout of each HTTP request in seconds (`none` to disable)
+    #[structopt(long, value_name("SECONDS|none"))]
+    pub http_timeout: Option<crate::HttpTimeout>,
+
     /// Path to `snowchains.dhall`
     #[structopt(long)]
     pub config: Option<PathBuf>,
@@ -36,6 +40,7 @@ pub(crate) fn run(
     ctx: crate::Context<impl BufRead, impl Sized, impl WriteColor>,
 ) -> anyhow::Result<()> {
     let OptWatchSubmissions {
+        http_timeout,
         config,
         color: _,
         service,
@@ -44,7 +49,8 @@ pub(crate) fn run(
 
     let crate::Context { cwd, mut shell } = ctx;
 
-    let (detected_target, _) = crate::config::detect_target(&cwd, config.as_deref())?;
+    let (detected_target, _) =
+        crate::config::detect_target(&cwd, config.as_deref(), service, contest.as_deref())?;
 
     let service = service
         .map(Ok)
@@ -55,7 +61,10 @@ pub(crate) fn run(
     let contest = contest.or(detected_target.contest);
 
     let cookie_storage = CookieStorage::with_jsonl(crate::web::credentials::cookie_store_path()?)?;
-    let timeout = Some(crate::web::SESSION_TIMEOUT);
+    let session = crate::config::session(&cwd, config.as_deref())?;
+    let timeout = session.timeout(http_timeout)?;
+    let user_agent = Some(session.user_agent());
+    let headers = session.headers()?;
 
     match service {
         PlatformKind::Atcoder => {
@@ -76,10 +85,14 @@ pub(crate) fn run(
                 credentials,
                 cookie_storage,
                 timeout,
+                user_agent,
+                headers,
                 shell: &shell,
             })
         }
         PlatformKind::Codeforces => todo!(),
         PlatformKind::Yukicoder => todo!(),
+        PlatformKind::Aoj => bail!("`watch submissions` is not supported for AOJ"),
+        PlatformKind::Other => bail!("`other` is a local service and has no submissions"),
     }
 }
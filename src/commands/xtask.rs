@@ -1,10 +1,14 @@
 use crate::config;
 use anyhow::bail;
-use std::{ffi::OsString, io::Write as _};
+use std::{ffi::OsString, io::Write as _, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 pub struct OptXtask {
+    /// Path to `snowchains.dhall`
+    #[structopt(long)]
+    pub config: Option<PathBuf>,
+
     /// Name
     pub subcommand: String,
 
@@ -17,7 +21,11 @@ pub(crate) fn run(
     opt: OptXtask,
     ctx: crate::Context<impl Sized, impl Sized, impl Sized>,
 ) -> anyhow::Result<()> {
-    let OptXtask { subcommand, args } = opt;
+    let OptXtask {
+        config,
+        subcommand,
+        args,
+    } = opt;
 
     let crate::Context {
         cwd,
@@ -34,7 +42,7 @@ pub(crate) fn run(
         program,
         extension,
         content,
-    } = config::xtask(&cwd, None, &subcommand)?;
+    } = config::xtask(&cwd, config.as_deref(), &subcommand)?;
 
     let mut tempfile = tempfile::Builder::new()
         .prefix(&format!("snowchains-xtask-{}", subcommand))
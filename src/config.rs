@@ -1,28 +1,43 @@
 // https://github.com/Nadrieril/dhall-rust/pull/215
 #![allow(redundant_semicolons)]
 
+use crate::web::CaseConversions;
 use anyhow::{anyhow, bail, ensure, Context as _};
 use dhall::syntax::InterpolatedText;
-use heck::{CamelCase as _, KebabCase as _, MixedCase as _, SnakeCase as _};
 use indexmap::IndexMap;
 use itertools::Itertools as _;
 use maplit::hashmap;
+use regex::Regex;
 use serde::Deserialize;
 use serde_dhall::{SimpleType, StaticType};
-use snowchains_core::web::PlatformKind;
+use snowchains_core::{testsuite::Match, web::PlatformKind};
 use std::{
     collections::BTreeMap,
     convert::Infallible,
-    fmt,
+    env, fmt, iter, mem,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
+/// Evaluates the detection functions.
+///
+/// `contest` and `problem` are left `None` if they were detected for another service or contest
+/// than `cli_opt_service` or `cli_opt_contest`.
 pub(crate) fn detect_target(
     cwd: &Path,
     rel_path: Option<&Path>,
+    cli_opt_service: Option<PlatformKind>,
+    cli_opt_contest: Option<&str>,
 ) -> anyhow::Result<(Detected, PathBuf)> {
     let path = find_snowchains_dhall(cwd, rel_path)?;
-    let detected = Detected::load_and_eval(cwd, &path)?;
+    let mut detected = Detected::load_and_eval(cwd, &path)?;
+    let (contest, problem) = detected.contest_and_problem_for(cli_opt_service, cli_opt_contest);
+    let (contest, problem) = (
+        contest.map(ToOwned::to_owned),
+        problem.map(ToOwned::to_owned),
+    );
+    detected.contest = contest;
+    detected.problem = problem;
     let dir = Path::new(&path)
         .parent()
         .unwrap_or_else(|| path.as_ref())
@@ -30,6 +45,27 @@ pub(crate) fn detect_target(
     Ok((detected, dir))
 }
 
+/// Resolves the target without evaluating `languages`.
+pub(crate) fn target(
+    cwd: &Path,
+    rel_path: Option<&Path>,
+    cli_opt_service: Option<PlatformKind>,
+    cli_opt_contest: Option<&str>,
+    cli_opt_problem: Option<&str>,
+) -> anyhow::Result<Target> {
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+
+    let detected = Detected::load_and_eval(cwd, &path)?;
+    let mut target = detected.merge_with_cli_options(
+        cli_opt_service,
+        cli_opt_contest,
+        cli_opt_problem,
+        Mode::Debug,
+    )?;
+    target.resolve_alias(&aliases(&path)?)?;
+    Ok(target)
+}
+
 pub(crate) fn target_and_language(
     cwd: &Path,
     rel_path: Option<&Path>,
@@ -41,31 +77,29 @@ pub(crate) fn target_and_language(
 ) -> anyhow::Result<(Target, Language, PathBuf)> {
     let path = find_snowchains_dhall(cwd, rel_path)?;
 
-    let (target, language_name) = Detected::load_and_eval(cwd, &path)?.merge_with_cli_options(
+    let detected = Detected::load_and_eval(cwd, &path)?;
+    let mut target = detected.merge_with_cli_options(
         cli_opt_service,
         cli_opt_contest,
         cli_opt_problem,
-        cli_opt_language,
         cli_opt_mode,
     )?;
+    target.resolve_alias(&aliases(&path)?)?;
+    let language_name = detected.language_name(cli_opt_language)?;
 
-    let mut languages = serde_dhall::from_str(&format!(
-        "let target = {} let config = {} in config.languages target",
-        target.to_dhall_expr(),
-        path,
-    ))
-    .parse::<BTreeMap<String, Language>>()
-    .with_context(|| format!("Could not evaluate `{}`", path))?;
+    let mut languages = languages(&path, &target)?;
 
     let expected_names = languages.keys().join(", ");
 
-    let language = languages.remove(&language_name).with_context(|| {
+    let mut language = languages.remove(&language_name).with_context(|| {
         format!(
             "The language `{}` not found. Expected one of [{}]",
             language_name, expected_names,
         )
     })?;
 
+    language.expand_env_vars(&language_name)?;
+
     let dir = Path::new(&path)
         .parent()
         .unwrap_or_else(|| path.as_ref())
@@ -74,6 +108,98 @@ pub(crate) fn target_and_language(
     Ok((target, language, dir))
 }
 
+/// Evaluates every part of the config for the target without executing anything.
+///
+/// Returns the path to the config and the names of the languages.
+pub(crate) fn check(
+    cwd: &Path,
+    rel_path: Option<&Path>,
+    cli_opt_service: Option<PlatformKind>,
+    cli_opt_contest: Option<&str>,
+    cli_opt_problem: Option<&str>,
+) -> anyhow::Result<(String, Vec<String>)> {
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+
+    let detected = Detected::load_and_eval(cwd, &path)?;
+
+    let aliases = aliases(&path)?;
+
+    let mut language_names = vec![];
+
+    for &mode in &[Mode::Debug, Mode::Release] {
+        let mut target = detected.merge_with_cli_options(
+            cli_opt_service,
+            cli_opt_contest,
+            cli_opt_problem,
+            mode,
+        )?;
+        target.resolve_alias(&aliases)?;
+
+        for (name, mut language) in languages(&path, &target)? {
+            language.expand_env_vars(&name)?;
+            language.check_paths(&name)?;
+            if !language_names.contains(&name) {
+                language_names.push(name);
+            }
+        }
+    }
+
+    if let Some(language) = &detected.language {
+        ensure!(
+            language_names.contains(language),
+            "`detectLanguageFromRelativePathSegments` returned `{}`, which is not in `languages`",
+            language,
+        );
+    }
+
+    testfiles(cwd, rel_path)?;
+    let session = session(cwd, rel_path)?;
+    session.request_interval()?;
+    session.headers()?;
+    submit_normalize_newlines(cwd, rel_path)?;
+
+    serde_dhall::from_str(&format!("let config = {} in config.xtask", path))
+        .type_annotation(&map_annot(SimpleType::Text, Script::static_type()))
+        .parse::<IndexMap<String, Script>>()
+        .with_context(|| format!("Could not evalute `xtask` in `{}`", path))?;
+
+    Ok((path, language_names))
+}
+
+/// Evaluates `languages` for the target.
+///
+/// Returns the directory of the config, the name of the detected language, and the languages.
+pub(crate) fn languages_for_target(
+    cwd: &Path,
+    rel_path: Option<&Path>,
+    cli_opt_service: Option<PlatformKind>,
+    cli_opt_contest: Option<&str>,
+    cli_opt_problem: Option<&str>,
+) -> anyhow::Result<(PathBuf, Option<String>, BTreeMap<String, Language>)> {
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+
+    let detected = Detected::load_and_eval(cwd, &path)?;
+    let mut target = detected.merge_with_cli_options(
+        cli_opt_service,
+        cli_opt_contest,
+        cli_opt_problem,
+        Mode::Debug,
+    )?;
+    target.resolve_alias(&aliases(&path)?)?;
+
+    let mut languages = languages(&path, &target)?;
+    for (name, language) in &mut languages {
+        language.expand_env_vars(name)?;
+    }
+
+    let dir = Path::new(&path)
+        .parent()
+        .unwrap_or_else(|| path.as_ref())
+        .to_owned();
+
+    Ok((dir, detected.language, languages))
+}
+
 pub(crate) fn xtask(cwd: &Path, rel_path: Option<&Path>, name: &str) -> anyhow::Result<Script> {
     let path = find_snowchains_dhall(cwd, rel_path)?;
 
@@ -93,6 +219,162 @@ pub(crate) fn xtask(cwd: &Path, rel_path: Option<&Path>, name: &str) -> anyhow::
     })
 }
 
+pub(crate) fn testfiles(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<Testfiles> {
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+    eval_field_or_default(
+        &path,
+        "testfiles",
+        "{ layout = < Suite | Directory >.Suite \
+         , gitignore = None Bool \
+         , defaultMatch = None < Exact | SplitWhitespace | Lines | Float | Binary > \
+         }",
+    )
+}
+
+pub(crate) fn session(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<Session> {
+    if rel_path.is_none() && !cwd.ancestors().any(|p| p.join("snowchains.dhall").exists()) {
+        return Ok(Session {
+            userAgent: None,
+            requestInterval: None,
+            timeout: None,
+            headers: None,
+        });
+    }
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+    eval_field_or_default(
+        &path,
+        "session",
+        "{ userAgent = None Text \
+         , requestInterval = None Text \
+         , timeout = None Text \
+         , headers = None (List { mapKey : Text, mapValue : Text }) \
+         }",
+    )
+}
+
+/// Evaluates `config.hooks`.
+pub(crate) fn hooks(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<Hooks> {
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+    let command_type = "< Args : List Text \
+                        | Script : { program : Text, extension : Text, content : Text } \
+                        >";
+    let mut hooks = eval_field_or_default::<Hooks>(
+        &path,
+        "hooks",
+        &format!(
+            "{{ onPass = None {} , onFail = None {} }}",
+            command_type, command_type,
+        ),
+    )?;
+    for hook in hooks.onPass.iter_mut().chain(&mut hooks.onFail) {
+        hook.expand_env_vars()?;
+    }
+    Ok(hooks)
+}
+
+/// Evaluates `config.submit.maxBytes`, the maximum code length in bytes for each service (e.g.
+/// `toMap { atcoder = 524288 }`).
+pub(crate) fn submit_max_bytes(
+    cwd: &Path,
+    rel_path: Option<&Path>,
+    service: PlatformKind,
+) -> anyhow::Result<Option<u64>> {
+    let Submit { maxBytes, .. } = submit(cwd, rel_path)?;
+    Ok(maxBytes.and_then(|TextMap(m)| m.get(service.to_kebab_case_str()).copied()))
+}
+
+fn submit(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<Submit> {
+    let path = find_snowchains_dhall(cwd, rel_path)?;
+    eval_field_or_default(
+        &path,
+        "submit",
+        "{ maxBytes = None (List { mapKey : Text, mapValue : Natural }) \
+         , normalizeNewlines = None < Lf | Crlf > \
+         }",
+    )
+}
+
+/// Evaluates `config.submit.normalizeNewlines` (e.g. `Some < Lf | Crlf >.Lf`).
+pub(crate) fn submit_normalize_newlines(
+    cwd: &Path,
+    rel_path: Option<&Path>,
+) -> anyhow::Result<Option<Newline>> {
+    Ok(submit(cwd, rel_path)?.normalizeNewlines)
+}
+
+/// Evaluates `config.aliases`, which maps short problem names to the ones used for `src` and the test suites.
+fn aliases(path: &str) -> anyhow::Result<IndexMap<String, String>> {
+    serde_dhall::from_str(&format!(
+        "let config = {} in ({{ aliases = [] : List {{ mapKey : Text, mapValue : Text }} }} ⫽ config).aliases",
+        path,
+    ))
+    .type_annotation(&map_annot(SimpleType::Text, SimpleType::Text))
+    .parse()
+    .with_context(|| format!("Could not evalute `aliases` in `{}`", path))
+}
+
+/// `Service` in the schema.
+const SERVICE_TYPE: &str = "< Atcoder | Codeforces | Yukicoder | Aoj | Other >";
+
+/// Older `Service`s, newest first. Configs written against them keep working for the services
+/// they have.
+const LEGACY_SERVICE_TYPES: &[&str] = &[
+    "< Atcoder | Codeforces | Yukicoder | Aoj >",
+    "< Atcoder | Codeforces | Yukicoder >",
+];
+
+fn languages(path: &str, target: &Target) -> anyhow::Result<BTreeMap<String, Language>> {
+    let eval = |service_type: &str| {
+        serde_dhall::from_str(&format!(
+            "let target = {} let config = {} in config.languages target",
+            target.to_dhall_expr(service_type),
+            path,
+        ))
+        .parse()
+        .map_err(anyhow::Error::from)
+    };
+
+    let err = match eval(SERVICE_TYPE) {
+        Ok(languages) => return Ok(languages),
+        Err(err) => err,
+    };
+
+    let service = target.service.to_pascal_case_str();
+
+    for service_type in LEGACY_SERVICE_TYPES {
+        let has_service = service_type
+            .trim_matches(|c| c == '<' || c == '>')
+            .split('|')
+            .any(|s| s.trim() == service);
+
+        if has_service {
+            if let Ok(languages) = eval(service_type) {
+                return Ok(languages);
+            }
+        }
+    }
+
+    Err(err).with_context(|| format!("Could not evaluate `{}`", path))
+}
+
+/// Evaluates `config.<field>`, falling back to `default` if the config does not have the field.
+///
+/// `default` is also merged under the value so that configs written before a field was added
+/// keep working.
+fn eval_field_or_default<T: serde_dhall::FromDhall + StaticType>(
+    path: &str,
+    field: &str,
+    default: &str,
+) -> anyhow::Result<T> {
+    serde_dhall::from_str(&format!(
+        "let config = {} let default = {} in default ⫽ ({{ {} = default }} ⫽ config).{}",
+        path, default, field, field,
+    ))
+    .static_type_annotation()
+    .parse()
+    .with_context(|| format!("Could not evaluate `{}` in `{}`", field, path))
+}
+
 fn find_snowchains_dhall(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<String> {
     let path = if let Some(rel_path) = rel_path {
         let rel_path = rel_path.strip_prefix(".").unwrap_or(rel_path);
@@ -126,6 +408,53 @@ fn find_snowchains_dhall(cwd: &Path, rel_path: Option<&Path>) -> anyhow::Result<
     Ok(path)
 }
 
+/// Expands `${env:NAME}` into the value of the environment variable `NAME`.
+///
+/// `${` starts an interpolation in Dhall, so it is written as `"\${env:NAME}"` in double-quoted
+/// text and as `''${env:NAME}` in single-quoted text. Unlike `env:NAME as Text` of Dhall, which is
+/// resolved when the config is evaluated, this is expanded only when the field is used.
+fn expand_env_vars(s: &mut String) -> anyhow::Result<()> {
+    if !s.contains("${env:") {
+        return Ok(());
+    }
+
+    let mut expanded = "".to_owned();
+    let mut rest = &**s;
+
+    while let Some(start) = rest.find("${env:") {
+        expanded += &rest[..start];
+        rest = &rest[start + "${env:".len()..];
+
+        let end = rest
+            .find('}')
+            .with_context(|| format!("Unclosed `${{env:...}}` in {:?}", s))?;
+        let name = &rest[..end];
+
+        expanded += &env::var(name)
+            .with_context(|| format!("Environment variable `{}` is not set", name))?;
+        rest = &rest[end + 1..];
+    }
+
+    expanded += rest;
+    *s = expanded;
+    Ok(())
+}
+
+fn check_path(path: &str) -> anyhow::Result<()> {
+    ensure!(!path.is_empty(), "The path is empty");
+    ensure!(
+        !path.chars().any(|c| c.is_control()),
+        "The path contains control characters: {:?}",
+        path,
+    );
+    ensure!(
+        path.trim() == path,
+        "The path starts or ends with whitespace: {:?}",
+        path,
+    );
+    Ok(())
+}
+
 fn quote(s: impl AsRef<str>) -> impl fmt::Display {
     InterpolatedText::<Infallible>::from(s.as_ref().to_owned())
 }
@@ -193,9 +522,8 @@ in  {{ service = config.detectServiceFromRelativePathSegments relativePathSegmen
         service: Option<PlatformKind>,
         contest: Option<&str>,
         problem: Option<&str>,
-        language: Option<&str>,
         mode: Mode,
-    ) -> anyhow::Result<(Target, String)> {
+    ) -> anyhow::Result<Target> {
         let service = service.map(Ok).unwrap_or_else(|| {
             self.service
                 .as_deref()
@@ -207,36 +535,55 @@ in  {{ service = config.detectServiceFromRelativePathSegments relativePathSegmen
                 })
         })?;
 
-        let contest = contest
-            .or_else(|| self.contest.as_deref())
-            .map(ToOwned::to_owned);
+        let (detected_contest, detected_problem) =
+            self.contest_and_problem_for(Some(service), contest);
+
+        let contest = contest.or(detected_contest).map(ToOwned::to_owned);
 
         let problem = problem
             .map(Ok)
             .unwrap_or_else(|| {
-                self.problem
-                    .as_deref()
+                detected_problem
                     .with_context(|| "`problem` was not detected. Specify with `--problem`")
             })?
             .to_owned();
 
-        let language = language
+        Ok(Target {
+            service,
+            contest,
+            problem,
+            alias: None,
+            mode,
+        })
+    }
+
+    fn language_name(&self, language: Option<&str>) -> anyhow::Result<String> {
+        language
             .map(Ok)
             .unwrap_or_else(|| {
                 self.language
                     .as_deref()
                     .with_context(|| "`language` was not detected. Specify with `--language`")
-            })?
-            .to_owned();
-
-        let target = Target {
-            service,
-            contest,
-            problem,
-            mode,
-        };
+            })
+            .map(ToOwned::to_owned)
+    }
 
-        Ok((target, language))
+    /// Returns the detected `contest` and `problem`, dropping the ones that belong to another
+    /// service than `service` or another contest than `contest`.
+    fn contest_and_problem_for(
+        &self,
+        service: Option<PlatformKind>,
+        contest: Option<&str>,
+    ) -> (Option<&str>, Option<&str>) {
+        let same_service = service
+            .is_none_or(|service| matches!(self.parse_service(), Ok(Some(s)) if s == service));
+        let detected_contest = self.contest.as_deref().filter(|_| same_service);
+        let same_contest = contest.is_none_or(|contest| detected_contest == Some(contest));
+        let detected_problem = self
+            .problem
+            .as_deref()
+            .filter(|_| same_service && same_contest);
+        (detected_contest, detected_problem)
     }
 
     pub(crate) fn parse_service(&self) -> anyhow::Result<Option<PlatformKind>> {
@@ -256,6 +603,25 @@ pub(crate) enum Command {
     Script(Script),
 }
 
+impl Command {
+    fn expand_env_vars(&mut self) -> anyhow::Result<()> {
+        match self {
+            Self::Args(args) => args.iter_mut().try_for_each(expand_env_vars),
+            Self::Script(Script { program, .. }) => expand_env_vars(program),
+        }
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Args(args) => {
+                let program = args.first().with_context(|| "The command is empty")?;
+                check_path(program)
+            }
+            Self::Script(Script { program, .. }) => check_path(program),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, StaticType, Clone)]
 pub(crate) struct Script {
     pub(crate) program: String,
@@ -267,16 +633,374 @@ pub(crate) struct Script {
 #[derive(Debug, Deserialize, StaticType)]
 pub(crate) struct Language {
     pub(crate) src: String,
+    /// Other source files, directories, or globs (e.g. `"./src/**/*.rs"`) built with `src`.
+    /// `"$srcs"` in `transpile` and `compile` expands to all of them, `src` first.
+    pub(crate) srcs: Option<Vec<String>>,
     pub(crate) transpile: Option<Compile>,
     pub(crate) compile: Option<Compile>,
     pub(crate) run: Command,
+    #[serde(default, deserialize_with = "deserialize_env_vars")]
+    pub(crate) runEnv: Option<Vec<EnvVar>>,
+    /// Arguments appended to `run`
+    pub(crate) runArgs: Option<Vec<String>>,
+    /// Command whose stdout is submitted instead of `src`. It is given the code with `replace`
+    /// applied to its stdin.
+    pub(crate) bundler: Option<Command>,
+    /// Substitutions applied in order to the code to submit
+    pub(crate) replace: Option<Vec<Replace>>,
+    /// Directories searched for `#include "..."` to inline on submit (C/C++)
+    pub(crate) includeDirs: Option<Vec<String>>,
     pub(crate) languageId: Option<String>,
+    /// Language IDs for each service (e.g. `toMap { atcoder = "4003", yukicoder = "cpp17" }`),
+    /// preferred over `languageId`
+    pub(crate) languageIds: Option<TextMap<String>>,
+    /// Content of `src` created by `new` command
+    pub(crate) template: Option<Template>,
+}
+
+impl Language {
+    fn expand_env_vars(&mut self, name: &str) -> anyhow::Result<()> {
+        let context = |field: &str| format!("Could not expand `languages.{}.{}`", name, field);
+
+        expand_env_vars(&mut self.src).with_context(|| context("src"))?;
+        for src in self.srcs.iter_mut().flatten() {
+            expand_env_vars(src).with_context(|| context("srcs"))?;
+        }
+        let compile_fields = self
+            .transpile
+            .iter_mut()
+            .map(|c| ("transpile", c))
+            .chain(self.compile.iter_mut().map(|c| ("compile", c)));
+        for (field, compile) in compile_fields {
+            compile
+                .command
+                .expand_env_vars()
+                .with_context(|| context(&format!("{}.command", field)))?;
+            expand_env_vars(&mut compile.output)
+                .with_context(|| context(&format!("{}.output", field)))?;
+            for EnvVar { mapKey, mapValue } in compile.env.iter_mut().flatten() {
+                expand_env_vars(mapValue)
+                    .with_context(|| context(&format!("{}.env.{}", field, mapKey)))?;
+            }
+        }
+        self.run.expand_env_vars().with_context(|| context("run"))?;
+        for EnvVar { mapKey, mapValue } in self.runEnv.iter_mut().flatten() {
+            expand_env_vars(mapValue).with_context(|| context(&format!("runEnv.{}", mapKey)))?;
+        }
+        for arg in self.runArgs.iter_mut().flatten() {
+            expand_env_vars(arg).with_context(|| context("runArgs"))?;
+        }
+        if let Some(bundler) = &mut self.bundler {
+            bundler
+                .expand_env_vars()
+                .with_context(|| context("bundler"))?;
+        }
+        for dir in self.includeDirs.iter_mut().flatten() {
+            expand_env_vars(dir).with_context(|| context("includeDirs"))?;
+        }
+        if let Some(Template::Path(path)) = &mut self.template {
+            expand_env_vars(path).with_context(|| context("template"))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the programs that `transpile`, `compile`, `run`, and `bundler` execute, with the
+    /// names of the fields.
+    ///
+    /// Programs that are built by `transpile` or `compile` are excluded.
+    pub(crate) fn programs(&self) -> Vec<(&'static str, &str)> {
+        let outputs = self
+            .transpile
+            .iter()
+            .chain(&self.compile)
+            .map(|c| &*c.output)
+            .collect::<Vec<_>>();
+
+        let commands = self
+            .transpile
+            .iter()
+            .map(|c| ("transpile", &c.command))
+            .chain(self.compile.iter().map(|c| ("compile", &c.command)))
+            .chain(iter::once(("run", &self.run)))
+            .chain(self.bundler.iter().map(|c| ("bundler", c)));
+
+        commands
+            .flat_map(|(field, command)| match command {
+                Command::Args(args) => args.first().map(|p| (field, &**p)),
+                Command::Script(Script { program, .. }) => Some((field, &**program)),
+            })
+            .filter(|(_, program)| !outputs.contains(program))
+            .collect()
+    }
+
+    fn check_paths(&self, name: &str) -> anyhow::Result<()> {
+        let context = |field: &str| format!("Invalid `languages.{}.{}`", name, field);
+
+        check_path(&self.src).with_context(|| context("src"))?;
+        for (i, src) in self.srcs.iter().flatten().enumerate() {
+            check_path(src).with_context(|| context(&format!("srcs[{}]", i)))?;
+        }
+        let compile_fields = self
+            .transpile
+            .iter()
+            .map(|c| ("transpile", c))
+            .chain(self.compile.iter().map(|c| ("compile", c)));
+        for (field, compile) in compile_fields {
+            compile
+                .command
+                .check()
+                .with_context(|| context(&format!("{}.command", field)))?;
+            check_path(&compile.output).with_context(|| context(&format!("{}.output", field)))?;
+        }
+        self.run.check().with_context(|| context("run"))?;
+        if let Some(bundler) = &self.bundler {
+            bundler.check().with_context(|| context("bundler"))?;
+        }
+        for (i, Replace { regex, .. }) in self.replace.iter().flatten().enumerate() {
+            Regex::new(regex).with_context(|| context(&format!("replace[{}].regex", i)))?;
+        }
+        for (i, dir) in self.includeDirs.iter().flatten().enumerate() {
+            check_path(dir).with_context(|| context(&format!("includeDirs[{}]", i)))?;
+        }
+        if let Some(Template::Path(path)) = &self.template {
+            check_path(path).with_context(|| context("template"))?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize, StaticType)]
 pub(crate) struct Compile {
     pub(crate) command: Command,
     pub(crate) output: String,
+    #[serde(default, deserialize_with = "deserialize_env_vars")]
+    pub(crate) env: Option<Vec<EnvVar>>,
+    /// Seconds after which the command is killed
+    pub(crate) timeout: Option<u64>,
+}
+
+/// `< Path : Text | Content : Text >`
+///
+/// `{lower}`, `{upper}`, `{snake}`, `{kebab}`, `{mixed}`, and `{pascal}` in the template are
+/// replaced with the case conversions of the problem.
+#[derive(Debug, Deserialize, StaticType, Clone)]
+pub(crate) enum Template {
+    /// Path to a file, relative to the directory of `snowchains.dhall`
+    Path(String),
+    Content(String),
+}
+
+/// Replaces every match of `regex` with `replacement` (`$1`, `${name}`, ...).
+#[derive(Debug, Deserialize, StaticType, Clone)]
+pub(crate) struct Replace {
+    pub(crate) regex: String,
+    pub(crate) replacement: String,
+}
+
+impl Replace {
+    pub(crate) fn apply(rules: &[Self], code: &str) -> anyhow::Result<String> {
+        rules
+            .iter()
+            .try_fold(code.to_owned(), |code, Self { regex, replacement }| {
+                let regex =
+                    Regex::new(regex).with_context(|| format!("Invalid regex: {:?}", regex))?;
+                Ok(regex.replace_all(&code, &**replacement).into_owned())
+            })
+    }
+}
+
+/// `Map Text V`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(transparent)]
+pub(crate) struct TextMap<V>(pub(crate) IndexMap<String, V>);
+
+impl<V: StaticType> StaticType for TextMap<V> {
+    fn static_type() -> SimpleType {
+        map_annot(SimpleType::Text, V::static_type())
+    }
+}
+
+/// `serde_dhall` hands a `Map Text Text` to serde as a map rather than a list of entries.
+fn deserialize_env_vars<'de, D>(deserializer: D) -> Result<Option<Vec<EnvVar>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let env_vars = Option::<IndexMap<String, String>>::deserialize(deserializer)?;
+    Ok(env_vars.map(|env_vars| {
+        env_vars
+            .into_iter()
+            .map(|(name, value)| EnvVar {
+                mapKey: name,
+                mapValue: value,
+            })
+            .collect()
+    }))
+}
+
+/// Returns the entry of `language_ids` for `service`, or `language_id` if there is none.
+pub(crate) fn language_id(
+    service: PlatformKind,
+    language_ids: Option<TextMap<String>>,
+    language_id: Option<String>,
+) -> anyhow::Result<Option<String>> {
+    let mut found = None;
+    for (key, value) in language_ids.into_iter().flat_map(|TextMap(m)| m) {
+        let key_service = key
+            .parse::<PlatformKind>()
+            .with_context(|| format!("Unknown service in `languageIds`: {:?}", key))?;
+        if key_service == service {
+            found = Some(value);
+        }
+    }
+    Ok(found.or(language_id))
+}
+
+/// An entry of `Map Text Text` (`toMap { NAME = "value" }`).
+#[allow(non_snake_case)] // for `StaticType`
+#[derive(Debug, Deserialize, StaticType, Clone)]
+pub(crate) struct EnvVar {
+    pub(crate) mapKey: String,
+    pub(crate) mapValue: String,
+}
+
+#[allow(non_snake_case)] // for `StaticType`
+#[derive(Debug, Deserialize, StaticType)]
+pub(crate) struct Testfiles {
+    pub(crate) layout: TestfilesLayout,
+    /// Adds `/.snowchains/tests/` to `.gitignore` in the workspace on download
+    pub(crate) gitignore: Option<bool>,
+    /// `match` for the test suites that omit it
+    pub(crate) defaultMatch: Option<DefaultMatch>,
+}
+
+impl Testfiles {
+    pub(crate) fn default_match(&self) -> Match {
+        match self.defaultMatch.unwrap_or(DefaultMatch::Lines) {
+            DefaultMatch::Exact => Match::Exact,
+            DefaultMatch::SplitWhitespace => Match::SplitWhitespace,
+            DefaultMatch::Lines => Match::Lines,
+            DefaultMatch::Float => Match::Float {
+                relative_error: None,
+                absolute_error: None,
+            },
+            DefaultMatch::Binary => Match::Binary,
+        }
+    }
+}
+
+#[allow(non_snake_case)] // for `StaticType`
+#[derive(Debug, Deserialize, StaticType)]
+pub(crate) struct Hooks {
+    /// Runs after `judge` if all of the test cases pass
+    pub(crate) onPass: Option<Command>,
+    /// Runs after `judge` if any of the test cases fails
+    pub(crate) onFail: Option<Command>,
+}
+
+#[allow(non_snake_case)] // for `StaticType`
+#[derive(Debug, Deserialize, StaticType)]
+struct Submit {
+    maxBytes: Option<TextMap<u64>>,
+    normalizeNewlines: Option<Newline>,
+}
+
+#[allow(non_snake_case)] // for `StaticType`
+#[derive(Debug, Deserialize, StaticType)]
+pub(crate) struct Session {
+    pub(crate) userAgent: Option<String>,
+    /// Minimum interval between HTTP requests (e.g. `"500ms"`)
+    pub(crate) requestInterval: Option<String>,
+    /// Timeout of each HTTP request (e.g. `"1m"`, or `"none"` to disable it)
+    pub(crate) timeout: Option<String>,
+    /// Headers attached to every HTTP request, in addition to `User-Agent`
+    #[serde(default, deserialize_with = "deserialize_env_vars")]
+    pub(crate) headers: Option<Vec<EnvVar>>,
+}
+
+impl Session {
+    pub(crate) fn user_agent(&self) -> String {
+        self.userAgent
+            .clone()
+            .unwrap_or_else(|| crate::web::USER_AGENT.to_owned())
+    }
+
+    pub(crate) fn request_interval(&self) -> anyhow::Result<Option<Duration>> {
+        self.requestInterval
+            .as_deref()
+            .map(|s| {
+                humantime::parse_duration(s)
+                    .with_context(|| format!("Invalid `session.requestInterval`: {:?}", s))
+            })
+            .transpose()
+            .map(|d| d.filter(|d| *d > Duration::from_secs(0)))
+    }
+
+    pub(crate) fn headers(&self) -> anyhow::Result<IndexMap<String, String>> {
+        self.headers
+            .iter()
+            .flatten()
+            .map(|EnvVar { mapKey, mapValue }| {
+                let mut value = mapValue.clone();
+                expand_env_vars(&mut value)
+                    .with_context(|| format!("Could not expand `session.headers.{}`", mapKey))?;
+                Ok((mapKey.clone(), value))
+            })
+            .collect()
+    }
+
+    /// Returns `--http-timeout` if given, otherwise `session.timeout`, otherwise the default.
+    pub(crate) fn timeout(
+        &self,
+        cli_opt: Option<crate::HttpTimeout>,
+    ) -> anyhow::Result<Option<Duration>> {
+        let timeout = match (cli_opt, &self.timeout) {
+            (Some(timeout), _) => timeout,
+            (None, Some(timeout)) => timeout
+                .parse()
+                .with_context(|| format!("Invalid `session.timeout`: {:?}", timeout))?,
+            (None, None) => return Ok(Some(crate::web::SESSION_TIMEOUT)),
+        };
+        Ok(match timeout {
+            crate::HttpTimeout::Duration(timeout) => Some(timeout),
+            crate::HttpTimeout::None => None,
+        })
+    }
+}
+
+/// Newlines that `submit` converts the code to.
+#[derive(Debug, Deserialize, StaticType, Clone, Copy, PartialEq)]
+pub(crate) enum Newline {
+    Lf,
+    Crlf,
+}
+
+impl Newline {
+    pub(crate) fn normalize(self, code: &str) -> String {
+        let code = code.replace("\r\n", "\n");
+        match self {
+            Self::Lf => code,
+            Self::Crlf => code.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// `testfiles.defaultMatch`. `Float` has no error tolerance.
+#[derive(Debug, Deserialize, StaticType, Clone, Copy)]
+pub(crate) enum DefaultMatch {
+    Exact,
+    SplitWhitespace,
+    Lines,
+    Float,
+    Binary,
+}
+
+/// How `download` saves test cases.
+#[derive(Debug, Deserialize, StaticType, Clone, Copy, PartialEq)]
+pub(crate) enum TestfilesLayout {
+    /// `<problem>.yml`
+    Suite,
+    /// `<problem>.yml` that refers to `<problem>/in/*.txt` and `<problem>/out/*.txt`
+    Directory,
 }
 
 #[derive(Debug)]
@@ -284,13 +1008,37 @@ pub(crate) struct Target {
     pub(crate) service: PlatformKind,
     pub(crate) contest: Option<String>,
     pub(crate) problem: String,
+    /// The name the problem was specified with, if it was one of `aliases`.
+    pub(crate) alias: Option<String>,
     pub(crate) mode: Mode,
 }
 
 impl Target {
-    fn to_dhall_expr(&self) -> String {
+    /// Replaces `problem` with what it is aliased to.
+    ///
+    /// If `aliases` is not empty, `problem` must be one of its keys or values.
+    fn resolve_alias(&mut self, aliases: &IndexMap<String, String>) -> anyhow::Result<()> {
+        if let Some(problem) = aliases.get(&self.problem) {
+            self.alias = Some(mem::replace(&mut self.problem, problem.clone()));
+            return Ok(());
+        }
+
+        ensure!(
+            aliases.is_empty() || aliases.values().any(|v| *v == self.problem),
+            "Unknown problem `{}`. Expected one of the aliases [{}]",
+            self.problem,
+            aliases
+                .iter()
+                .format_with(", ", |(k, v), f| f(&format_args!("`{}` (`{}`)", k, v))),
+        );
+        Ok(())
+    }
+
+    fn to_dhall_expr(&self, service_type: &str) -> String {
+        let problem = CaseConversions::new(&self.problem);
+
         format!(
-            r"let Service = < Atcoder | Codeforces | Yukicoder >
+            r"let Service = {}
 
 let CaseConvertedText =
       {{ lowercase : Text
@@ -316,26 +1064,37 @@ in  {{ service = Service.{}
     , mode = Mode.{}
     }}
 ",
+            service_type,
             self.service.to_pascal_case_str(),
             if let Some(contest) = &self.contest {
+                let CaseConversions {
+                    lower,
+                    upper,
+                    snake,
+                    kebab,
+                    mixed,
+                    pascal,
+                    ..
+                } = CaseConversions::new(contest);
+
                 format!(
                     r"Some {{ lowercase =  {}, uppercase =  {}, snakeCase =  {}, kebabCase =  {}, mixedCase =  {}, pascalCase = {} }}",
-                    quote(contest.to_lowercase()),
-                    quote(contest.to_uppercase()),
-                    quote(contest.to_snake_case()),
-                    quote(contest.to_kebab_case()),
-                    quote(contest.to_mixed_case()),
-                    quote(contest.to_camel_case()),
+                    quote(lower),
+                    quote(upper),
+                    quote(snake),
+                    quote(kebab),
+                    quote(mixed),
+                    quote(pascal),
                 )
             } else {
                 "None CaseConvertedText".to_owned()
             },
-            quote(self.problem.to_lowercase()),
-            quote(self.problem.to_uppercase()),
-            quote(self.problem.to_snake_case()),
-            quote(self.problem.to_kebab_case()),
-            quote(self.problem.to_mixed_case()),
-            quote(self.problem.to_camel_case()),
+            quote(&problem.lower),
+            quote(&problem.upper),
+            quote(&problem.snake),
+            quote(&problem.kebab),
+            quote(&problem.mixed),
+            quote(&problem.pascal),
             match self.mode {
                 Mode::Debug => "Debug",
                 Mode::Release => "Release",
@@ -349,3 +1108,124 @@ pub(crate) enum Mode {
     Debug,
     Release,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Detected, Newline, Replace, TextMap};
+    use indexmap::indexmap;
+    use snowchains_core::web::PlatformKind;
+
+    #[test]
+    fn normalize_newlines() {
+        let code = "a\r\nb\nc\r\n";
+        assert_eq!("a\nb\nc\n", Newline::Lf.normalize(code));
+        assert_eq!("a\r\nb\r\nc\r\n", Newline::Crlf.normalize(code));
+    }
+
+    #[test]
+    fn replace_java() -> anyhow::Result<()> {
+        let rules = &[
+            Replace {
+                regex: r"(?m)^package [\w.]+;\n".to_owned(),
+                replacement: "".to_owned(),
+            },
+            Replace {
+                regex: r"public class \w+".to_owned(),
+                replacement: "public class Main".to_owned(),
+            },
+        ];
+
+        let code = r#"package com.example.abc999;
+import java.util.Scanner;
+
+public class A {
+    public static void main(String[] args) {
+        System.out.println(new Scanner(System.in).nextInt());
+    }
+}
+"#;
+
+        assert_eq!(
+            r#"import java.util.Scanner;
+
+public class Main {
+    public static void main(String[] args) {
+        System.out.println(new Scanner(System.in).nextInt());
+    }
+}
+"#,
+            Replace::apply(rules, code)?,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn expand_env_vars() {
+        std::env::set_var("SNOWCHAINS_TEST_EXPAND_ENV_VARS", "foo");
+        let expand = |s: &str| -> anyhow::Result<String> {
+            let mut s = s.to_owned();
+            super::expand_env_vars(&mut s)?;
+            Ok(s)
+        };
+
+        assert_eq!("a/b", expand("a/b").unwrap());
+        assert_eq!(
+            "a/foo/foo",
+            expand(
+                "a/${env:SNOWCHAINS_TEST_EXPAND_ENV_VARS}/${env:SNOWCHAINS_TEST_EXPAND_ENV_VARS}"
+            )
+            .unwrap(),
+        );
+        assert!(expand("${env:SNOWCHAINS_TEST_EXPAND_ENV_VARS").is_err());
+        assert!(expand("${env:SNOWCHAINS_TEST_NOT_SET}").is_err());
+    }
+
+    #[test]
+    fn contest_and_problem_for() {
+        let detected = Detected {
+            service: Some("atcoder".to_owned()),
+            contest: Some("abc100".to_owned()),
+            problem: Some("a".to_owned()),
+            language: None,
+        };
+        let for_ = |service, contest| detected.contest_and_problem_for(service, contest);
+
+        assert_eq!((Some("abc100"), Some("a")), for_(None, None));
+        assert_eq!(
+            (Some("abc100"), Some("a")),
+            for_(Some(PlatformKind::Atcoder), Some("abc100")),
+        );
+        assert_eq!((Some("abc100"), None), for_(None, Some("abc101")));
+        assert_eq!((None, None), for_(Some(PlatformKind::Codeforces), None));
+    }
+
+    #[test]
+    fn language_id() -> anyhow::Result<()> {
+        let language_ids = || {
+            Some(TextMap(indexmap!(
+                "atcoder".to_owned() => "4003".to_owned(),
+                "yukicoder".to_owned() => "cpp17".to_owned(),
+            )))
+        };
+        let language_id = |service, language_ids, language_id: Option<&str>| {
+            super::language_id(service, language_ids, language_id.map(ToOwned::to_owned))
+        };
+
+        assert_eq!(
+            Some("cpp17".to_owned()),
+            language_id(PlatformKind::Yukicoder, language_ids(), Some("54"))?,
+        );
+        assert_eq!(
+            Some("54".to_owned()),
+            language_id(PlatformKind::Codeforces, language_ids(), Some("54"))?,
+        );
+        assert_eq!(
+            None,
+            language_id(PlatformKind::Codeforces, language_ids(), None)?
+        );
+
+        let unknown = Some(TextMap(indexmap!("topcoder".to_owned() => "1".to_owned())));
+        assert!(language_id(PlatformKind::Atcoder, unknown, None).is_err());
+        Ok(())
+    }
+}
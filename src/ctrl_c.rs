@@ -0,0 +1,80 @@
+use once_cell::sync::Lazy;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+use termcolor::{StandardStream, WriteColor as _};
+
+/// Exit code for Ctrl-C. (`128 + SIGINT`)
+pub(crate) const EXIT_CODE: i32 = 130;
+
+static NUM_GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+static PARTIAL_FILES: Lazy<Mutex<BTreeSet<PathBuf>>> = Lazy::new(Default::default);
+
+/// Installs a Ctrl-C handler.
+///
+/// Unless a [`Guard`] is alive, the handler removes the partially written files, resets the color
+/// of stderr, and exits with `130`. Child processes are left to the operations holding a
+/// [`Guard`], which kill them by themselves.
+pub fn install_handler(color: crate::ColorChoice) {
+    thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("could not build a Tokio runtime");
+
+        while rt.block_on(tokio::signal::ctrl_c()).is_ok() {
+            if NUM_GUARDS.load(Ordering::SeqCst) > 0 {
+                continue;
+            }
+
+            // Keeps the lock so that no more partial files are created.
+            let partial_files = PARTIAL_FILES.lock().unwrap_or_else(|e| e.into_inner());
+            for path in &*partial_files {
+                let _ = std::fs::remove_file(path);
+            }
+
+            let mut stderr = StandardStream::stderr(color.for_stream(atty::Stream::Stderr));
+            let _ = stderr.reset();
+
+            process::exit(EXIT_CODE);
+        }
+    });
+}
+
+/// While this is alive, the Ctrl-C handler does nothing and the holder is responsible for
+/// handling Ctrl-C.
+#[derive(Debug)]
+pub(crate) struct Guard(());
+
+impl Guard {
+    pub(crate) fn new() -> Self {
+        NUM_GUARDS.fetch_add(1, Ordering::SeqCst);
+        Self(())
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        NUM_GUARDS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Registers `path` to be removed on Ctrl-C until `f` returns.
+pub(crate) fn with_partial_file<T>(path: &Path, f: impl FnOnce() -> T) -> T {
+    lock_partial_files().insert(path.to_owned());
+    let ret = f();
+    lock_partial_files().remove(path);
+    return ret;
+
+    fn lock_partial_files() -> std::sync::MutexGuard<'static, BTreeSet<PathBuf>> {
+        PARTIAL_FILES.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
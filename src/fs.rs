@@ -1,6 +1,13 @@
 use anyhow::Context as _;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fs::Metadata, path::Path};
+use snowchains_core::testsuite::TestSuite;
+use std::{
+    ffi::OsStr,
+    fs::{File, Metadata},
+    io::{self, Read as _, Write as _},
+    path::Path,
+};
 
 pub(crate) fn metadata(path: impl AsRef<Path>) -> anyhow::Result<Metadata> {
     let path = path.as_ref();
@@ -13,20 +20,148 @@ pub(crate) fn read_to_string(path: impl AsRef<Path>) -> anyhow::Result<String> {
     std::fs::read_to_string(path).with_context(|| format!("Could not read `{}`", path.display()))
 }
 
+/// Reads `path` as a gzipped text file.
+fn read_gzipped_to_string(path: &Path) -> anyhow::Result<String> {
+    (|| {
+        let mut content = String::new();
+        GzDecoder::new(File::open(path)?).read_to_string(&mut content)?;
+        Ok::<_, io::Error>(content)
+    })()
+    .with_context(|| format!("Could not read `{}`", path.display()))
+}
+
 pub(crate) fn read_json<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> anyhow::Result<T> {
     let path = path.as_ref();
-    let content = read_to_string(path)?;
-    serde_json::from_str(&content)
-        .with_context(|| format!("Could not parse the JSON at `{}`", path.display()))
+    parse_json(path, &read_to_string(path)?)
 }
 
 pub(crate) fn read_yaml<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> anyhow::Result<T> {
     let path = path.as_ref();
-    let content = read_to_string(path)?;
-    serde_yaml::from_str(&content)
+    parse_yaml(path, &read_to_string(path)?)
+}
+
+fn parse_json<T: DeserializeOwned>(path: &Path, content: &str) -> anyhow::Result<T> {
+    serde_json::from_str(strip_bom(content))
+        .with_context(|| format!("Could not parse the JSON at `{}`", path.display()))
+}
+
+fn parse_yaml<T: DeserializeOwned>(path: &Path, content: &str) -> anyhow::Result<T> {
+    serde_yaml::from_str(strip_trailing_document_markers(strip_bom(content)))
         .with_context(|| format!("Could not parse the YAML at `{}`", path.display()))
 }
 
+fn parse_toml<T: DeserializeOwned>(path: &Path, content: &str) -> anyhow::Result<T> {
+    toml::from_str(strip_bom(content))
+        .with_context(|| format!("Could not parse the TOML at `{}`", path.display()))
+}
+
+fn strip_bom(content: &str) -> &str {
+    content.trim_start_matches('\u{feff}')
+}
+
+/// Removes `---`/`...` lines at the end, which would make `serde_yaml` see a second document.
+fn strip_trailing_document_markers(mut content: &str) -> &str {
+    loop {
+        let trimmed = content.trim_end();
+        let (rest, last_line) = match trimmed.rfind('\n') {
+            Some(i) => (&trimmed[..=i], &trimmed[i + 1..]),
+            None => ("", trimmed),
+        };
+        if last_line.trim_end() == "---" || last_line.trim_end() == "..." {
+            content = rest;
+        } else {
+            return content;
+        }
+    }
+}
+
+/// Whether `path` ends with `.gz`.
+fn is_gzipped(path: &Path) -> bool {
+    path.extension() == Some("gz".as_ref())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+/// Decides the format by the extension of `path`, or the inner one if it ends with `.gz`.
+fn format(path: &Path) -> Format {
+    let path = if is_gzipped(path) {
+        Path::new(path.file_stem().unwrap_or_default())
+    } else {
+        path
+    };
+    match path.extension().and_then(OsStr::to_str) {
+        Some("json") => Format::Json,
+        Some("toml") => Format::Toml,
+        _ => Format::Yaml,
+    }
+}
+
+/// Reads `path` as JSON if the extension is `json`, as TOML if it is `toml`, as YAML otherwise.
+///
+/// `*.gz` files are decompressed first, and the format is decided by the inner extension.
+pub(crate) fn read_json_or_yaml<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> anyhow::Result<T> {
+    let path = path.as_ref();
+
+    let content = if is_gzipped(path) {
+        read_gzipped_to_string(path)?
+    } else {
+        read_to_string(path)?
+    };
+
+    match format(path) {
+        Format::Json => parse_json(path, &content),
+        Format::Toml => parse_toml(path, &content),
+        Format::Yaml => parse_yaml(path, &content),
+    }
+}
+
+/// Writes `test_suite` to `path` in the format [`read_json_or_yaml`] expects for it.
+pub(crate) fn write_test_suite(
+    path: impl AsRef<Path>,
+    test_suite: &TestSuite,
+    create_dir_all: bool,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+
+    let content = match format(path) {
+        Format::Json => serde_json::to_string(test_suite)?,
+        Format::Toml => to_toml(test_suite)?,
+        Format::Yaml => test_suite.to_yaml_pretty(),
+    };
+
+    if is_gzipped(path) {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(content.as_ref())?;
+        write(path, encoder.finish()?, create_dir_all)
+    } else {
+        write(path, content, create_dir_all)
+    }
+}
+
+/// `toml` cannot serialize `None`s outside of structs and enum variants with fields (e.g.
+/// `Match::Float`), so `value` is converted through JSON with the `null`s removed.
+fn to_toml(value: impl Serialize) -> anyhow::Result<String> {
+    let mut value = serde_json::to_value(value)?;
+    remove_nulls(&mut value);
+    return Ok(toml::to_string_pretty(&toml::Value::try_from(value)?)?);
+
+    fn remove_nulls(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+            serde_json::Value::Object(entries) => {
+                entries.retain(|_, value| !value.is_null());
+                entries.values_mut().for_each(remove_nulls);
+            }
+            _ => {}
+        }
+    }
+}
+
 pub(crate) fn write(
     path: impl AsRef<Path>,
     contents: impl AsRef<[u8]>,
@@ -38,8 +173,18 @@ pub(crate) fn write(
         }
     }
 
-    std::fs::write(&path, contents)
-        .with_context(|| format!("Could not write `{}`", path.as_ref().display()))
+    // Writes to `<path>.part` first so that Ctrl-C does not leave a broken file at `path`.
+    let path = path.as_ref();
+    let part = {
+        let mut file_name = path.file_name().unwrap_or_default().to_owned();
+        file_name.push(".part");
+        path.with_file_name(file_name)
+    };
+
+    crate::ctrl_c::with_partial_file(&part, || {
+        std::fs::write(&part, contents).and_then(|()| std::fs::rename(&part, path))
+    })
+    .with_context(|| format!("Could not write `{}`", path.display()))
 }
 
 pub(crate) fn write_json(
@@ -54,3 +199,89 @@ pub(crate) fn create_dir_all(path: impl AsRef<Path>) -> anyhow::Result<()> {
     std::fs::create_dir_all(&path)
         .with_context(|| format!("Could not create `{}`", path.as_ref().display()))
 }
+
+#[cfg(test)]
+mod tests {
+    use snowchains_core::testsuite::TestSuite;
+    use std::fs;
+
+    #[test]
+    fn read_json_or_yaml_with_bom() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-fs-test-")
+            .tempdir()?;
+
+        let yaml = tempdir.path().join("a.yml");
+        fs::write(
+            &yaml,
+            "\u{feff}---\ntype: Batch\ntimelimit: 2s\nmatch: Lines\ncases: []\n...\n---\n",
+        )?;
+        assert!(matches!(
+            super::read_json_or_yaml(&yaml)?,
+            TestSuite::Batch(_),
+        ));
+
+        let json = tempdir.path().join("last_run.json");
+        fs::write(&json, "\u{feff}{\"failed\":[\"1\"]}")?;
+        let value = super::read_json_or_yaml::<serde_json::Value, _>(&json)?;
+        assert_eq!(value["failed"][0], "1");
+
+        tempdir.close().map_err(Into::into)
+    }
+
+    #[test]
+    fn write_and_read_test_suites() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-fs-test-")
+            .tempdir()?;
+
+        let yaml = tempdir.path().join("a.yml");
+        fs::write(
+            &yaml,
+            r#"---
+type: Batch
+timelimit: 2s
+match:
+  Float:
+    relative_error: 1e-6
+    absolute_error: ~
+cases:
+  - name: Sample 1
+    in: "4\n"
+    out: "2 2\n"
+    timelimit: 500ms
+  - in: "5\n"
+extend:
+  - type: Text
+    path: ./a
+    in: /in/*.txt
+    out: /out/*.txt
+"#,
+        )?;
+        let test_suite = super::read_yaml::<TestSuite, _>(&yaml)?;
+
+        for file_name in &[
+            "b.yml",
+            "b.yaml",
+            "b.json",
+            "b.toml",
+            "b.yml.gz",
+            "b.json.gz",
+            "b.toml.gz",
+        ] {
+            let path = tempdir.path().join(file_name);
+            super::write_test_suite(&path, &test_suite, false)?;
+            if file_name.ends_with(".gz") {
+                assert_eq!(&fs::read(&path)?[..2], b"\x1f\x8b");
+            }
+            assert_eq!(
+                test_suite,
+                super::read_json_or_yaml(&path)?,
+                "{}",
+                file_name,
+            );
+        }
+
+        tempdir.close().map_err(Into::into)
+    }
+}
@@ -1,21 +1,28 @@
 use crate::config;
-use anyhow::bail;
-use az::SaturatingAs as _;
-use human_size::{Byte, Size};
+use anyhow::{anyhow, bail, ensure, Context as _};
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
 use indicatif::ProgressDrawTarget;
 use itertools::Itertools as _;
-use maplit::btreemap;
+use serde::{Deserialize, Serialize};
 use snowchains_core::{
-    color_spec, judge::CommandExpression, testsuite::TestSuite, web::PlatformKind,
+    color_spec,
+    judge::{CommandExpression, Verdict},
+    testsuite::{
+        BatchTestCase, BatchTestSuite, DeterministicExpectedOutput, ExpectedOutput, Match,
+        PartialBatchTestCase, TestSuite,
+    },
+    web::PlatformKind,
 };
 use std::{
-    collections::HashSet,
-    ffi::OsStr,
-    io::Write as _,
+    collections::{BTreeMap, HashSet},
+    ffi::{OsStr, OsString},
+    io::{self, Write as _},
     iter, mem,
     ops::Deref,
     path::{Path, PathBuf},
     process::Stdio,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use termcolor::{Color, WriteColor};
 
@@ -30,17 +37,44 @@ pub(crate) struct Args<W1, W2> {
     pub(crate) service: PlatformKind,
     pub(crate) contest: Option<String>,
     pub(crate) problem: String,
-    pub(crate) src: String,
+    /// `src` and the files `srcs` matched, `src` first
+    pub(crate) srcs: Vec<PathBuf>,
     pub(crate) transpile: Option<config::Compile>,
     pub(crate) compile: Option<config::Compile>,
     pub(crate) run: config::Command,
+    pub(crate) run_env: Vec<config::EnvVar>,
+    pub(crate) run_args: Vec<String>,
+    /// `match` for the test suite that omits it
+    pub(crate) default_match: Match,
     pub(crate) test_case_names: Option<HashSet<String>>,
-    pub(crate) display_limit: Size,
+    /// Runs once with this input instead of the test suite
+    pub(crate) stdin_input: Option<String>,
+    /// Runs once with the input and the expected output in these files instead of the test cases
+    /// in the test suite
+    pub(crate) input_and_expected: Option<(PathBuf, Option<PathBuf>)>,
+    pub(crate) display_limit: crate::DisplayLimit,
+    pub(crate) capture_limit: crate::DisplayLimit,
+    pub(crate) show_stderr: bool,
+    pub(crate) format: crate::JudgeFormat,
+    pub(crate) summary: bool,
+    pub(crate) only_failed: bool,
+    pub(crate) fail_fast: bool,
+    /// Runs only the first this number of the test cases
+    pub(crate) max_cases: Option<usize>,
+    /// Prints elapsed times with microseconds
+    pub(crate) precise_time: bool,
+    /// Builds even if the output is up to date
+    pub(crate) force_compile: bool,
+    /// Uses the existing outputs of `transpile` and `compile` without building them
+    pub(crate) no_compile: bool,
+    /// Value of `$seed` in `run`. A random one is used if `None`.
+    pub(crate) seed: Option<u64>,
+    pub(crate) hooks: config::Hooks,
 }
 
 pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Result<()> {
     let Args {
-        stdout,
+        mut stdout,
         mut stderr,
         stdin_process_redirection,
         stdout_process_redirection,
@@ -50,30 +84,129 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
         service,
         contest,
         problem,
-        src,
+        srcs,
         transpile,
         compile,
         run,
+        run_env,
+        run_args,
+        default_match,
         test_case_names,
+        stdin_input,
+        input_and_expected,
         display_limit,
+        capture_limit,
+        show_stderr,
+        format,
+        summary,
+        only_failed,
+        fail_fast,
+        max_cases,
+        precise_time,
+        force_compile,
+        no_compile,
+        seed,
+        hooks,
     } = args;
 
-    let test_suite_dir = base_dir
+    let test_suite_path = test_suite_path(&base_dir, service, contest.as_deref(), &problem);
+    let test_suite_dir = test_suite_path.parent().expect("should have a parent");
+
+    let last_run_path = base_dir
         .join(".snowchains")
-        .join("tests")
+        .join("last_run")
         .join(service.to_kebab_case_str())
-        .join(contest.as_deref().unwrap_or(""));
-    let test_suite_path = test_suite_dir.join(problem).with_extension("yml");
-
-    let test_cases = match crate::fs::read_yaml(&test_suite_path)? {
-        TestSuite::Batch(test_sutie) => {
-            test_sutie.load_test_cases(&test_suite_dir, test_case_names, |_| {
-                unimplemented!("`SystemTestCases` is not impelemented");
-            })?
+        .join(contest.as_deref().unwrap_or(""))
+        .join(&problem)
+        .with_extension("json");
+
+    let (test_case_names, skip_unnamed) =
+        if only_failed && test_case_names.is_none() && last_run_path.exists() {
+            let LastRun {
+                failed,
+                unnamed_failed,
+            } = crate::fs::read_json(&last_run_path)?;
+            if failed.is_empty() && !unnamed_failed {
+                (None, false)
+            } else {
+                (Some(failed), !unnamed_failed)
+            }
+        } else {
+            (test_case_names, false)
+        };
+
+    let mut test_cases = if let Some(input) = &stdin_input {
+        vec![BatchTestCase {
+            name: Some("stdin".to_owned()),
+            timelimit: None,
+            input: input.as_str().into(),
+            output: ExpectedOutput::Deterministic(DeterministicExpectedOutput::Pass),
+            allow_nonzero_exit: false,
+            normalizer: None,
+        }]
+    } else if let Some((input, expected)) = &input_and_expected {
+        // Only `timelimit` and `match` are taken from the test suite.
+        let (timelimit, r#match) = if test_suite_path.exists() {
+            match crate::fs::read_json_or_yaml(&test_suite_path)? {
+                TestSuite::Batch(BatchTestSuite {
+                    timelimit, r#match, ..
+                }) => (timelimit, r#match),
+                // The given files are judged as a batch test case anyway.
+                _ => (None, None),
+            }
+        } else {
+            (None, None)
+        };
+
+        let case = PartialBatchTestCase {
+            name: input.file_stem().map(|s| s.to_string_lossy().into_owned()),
+            r#in: crate::fs::read_to_string(input)?.into(),
+            out: expected
+                .as_ref()
+                .map(crate::fs::read_to_string)
+                .transpose()?
+                .map(Into::into),
+            alternative_outs: vec![],
+            timelimit: None,
+            r#match: None,
+        };
+
+        BatchTestSuite {
+            timelimit,
+            memorylimit: None,
+            systemtests: None,
+            r#match,
+            cases: vec![case],
+            extend: vec![],
+            allow_nonzero_exit: false,
+            normalizer: None,
+        }
+        .load_test_cases(
+            test_suite_dir,
+            None::<HashSet<String>>,
+            &default_match,
+            |_| unreachable!(),
+        )?
+    } else {
+        match crate::fs::read_json_or_yaml(&test_suite_path)? {
+            TestSuite::Batch(test_sutie) => test_sutie.load_test_cases(
+                test_suite_dir,
+                test_case_names,
+                &default_match,
+                |_| unimplemented!("`SystemTestCases` is not impelemented"),
+            )?,
+            _ => todo!("currently only `Batch` is supported"),
         }
-        _ => todo!("currently only `Batch` is supported"),
     };
 
+    // Test cases without names are not filtered by `test_case_names`.
+    if skip_unnamed {
+        test_cases.retain(|case| case.name.is_some());
+    }
+
+    let num_skipped = max_cases.map_or(0, |max| test_cases.len().saturating_sub(max));
+    test_cases.truncate(test_cases.len() - num_skipped);
+
     let redirections = (
         stdin_process_redirection,
         stdout_process_redirection,
@@ -88,7 +221,28 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
                 writeln!(stderr)?;
             }
 
-            build(&mut stderr, &base_dir, &src, action, redirections, msg)?;
+            if no_compile {
+                let output = build_output(&base_dir, action);
+                ensure!(
+                    output.exists(),
+                    "`{}` does not exist. Build it, or run without `--no-compile`",
+                    output.display(),
+                );
+                writeln!(stderr, "Using {} as is.", output.display())?;
+                stderr.flush()?;
+                continue;
+            }
+
+            build(
+                &mut stderr,
+                &base_dir,
+                &srcs,
+                action,
+                redirections,
+                msg,
+                force_compile,
+            )
+            .context(crate::Failure::BuildFailed)?;
         }
     }
 
@@ -101,13 +255,26 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
     writeln!(stderr)?;
     stderr.flush()?;
 
-    let (cmd, tempfile) = match run {
+    let env = run_env
+        .into_iter()
+        .map(|config::EnvVar { mapKey, mapValue }| (mapKey.into(), mapValue.into()))
+        .collect::<BTreeMap<_, _>>();
+
+    let (mut cmd, tempfile) = match run {
         config::Command::Args(args) => {
             let cmd = CommandExpression {
                 program: args.get(0).cloned().unwrap_or_default().into(),
-                args: args.into_iter().skip(1).map(Into::into).collect(),
+                args: args
+                    .into_iter()
+                    .skip(1)
+                    .chain(run_args)
+                    .map(Into::into)
+                    .collect(),
                 cwd: base_dir,
-                env: btreemap!(),
+                env,
+                inherit_stderr: show_stderr,
+                seed: None,
+                capture_limit: capture_limit.to_bytes(),
             };
 
             (cmd, None)
@@ -126,9 +293,14 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
 
             let cmd = CommandExpression {
                 program: program.into(),
-                args: vec![tempfile.path().into()],
+                args: iter::once(tempfile.path().into())
+                    .chain(run_args.into_iter().map(Into::into))
+                    .collect(),
                 cwd: base_dir,
-                env: btreemap!(),
+                env,
+                inherit_stderr: show_stderr,
+                seed: None,
+                capture_limit: capture_limit.to_bytes(),
             };
 
             (cmd, Some(tempfile))
@@ -138,13 +310,35 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
     stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
     write!(stderr, "Test file:")?;
     stderr.reset()?;
-    writeln!(stderr, " {}", test_suite_path.display())?;
+    if stdin_input.is_some() {
+        writeln!(stderr, " (stdin)")?;
+    } else if let Some((input, _)) = &input_and_expected {
+        writeln!(stderr, " {}", input.display())?;
+    } else {
+        writeln!(stderr, " {}", test_suite_path.display())?;
+    }
 
     stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
     write!(stderr, "Command:")?;
     stderr.reset()?;
     writeln!(stderr, " {}", shell_escape_args(&cmd.program, &cmd.args))?;
 
+    if cmd.uses_seed() {
+        let seed = seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos()
+                .into()
+        });
+        cmd.seed = Some(seed);
+
+        stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+        write!(stderr, "Seed:")?;
+        stderr.reset()?;
+        writeln!(stderr, " {}", seed)?;
+    }
+
     stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
     write!(stderr, "Working Directory:")?;
     stderr.reset()?;
@@ -152,31 +346,224 @@ pub(crate) fn judge(args: Args<impl WriteColor, impl WriteColor>) -> anyhow::Res
 
     stderr.flush()?;
 
-    let outcome = snowchains_core::judge::judge(
-        progress_draw_target,
-        tokio::signal::ctrl_c,
-        &cmd,
-        &test_cases,
-    )?;
+    let outcome = {
+        // The judge kills the running programs by itself on Ctrl-C.
+        let _guard = crate::ctrl_c::Guard::new();
+        snowchains_core::judge::judge(
+            progress_draw_target,
+            tokio::signal::ctrl_c,
+            &cmd,
+            &test_cases,
+            fail_fast,
+            precise_time,
+        )?
+    };
 
     if let Some(tempfile) = tempfile {
         tempfile.close()?;
     }
 
+    if stdin_input.is_none() && input_and_expected.is_none() {
+        let failed = outcome
+            .verdicts
+            .iter()
+            .filter(|v| !matches!(v, Verdict::Accepted { .. }));
+        let last_run = LastRun {
+            failed: failed
+                .clone()
+                .flat_map(Verdict::test_case_name)
+                .map(ToOwned::to_owned)
+                .collect(),
+            unnamed_failed: failed.clone().any(|v| v.test_case_name().is_none()),
+        };
+        crate::fs::write_json(&last_run_path, last_run, true)?;
+    }
+
     writeln!(stderr)?;
     stderr.flush()?;
-    outcome.print_pretty(
-        stdout,
-        Some(display_limit.into::<Byte>().value().saturating_as()),
-    )?;
+    match format {
+        crate::JudgeFormat::Human => {
+            outcome.print_pretty(&mut stdout, display_limit.to_bytes())?;
+            writeln!(stdout)?;
+            outcome.print_timing_summary(&mut stdout)?;
+            outcome.print_verdict_summary(&mut stdout)?;
+            if num_skipped > 0 {
+                writeln!(
+                    stdout,
+                    "Skipped {} test case{} because of `--max-cases`",
+                    num_skipped,
+                    if num_skipped == 1 { "" } else { "s" },
+                )?;
+            }
+        }
+        crate::JudgeFormat::Tap => outcome.print_tap(&mut stdout)?,
+    }
+
+    if summary {
+        let total = outcome.verdicts.len();
+        let passed = outcome.num_accepted();
+        write!(
+            stdout,
+            "RESULT passed={} failed={} total={}",
+            passed,
+            total - passed,
+            total,
+        )?;
+        if num_skipped > 0 {
+            write!(stdout, " skipped={}", num_skipped)?;
+        }
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+
+    let failed = outcome
+        .verdicts
+        .iter()
+        .find(|v| !matches!(v, Verdict::Accepted { .. }));
+
+    let (hook_name, hook) = match failed {
+        None => ("onPass", hooks.onPass),
+        Some(_) => ("onFail", hooks.onFail),
+    };
+
+    if let Some(hook) = hook {
+        let verdict = failed.map_or("Accepted", Verdict::name);
+
+        if let Err(err) = run_hook(
+            &hook,
+            &cmd.cwd,
+            &problem,
+            verdict,
+            stdin_process_redirection(),
+            stderr_process_redirection(),
+            &mut stderr,
+        ) {
+            stderr.set_color(color_spec!(Bold, Fg(Color::Yellow)))?;
+            write!(stderr, "warning:")?;
+            stderr.reset()?;
+            writeln!(stderr, " `hooks.{}` failed: {:#}", hook_name, err)?;
+            stderr.flush()?;
+        }
+    }
+
+    outcome.error_on_fail().context(crate::Failure::TestsFailed)
+}
+
+/// Runs `hooks.onPass` or `hooks.onFail` with the problem and the verdict appended to the
+/// arguments. They are also set to `$SNOWCHAINS_PROBLEM` and `$SNOWCHAINS_VERDICT`.
+///
+/// The stdout of the hook is written to `stderr` so that it does not mix with `--summary` or TAP.
+fn run_hook(
+    hook: &config::Command,
+    base_dir: &Path,
+    problem: &str,
+    verdict: &str,
+    stdin_process_redirection: Stdio,
+    stderr_process_redirection: Stdio,
+    mut stderr: impl WriteColor,
+) -> anyhow::Result<()> {
+    let mut tempfile = None;
+
+    let (program, mut args): (OsString, Vec<OsString>) = match hook {
+        config::Command::Args(args) => {
+            let program = args.first().with_context(|| "The command is empty")?;
+            (program.into(), args[1..].iter().map(Into::into).collect())
+        }
+        config::Command::Script(config::Script {
+            program,
+            extension,
+            content,
+        }) => {
+            let mut file = tempfile::Builder::new()
+                .prefix("snowchains-hook")
+                .suffix(&format!(".{}", extension))
+                .tempfile()?;
+            file.write_all(content.as_ref())?;
+            file.flush()?;
+            let args = vec![file.path().into()];
+            tempfile = Some(file);
+            (program.into(), args)
+        }
+    };
+    args.push(problem.into());
+    args.push(verdict.into());
+
+    let shell_escaped = shell_escape_args(&program, &args);
+
+    stderr.set_color(color_spec!(Bold, Fg(Color::Magenta)))?;
+    write!(stderr, "Hook:")?;
+    stderr.reset()?;
+    writeln!(stderr, " {}", shell_escaped)?;
+    stderr.flush()?;
+
+    let output = std::process::Command::new(&program)
+        .args(&args)
+        .current_dir(base_dir)
+        .env("SNOWCHAINS_PROBLEM", problem)
+        .env("SNOWCHAINS_VERDICT", verdict)
+        .stdin(stdin_process_redirection)
+        .stdout(Stdio::piped())
+        .stderr(stderr_process_redirection)
+        .output()
+        .with_context(|| format!("Could not execute {}", shell_escaped))?;
+
+    stderr.write_all(&output.stdout)?;
+    stderr.flush()?;
+
+    if let Some(tempfile) = tempfile {
+        tempfile.close()?;
+    }
+
+    if !output.status.success() {
+        bail!("{} failed ({})", shell_escaped, output.status);
+    }
+    Ok(())
+}
+
+/// Extensions of test suite files, in the order of priority.
+pub(crate) const TEST_SUITE_EXTENSIONS: &[&str] = &[
+    "yml", "yaml", "json", "toml", "yml.gz", "yaml.gz", "json.gz", "toml.gz",
+];
+
+pub(crate) fn test_suite_path(
+    base_dir: &Path,
+    service: PlatformKind,
+    contest: Option<&str>,
+    problem: &str,
+) -> PathBuf {
+    let test_suite_dir = base_dir
+        .join(".snowchains")
+        .join("tests")
+        .join(service.to_kebab_case_str())
+        .join(contest.unwrap_or(""));
 
-    outcome.error_on_fail()
+    existing_test_suite_file(&test_suite_dir, problem)
+}
+
+/// Returns `<dir>/<name>.<ext>` for the first `ext` in [`TEST_SUITE_EXTENSIONS`] that exists,
+/// or `<dir>/<name>.yml`.
+pub(crate) fn existing_test_suite_file(dir: &Path, name: &str) -> PathBuf {
+    TEST_SUITE_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(name).with_extension(ext))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| dir.join(name).with_extension("yml"))
+}
+
+/// Test cases that failed in the last run.
+#[derive(Deserialize, Serialize)]
+struct LastRun {
+    failed: HashSet<String>,
+    /// Whether any test case without a name failed. Such test cases cannot be told apart, so all
+    /// of them are tested again.
+    #[serde(default)]
+    unnamed_failed: bool,
 }
 
 pub(crate) fn transpile(
     stderr: impl WriteColor,
     base_dir: &Path,
-    src: &str,
+    srcs: &[PathBuf],
     transpile: &config::Compile,
     stdin_process_redirection: fn() -> Stdio,
     stdout_process_redirection: fn() -> Stdio,
@@ -185,7 +572,7 @@ pub(crate) fn transpile(
     build(
         stderr,
         base_dir,
-        src,
+        srcs,
         transpile,
         (
             stdin_process_redirection,
@@ -193,33 +580,45 @@ pub(crate) fn transpile(
             stderr_process_redirection,
         ),
         "Transpiling...",
+        false,
     )
+    .context(crate::Failure::BuildFailed)
 }
 
 #[allow(clippy::type_complexity)]
 fn build(
     mut stderr: impl WriteColor,
     base_dir: &Path,
-    src: &str,
+    srcs: &[PathBuf],
     build_action: &config::Compile,
     redirections: (fn() -> Stdio, fn() -> Stdio, fn() -> Stdio),
     msg: &'static str,
+    force: bool,
 ) -> anyhow::Result<()> {
-    let src_modified = {
-        let src = Path::new(&src);
-        let src = base_dir.join(src.strip_prefix(".").unwrap_or(src));
-        crate::fs::metadata(src)?.modified()?
-    };
+    let src_modified = srcs
+        .iter()
+        .map(|src| crate::fs::metadata(src)?.modified().map_err(Into::into))
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter()
+        .max()
+        .expect("`srcs` should contain `src`");
+
+    let config::Compile {
+        command,
+        output: _,
+        env,
+        timeout,
+    } = build_action;
 
-    let config::Compile { command, output } = build_action;
+    let env = env.as_deref().unwrap_or_default();
+    let timeout = timeout.map(Duration::from_secs);
 
-    let output = Path::new(&output);
-    let output = base_dir.join(output.strip_prefix(".").unwrap_or(output));
+    let output = build_output(base_dir, build_action);
 
     let (stdin_process_redirection, stdout_process_redirection, stderr_process_redirection) =
         redirections;
 
-    if output.exists() && crate::fs::metadata(&output)?.modified()? > src_modified {
+    if !force && output.exists() && crate::fs::metadata(&output)?.modified()? > src_modified {
         writeln!(stderr, "{} is up to date.", output.display())?;
         stderr.flush()?;
     } else {
@@ -244,12 +643,26 @@ fn build(
 
         match command {
             config::Command::Args(args) => run_command(
-                args.get(0).map(Deref::deref).unwrap_or(""),
-                args.iter().skip(1),
-                base_dir,
-                stdin_process_redirection(),
-                stdout_process_redirection(),
-                stderr_process_redirection(),
+                BuildCommand {
+                    program: args.first().map(Deref::deref).unwrap_or("").as_ref(),
+                    args: args
+                        .iter()
+                        .skip(1)
+                        .flat_map(|arg| -> Vec<&OsStr> {
+                            if arg == "$srcs" || arg == "${srcs}" {
+                                srcs.iter().map(AsRef::as_ref).collect()
+                            } else {
+                                vec![arg.as_ref()]
+                            }
+                        })
+                        .collect(),
+                    base_dir,
+                    env,
+                    timeout,
+                    stdin: stdin_process_redirection(),
+                    stdout: stdout_process_redirection(),
+                    stderr: stderr_process_redirection(),
+                },
                 &mut stderr,
             )?,
             config::Command::Script(config::Script {
@@ -265,12 +678,16 @@ fn build(
                 tempfile.write_all(content.as_ref())?;
 
                 run_command(
-                    program,
-                    &[tempfile.path()],
-                    base_dir,
-                    stdin_process_redirection(),
-                    stdout_process_redirection(),
-                    stderr_process_redirection(),
+                    BuildCommand {
+                        program: program.as_ref(),
+                        args: vec![tempfile.path().as_ref()],
+                        base_dir,
+                        env,
+                        timeout,
+                        stdin: stdin_process_redirection(),
+                        stdout: stdout_process_redirection(),
+                        stderr: stderr_process_redirection(),
+                    },
                     &mut stderr,
                 )?;
 
@@ -282,21 +699,108 @@ fn build(
     Ok(())
 }
 
-fn run_command<S1: AsRef<OsStr>, S2: AsRef<OsStr>, I: IntoIterator<Item = S2>, W: WriteColor>(
-    program: S1,
-    args: I,
+/// Resolves `src` and `srcs` of a language into files, `src` first.
+///
+/// Each of `srcs` is a file, a directory to search recursively, or a glob relative to `base_dir`.
+pub(crate) fn src_paths(
     base_dir: &Path,
-    stdin_process_redirection: Stdio,
-    stdout_process_redirection: Stdio,
-    stderr_process_redirection: Stdio,
-    mut stderr: W,
-) -> anyhow::Result<()> {
-    let program = program.as_ref();
+    src: &str,
+    srcs: &[String],
+) -> anyhow::Result<Vec<PathBuf>> {
+    let resolve = |p: &str| {
+        base_dir.join(
+            Path::new(p)
+                .strip_prefix(".")
+                .unwrap_or_else(|_| p.as_ref()),
+        )
+    };
 
-    let args = args
-        .into_iter()
-        .map(|s| s.as_ref().to_owned())
-        .collect::<Vec<_>>();
+    let mut paths = vec![resolve(src)];
+
+    for pattern in srcs {
+        let path = resolve(pattern);
+
+        let mut found = if path.is_file() {
+            vec![path]
+        } else {
+            let (root, glob) = if path.is_dir() {
+                (path, "**".to_owned())
+            } else {
+                // Walks from the deepest directory without glob characters.
+                let segments = pattern
+                    .strip_prefix("./")
+                    .unwrap_or(pattern)
+                    .split('/')
+                    .collect::<Vec<_>>();
+                let i = segments
+                    .iter()
+                    .position(|s| s.contains(['*', '?', '[', '{']))
+                    .unwrap_or(segments.len());
+                (
+                    base_dir.join(segments[..i].join("/")),
+                    segments[i..].join("/"),
+                )
+            };
+
+            if !root.is_dir() {
+                vec![]
+            } else {
+                let overrides = OverrideBuilder::new(&root)
+                    .add(&format!("/{}", glob))?
+                    .build()?;
+                let mut found = WalkBuilder::new(&root)
+                    .overrides(overrides)
+                    .standard_filters(false)
+                    .build()
+                    .map(|entry| Ok(entry?.into_path()))
+                    .filter(|path| !matches!(path, Ok(path) if path.is_dir()))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                found.sort();
+                found
+            }
+        };
+
+        ensure!(
+            !found.is_empty(),
+            "`{}` in `srcs` matched no files",
+            pattern
+        );
+
+        found.retain(|p| !paths.contains(p));
+        paths.extend(found);
+    }
+
+    Ok(paths)
+}
+
+pub(crate) fn build_output(base_dir: &Path, build_action: &config::Compile) -> PathBuf {
+    let output = Path::new(&build_action.output);
+    base_dir.join(output.strip_prefix(".").unwrap_or(output))
+}
+
+/// A `transpile` or `compile` command to run.
+struct BuildCommand<'a> {
+    program: &'a OsStr,
+    args: Vec<&'a OsStr>,
+    base_dir: &'a Path,
+    env: &'a [config::EnvVar],
+    timeout: Option<Duration>,
+    stdin: Stdio,
+    stdout: Stdio,
+    stderr: Stdio,
+}
+
+fn run_command(command: BuildCommand<'_>, mut stderr: impl WriteColor) -> anyhow::Result<()> {
+    let BuildCommand {
+        program,
+        args,
+        base_dir,
+        env,
+        timeout,
+        stdin: stdin_process_redirection,
+        stdout: stdout_process_redirection,
+        stderr: stderr_process_redirection,
+    } = command;
 
     let shell_escaped = shell_escape_args(program, &args);
 
@@ -312,13 +816,45 @@ fn run_command<S1: AsRef<OsStr>, S2: AsRef<OsStr>, I: IntoIterator<Item = S2>, W
 
     stderr.flush()?;
 
-    let status = std::process::Command::new(program)
+    let mut child = std::process::Command::new(program)
         .args(&args)
         .current_dir(base_dir)
+        .envs(env.iter().map(|e| (&e.mapKey, &e.mapValue)))
         .stdin(stdin_process_redirection)
         .stdout(stdout_process_redirection)
         .stderr(stderr_process_redirection)
-        .status()?;
+        .spawn()
+        .map_err(|err| {
+            if err.kind() == io::ErrorKind::NotFound {
+                anyhow!(
+                    "Could not find `{}`. Check `transpile` and `compile` in `snowchains.dhall`",
+                    program.to_string_lossy(),
+                )
+            } else {
+                err.into()
+            }
+        })?;
+
+    let status = if let Some(timeout) = timeout {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                child.kill()?;
+                child.wait()?;
+                bail!(
+                    "{} timed out after {}s and was killed",
+                    shell_escaped,
+                    timeout.as_secs(),
+                );
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    } else {
+        child.wait()?
+    };
 
     if !status.success() {
         bail!(
@@ -335,7 +871,7 @@ fn run_command<S1: AsRef<OsStr>, S2: AsRef<OsStr>, I: IntoIterator<Item = S2>, W
     Ok(())
 }
 
-fn shell_escape_args(program: impl AsRef<OsStr>, args: &[impl AsRef<OsStr>]) -> String {
+pub(crate) fn shell_escape_args(program: impl AsRef<OsStr>, args: &[impl AsRef<OsStr>]) -> String {
     format!(
         "`{}`",
         iter::once(program.as_ref())
@@ -344,3 +880,52 @@ fn shell_escape_args(program: impl AsRef<OsStr>, args: &[impl AsRef<OsStr>]) ->
             .format(" "),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    #[test]
+    fn src_paths() -> anyhow::Result<()> {
+        let tempdir = tempfile::Builder::new()
+            .prefix("snowchains-judge-test-")
+            .tempdir()?;
+        let base_dir = tempdir.path();
+
+        for file in &[
+            "Main.java",
+            "lib/A.java",
+            "lib/b/B.java",
+            "lib/README",
+            "C.java",
+        ] {
+            let path = base_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, "")?;
+        }
+
+        let src_paths = |srcs: &[&str]| -> anyhow::Result<_> {
+            let srcs = srcs.iter().map(|&s| s.to_owned()).collect::<Vec<_>>();
+            Ok(super::src_paths(base_dir, "./Main.java", &srcs)?
+                .into_iter()
+                .map(|p| p.strip_prefix(base_dir).unwrap().to_owned())
+                .collect::<Vec<_>>())
+        };
+
+        assert_eq!(
+            vec![base_dir.join("Main.java")],
+            super::src_paths(base_dir, "./Main.java", &[])?
+        );
+        assert_eq!(
+            ["Main.java", "C.java", "lib/A.java", "lib/b/B.java"]
+                .iter()
+                .map(Into::into)
+                .collect::<Vec<PathBuf>>(),
+            src_paths(&["./C.java", "./lib/**/*.java", "./Main.java"])?,
+        );
+        assert_eq!(4, src_paths(&["./lib/"])?.len());
+        assert!(src_paths(&["./nonexistent/*.java"]).is_err());
+
+        tempdir.close().map_err(Into::into)
+    }
+}
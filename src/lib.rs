@@ -1,18 +1,24 @@
 mod commands;
 mod config;
+pub mod ctrl_c;
 mod fs;
 mod judge;
+pub mod logger;
 pub mod shell;
 mod web;
 
 pub use crate::commands::{
-    init::OptInit, judge::OptJudge, login::OptLogin, participate::OptParticipate,
-    retrieve_languages::OptRetrieveLanguages,
+    config_check::OptConfigCheck, config_resolve::OptConfigResolve, convert::OptConvert,
+    doctor::OptDoctor, import::OptImport, init::OptInit, judge::OptJudge, login::OptLogin,
+    modify_match::OptModifyMatch, new::OptNew, open::OptOpen, participate::OptParticipate,
+    results::OptResults, retrieve_languages::OptRetrieveLanguages,
     retrieve_submission_summaries::OptRetrieveSubmissionSummaries,
     retrieve_testcases::OptRetrieveTestcases, submit::OptSubmit,
     watch_submissions::OptWatchSubmissions, xtask::OptXtask,
 };
-use std::{env, io::BufRead, path::PathBuf};
+use az::SaturatingAs as _;
+use human_size::{Byte, Size};
+use std::{env, fmt, io::BufRead, path::PathBuf, str::FromStr, time::Duration};
 use structopt::{
     clap::{self, AppSettings},
     StructOpt,
@@ -22,9 +28,32 @@ use termcolor::WriteColor;
 
 pub const STACK_SIZE: usize = 128 * 1024 * 1024;
 
+static EXIT_STATUS_HELP: &str = "EXIT STATUS:
+    0    Succeeded
+    1    Any other error
+    2    Some of the test cases failed
+    3    Failed to transpile or compile the code
+    4    The submission was not accepted
+    5    Timed out waiting for the verdict";
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    author,
+    about,
+    after_help(EXIT_STATUS_HELP),
+    global_setting = AppSettings::DeriveDisplayOrder
+)]
+pub struct Opt {
+    /// Path to `snowchains.dhall` for subcommands without their own `--config`
+    #[structopt(long, value_name("PATH"))]
+    pub config: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub command: OptCommand,
+}
+
 #[derive(StructOpt, Debug)]
-#[structopt(author, about, global_setting = AppSettings::DeriveDisplayOrder)]
-pub enum Opt {
+pub enum OptCommand {
     /// Create a new config file
     #[structopt(author, visible_alias("i"))]
     Init(OptInit),
@@ -48,6 +77,14 @@ pub enum Opt {
     #[structopt(author, visible_alias("w"))]
     Watch(OptWatch),
 
+    /// Lists your submissions
+    #[structopt(author)]
+    Results(OptResults),
+
+    /// Creates `src` from `template` of the language
+    #[structopt(author, visible_alias("n"))]
+    New(OptNew),
+
     /// Tests code
     #[structopt(author, visible_aliases(&["j", "test", "t"]))]
     Judge(OptJudge),
@@ -56,9 +93,33 @@ pub enum Opt {
     #[structopt(author, visible_alias("s"))]
     Submit(OptSubmit),
 
+    /// Opens the problem page in the browser
+    #[structopt(author, visible_alias("o"))]
+    Open(OptOpen),
+
+    /// Converts a test suite into another format
+    #[structopt(author)]
+    Convert(OptConvert),
+
+    /// Imports test cases from a local zip archive
+    #[structopt(author)]
+    Import(OptImport),
+
+    /// Modifies a test suite
+    #[structopt(author)]
+    Modify(OptModify),
+
     /// Runs a custom subcommand written in the config file
     #[structopt(author, visible_alias("x"), setting = AppSettings::TrailingVarArg)]
     Xtask(OptXtask),
+
+    /// Manages the config file
+    #[structopt(author)]
+    Config(OptConfig),
+
+    /// Checks that the programs used in `languages` are installed
+    #[structopt(author)]
+    Doctor(OptDoctor),
 }
 
 #[derive(StructOpt, Debug)]
@@ -76,6 +137,24 @@ pub enum OptRetrieve {
     SubmissionSummaries(OptRetrieveSubmissionSummaries),
 }
 
+#[derive(StructOpt, Debug)]
+pub enum OptConfig {
+    /// Evaluates the config without executing anything
+    #[structopt(author, visible_alias("c"))]
+    Check(OptConfigCheck),
+
+    /// Prints the paths and the command resolved for a target
+    #[structopt(author, visible_alias("r"))]
+    Resolve(OptConfigResolve),
+}
+
+#[derive(StructOpt, Debug)]
+pub enum OptModify {
+    /// Sets `match` of a test suite
+    #[structopt(author)]
+    Match(OptModifyMatch),
+}
+
 #[derive(StructOpt, Debug)]
 pub enum OptWatch {
     /// Watches your submissions
@@ -88,10 +167,16 @@ impl Opt {
         let mut args = env::args_os().collect::<Vec<_>>();
 
         Self::from_iter_safe(&args).unwrap_or_else(|clap::Error { kind, .. }| {
+            let i = match args.get(1).and_then(|s| s.to_str()) {
+                Some("--config") => 3,
+                Some(s) if s.starts_with("--config=") => 2,
+                _ => 1,
+            };
+
             if matches!(
-                args.get(1).and_then(|s| s.to_str()),
+                args.get(i).and_then(|s| s.to_str()),
                 Some("x") | Some("xtask")
-            ) && matches!(args.get(2).and_then(|s| s.to_str()), Some(s) if !s.starts_with('-'))
+            ) && matches!(args.get(i + 1).and_then(|s| s.to_str()), Some(s) if !s.starts_with('-'))
                 && matches!(
                     kind,
                     clap::ErrorKind::UnknownArgument
@@ -99,7 +184,7 @@ impl Opt {
                         | clap::ErrorKind::VersionDisplayed
                 )
             {
-                args.insert(3, "--".into());
+                args.insert(i + 2, "--".into());
             }
 
             Self::from_iter(args)
@@ -107,6 +192,12 @@ impl Opt {
     }
 
     pub fn color(&self) -> crate::ColorChoice {
+        self.command.color()
+    }
+}
+
+impl OptCommand {
+    fn color(&self) -> crate::ColorChoice {
         match *self {
             Self::Init(OptInit { color, .. })
             | Self::Login(OptLogin { color, .. })
@@ -119,11 +210,157 @@ impl Opt {
             }))
             | Self::Download(OptRetrieveTestcases { color, .. })
             | Self::Watch(OptWatch::Submissions(OptWatchSubmissions { color, .. }))
+            | Self::Results(OptResults { color, .. })
+            | Self::New(OptNew { color, .. })
             | Self::Judge(OptJudge { color, .. })
-            | Self::Submit(OptSubmit { color, .. }) => color,
+            | Self::Submit(OptSubmit { color, .. })
+            | Self::Open(OptOpen { color, .. })
+            | Self::Convert(OptConvert { color, .. })
+            | Self::Import(OptImport { color, .. })
+            | Self::Modify(OptModify::Match(OptModifyMatch { color, .. }))
+            | Self::Config(OptConfig::Check(OptConfigCheck { color, .. }))
+            | Self::Config(OptConfig::Resolve(OptConfigResolve { color, .. }))
+            | Self::Doctor(OptDoctor { color, .. }) => color,
             Self::Xtask(_) => crate::ColorChoice::Auto,
         }
     }
+
+    fn config_mut(&mut self) -> Option<&mut Option<PathBuf>> {
+        match self {
+            Self::Init(_) => None,
+            Self::Login(OptLogin { config, .. })
+            | Self::Participate(OptParticipate { config, .. })
+            | Self::Retrieve(OptRetrieve::Languages(OptRetrieveLanguages { config, .. }))
+            | Self::Retrieve(OptRetrieve::Testcases(OptRetrieveTestcases { config, .. }))
+            | Self::Retrieve(OptRetrieve::SubmissionSummaries(OptRetrieveSubmissionSummaries {
+                config,
+                ..
+            }))
+            | Self::Download(OptRetrieveTestcases { config, .. })
+            | Self::Watch(OptWatch::Submissions(OptWatchSubmissions { config, .. }))
+            | Self::Results(OptResults { config, .. })
+            | Self::New(OptNew { config, .. })
+            | Self::Judge(OptJudge { config, .. })
+            | Self::Submit(OptSubmit { config, .. })
+            | Self::Open(OptOpen { config, .. })
+            | Self::Convert(OptConvert { config, .. })
+            | Self::Import(OptImport { config, .. })
+            | Self::Modify(OptModify::Match(OptModifyMatch { config, .. }))
+            | Self::Xtask(OptXtask { config, .. })
+            | Self::Config(OptConfig::Check(OptConfigCheck { config, .. }))
+            | Self::Config(OptConfig::Resolve(OptConfigResolve { config, .. }))
+            | Self::Doctor(OptDoctor { config, .. }) => Some(config),
+        }
+    }
+}
+
+/// Failures that have their own exit codes.
+///
+/// | Exit code | Meaning                                   |
+/// | --------- | ----------------------------------------- |
+/// | 0         | Succeeded                                 |
+/// | 1         | Any other error                           |
+/// | 2         | Some of the test cases failed             |
+/// | 3         | Failed to transpile or compile the code   |
+/// | 4         | The submission was not accepted           |
+/// | 5         | Timed out waiting for the verdict         |
+/// | 130       | Interrupted by Ctrl-C                     |
+#[derive(strum::Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    #[strum(serialize = "Some of the test cases failed")]
+    TestsFailed,
+    #[strum(serialize = "Failed to build the code")]
+    BuildFailed,
+    #[strum(serialize = "The submission was not accepted")]
+    NotAccepted,
+    #[strum(serialize = "Timed out waiting for the verdict")]
+    VerdictUnknown,
+    #[strum(serialize = "Interrupted by Ctrl-C")]
+    Interrupted,
+}
+
+impl Failure {
+    pub fn exit_code(err: &anyhow::Error) -> i32 {
+        match err.downcast_ref::<Self>() {
+            Some(Self::TestsFailed) => 2,
+            Some(Self::BuildFailed) => 3,
+            Some(Self::NotAccepted) => 4,
+            Some(Self::VerdictUnknown) => 5,
+            Some(Self::Interrupted) => crate::ctrl_c::EXIT_CODE,
+            None if err.is::<snowchains_core::judge::Interrupted>() => crate::ctrl_c::EXIT_CODE,
+            None => 1,
+        }
+    }
+
+    fn from_exit_code(code: Option<i32>) -> Option<Self> {
+        match code {
+            Some(2) => Some(Self::TestsFailed),
+            Some(3) => Some(Self::BuildFailed),
+            Some(4) => Some(Self::NotAccepted),
+            Some(5) => Some(Self::VerdictUnknown),
+            Some(crate::ctrl_c::EXIT_CODE) => Some(Self::Interrupted),
+            _ => None,
+        }
+    }
+}
+
+/// `<SIZE>` or `none`.
+#[derive(Debug, Clone, Copy)]
+pub enum DisplayLimit {
+    Size(Size),
+    None,
+}
+
+impl DisplayLimit {
+    fn to_bytes(self) -> Option<usize> {
+        match self {
+            Self::Size(size) => Some(size.into::<Byte>().value().saturating_as()),
+            Self::None => None,
+        }
+    }
+}
+
+impl FromStr for DisplayLimit {
+    type Err = human_size::ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, human_size::ParsingError> {
+        if s.eq_ignore_ascii_case("none") {
+            Ok(Self::None)
+        } else {
+            s.parse().map(Self::Size)
+        }
+    }
+}
+
+impl fmt::Display for DisplayLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Size(size) => write!(f, "{}", size),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+/// `--http-timeout` and `session.timeout`.
+#[derive(Debug, Clone, Copy)]
+pub enum HttpTimeout {
+    Duration(Duration),
+    None,
+}
+
+impl FromStr for HttpTimeout {
+    type Err = anyhow::Error;
+
+    /// Parses `none`, seconds (`30`), or a duration (`1m 30s`).
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if s.eq_ignore_ascii_case("none") {
+            Ok(Self::None)
+        } else if let Ok(secs) = s.parse() {
+            Ok(Self::Duration(Duration::from_secs(secs)))
+        } else {
+            Ok(Self::Duration(humantime::parse_duration(s)?))
+        }
+    }
 }
 
 #[derive(EnumVariantNames, EnumString, strum::Display, Debug, Clone, Copy)]
@@ -134,6 +371,13 @@ pub enum ColorChoice {
     Never,
 }
 
+#[derive(EnumVariantNames, EnumString, strum::Display, Debug, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum JudgeFormat {
+    Human,
+    Tap,
+}
+
 pub struct Context<R, W1, W2> {
     pub cwd: PathBuf,
     pub shell: crate::shell::Shell<R, W1, W2>,
@@ -143,19 +387,43 @@ pub fn run<R: BufRead, W1: WriteColor, W2: WriteColor>(
     opt: Opt,
     ctx: Context<R, W1, W2>,
 ) -> anyhow::Result<()> {
+    let Opt {
+        config,
+        command: mut opt,
+    } = opt;
+
+    if let Some(config) = config {
+        if let Some(opt_config @ None) = opt.config_mut() {
+            *opt_config = Some(config);
+        }
+    }
+
     match opt {
-        Opt::Init(opt) => commands::init::run(opt, ctx),
-        Opt::Login(opt) => commands::login::run(opt, ctx),
-        Opt::Participate(opt) => commands::participate::run(opt, ctx),
-        Opt::Retrieve(OptRetrieve::Languages(opt)) => commands::retrieve_languages::run(opt, ctx),
-        Opt::Retrieve(OptRetrieve::Testcases(opt)) => commands::retrieve_testcases::run(opt, ctx),
-        Opt::Retrieve(OptRetrieve::SubmissionSummaries(opt)) => {
+        OptCommand::Init(opt) => commands::init::run(opt, ctx),
+        OptCommand::Login(opt) => commands::login::run(opt, ctx),
+        OptCommand::Participate(opt) => commands::participate::run(opt, ctx),
+        OptCommand::Retrieve(OptRetrieve::Languages(opt)) => {
+            commands::retrieve_languages::run(opt, ctx)
+        }
+        OptCommand::Retrieve(OptRetrieve::Testcases(opt)) => {
+            commands::retrieve_testcases::run(opt, ctx)
+        }
+        OptCommand::Retrieve(OptRetrieve::SubmissionSummaries(opt)) => {
             commands::retrieve_submission_summaries::run(opt, ctx)
         }
-        Opt::Download(opt) => commands::retrieve_testcases::run(opt, ctx),
-        Opt::Watch(OptWatch::Submissions(opt)) => commands::watch_submissions::run(opt, ctx),
-        Opt::Judge(opt) => commands::judge::run(opt, ctx),
-        Opt::Submit(opt) => commands::submit::run(opt, ctx),
-        Opt::Xtask(opt) => commands::xtask::run(opt, ctx),
+        OptCommand::Download(opt) => commands::retrieve_testcases::run(opt, ctx),
+        OptCommand::Watch(OptWatch::Submissions(opt)) => commands::watch_submissions::run(opt, ctx),
+        OptCommand::Results(opt) => commands::results::run(opt, ctx),
+        OptCommand::New(opt) => commands::new::run(opt, ctx),
+        OptCommand::Judge(opt) => commands::judge::run(opt, ctx),
+        OptCommand::Submit(opt) => commands::submit::run(opt, ctx),
+        OptCommand::Open(opt) => commands::open::run(opt, ctx),
+        OptCommand::Convert(opt) => commands::convert::run(opt, ctx),
+        OptCommand::Import(opt) => commands::import::run(opt, ctx),
+        OptCommand::Modify(OptModify::Match(opt)) => commands::modify_match::run(opt, ctx),
+        OptCommand::Xtask(opt) => commands::xtask::run(opt, ctx),
+        OptCommand::Config(OptConfig::Check(opt)) => commands::config_check::run(opt, ctx),
+        OptCommand::Config(OptConfig::Resolve(opt)) => commands::config_resolve::run(opt, ctx),
+        OptCommand::Doctor(opt) => commands::doctor::run(opt, ctx),
     }
 }
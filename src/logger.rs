@@ -0,0 +1,98 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    env,
+    io::{self, Write as _},
+};
+
+/// Enables `log` records according to `RUST_LOG` (e.g. `snowchains=debug,warn`).
+///
+/// Does nothing if `RUST_LOG` is not set. Records are written to stderr.
+pub fn init() {
+    if let Some(spec) = env::var_os("RUST_LOG") {
+        let logger = Logger::parse(&spec.to_string_lossy());
+        let max_level = logger.max_level();
+        if log::set_boxed_logger(Box::new(logger)).is_ok() {
+            log::set_max_level(max_level);
+        }
+    }
+}
+
+struct Logger {
+    /// `(target prefix, level)`s. `None` applies to every target.
+    directives: Vec<(Option<String>, LevelFilter)>,
+}
+
+impl Logger {
+    fn parse(spec: &str) -> Self {
+        let directives = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .flat_map(|directive| match directive.split_once('=') {
+                Some((target, level)) => Some((Some(target.to_owned()), level.parse().ok()?)),
+                None => match directive.parse() {
+                    Ok(level) => Some((None, level)),
+                    Err(_) => Some((Some(directive.to_owned()), LevelFilter::Trace)),
+                },
+            })
+            .collect();
+
+        Self { directives }
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|&(_, level)| level)
+            .max()
+            .unwrap_or(LevelFilter::Off)
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| prefix.as_deref().is_none_or(|p| target.starts_with(p)))
+            .max_by_key(|(prefix, _)| prefix.as_ref().map_or(0, String::len))
+            .map_or(LevelFilter::Off, |&(_, level)| level)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(
+                io::stderr(),
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                record.args(),
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Logger;
+    use log::LevelFilter;
+
+    #[test]
+    fn level_for() {
+        let logger = Logger::parse("warn,snowchains_core::web=trace,snowchains=debug");
+        assert_eq!(LevelFilter::Trace, logger.max_level());
+        assert_eq!(LevelFilter::Trace, logger.level_for("snowchains_core::web"));
+        assert_eq!(
+            LevelFilter::Debug,
+            logger.level_for("snowchains_core::judge")
+        );
+        assert_eq!(LevelFilter::Debug, logger.level_for("snowchains::config"));
+        assert_eq!(LevelFilter::Warn, logger.level_for("reqwest::connect"));
+        assert_eq!(LevelFilter::Off, Logger::parse("").level_for("snowchains"));
+    }
+}
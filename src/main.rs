@@ -8,9 +8,13 @@ use std::{
 use termcolor::{Color, WriteColor as _};
 
 fn main() {
+    snowchains::logger::init();
+
     let opt = snowchains::Opt::from_args_with_workaround_for_clap_issue_1538();
     let color = opt.color();
 
+    snowchains::ctrl_c::install_handler(color);
+
     run_with_large_stack(|| {
         let stdin = io::stdin();
 
@@ -61,7 +65,7 @@ fn main() {
             let _ = stderr.write_all(b"\n");
             let _ = stderr.flush();
 
-            process::exit(1);
+            process::exit(snowchains::Failure::exit_code(&err));
         }
     });
 }
@@ -2,7 +2,7 @@ use indicatif::ProgressDrawTarget;
 use snowchains_core::{color_spec, web::StatusCodeColor};
 use std::{
     env, fmt,
-    io::{self, BufRead, Stdin, StdinLock, Write},
+    io::{self, BufRead, Read as _, Stdin, StdinLock, Write},
     process::Stdio,
 };
 use termcolor::{BufferedStandardStream, Color, WriteColor};
@@ -19,16 +19,10 @@ pub struct Shell<R, W1, W2> {
 
 impl<'a> Shell<StdinLock<'a>, BufferedStandardStream, BufferedStandardStream> {
     pub fn new(stdin: &'a Stdin, color: crate::ColorChoice) -> Self {
-        let convert_with_atty_fitler = |stream| match (color, atty::is(stream)) {
-            (crate::ColorChoice::Auto, true) => termcolor::ColorChoice::Auto,
-            (crate::ColorChoice::Always, _) => termcolor::ColorChoice::Always,
-            _ => termcolor::ColorChoice::Never,
-        };
-
         Self {
             stdin: TtyOrPiped::auto(stdin),
-            stdout: BufferedStandardStream::stdout(convert_with_atty_fitler(atty::Stream::Stdout)),
-            stderr: BufferedStandardStream::stderr(convert_with_atty_fitler(atty::Stream::Stderr)),
+            stdout: BufferedStandardStream::stdout(color.for_stream(atty::Stream::Stdout)),
+            stderr: BufferedStandardStream::stderr(color.for_stream(atty::Stream::Stderr)),
             stderr_tty: atty::is(atty::Stream::Stderr),
             stdin_process_redirection: Stdio::inherit,
             stdout_process_redirection: Stdio::inherit,
@@ -37,6 +31,23 @@ impl<'a> Shell<StdinLock<'a>, BufferedStandardStream, BufferedStandardStream> {
     }
 }
 
+impl crate::ColorChoice {
+    /// Decides whether to color `stream`.
+    ///
+    /// `auto` colors only a TTY, and not at all if `NO_COLOR` is set to a non-empty value. `always` ignores both.
+    pub(crate) fn for_stream(self, stream: atty::Stream) -> termcolor::ColorChoice {
+        match self {
+            Self::Always => termcolor::ColorChoice::Always,
+            Self::Never => termcolor::ColorChoice::Never,
+            Self::Auto if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) => {
+                termcolor::ColorChoice::Never
+            }
+            Self::Auto if atty::is(stream) => termcolor::ColorChoice::Auto,
+            Self::Auto => termcolor::ColorChoice::Never,
+        }
+    }
+}
+
 impl<R, W1, W2: WriteColor> Shell<R, W1, W2> {
     pub(crate) fn warn(&mut self, message: impl fmt::Display) -> io::Result<()> {
         self.stderr
@@ -138,17 +149,38 @@ impl<'a> TtyOrPiped<StdinLock<'a>> {
 }
 
 impl<R: BufRead> TtyOrPiped<R> {
+    pub(crate) fn read_to_string(&mut self) -> io::Result<String> {
+        let mut buf = "".to_owned();
+        match self {
+            Self::Tty => io::stdin().read_to_string(&mut buf),
+            Self::Piped(r) => r.read_to_string(&mut buf),
+        }?;
+        Ok(buf)
+    }
+
     fn read_reply(&mut self) -> io::Result<String> {
         match self {
             Self::Tty => rprompt::read_reply(),
-            Self::Piped(r) => rpassword::read_password_with_reader(Some(r)),
+            Self::Piped(r) => read_line_before_eof(r),
         }
     }
 
     fn read_password(&mut self) -> io::Result<String> {
         match self {
             Self::Tty => rpassword::read_password_from_tty(None),
-            Self::Piped(r) => rpassword::read_password_with_reader(Some(r)),
+            Self::Piped(r) => read_line_before_eof(r),
         }
     }
 }
+
+/// Reads a line from non-TTY stdin, failing instead of returning an empty string at EOF.
+fn read_line_before_eof(r: &mut impl BufRead) -> io::Result<String> {
+    let mut line = "".to_owned();
+    if r.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "stdin is not a TTY and has reached EOF",
+        ));
+    }
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_owned())
+}
@@ -1,46 +1,121 @@
-use anyhow::Context as _;
+use anyhow::{bail, Context as _};
 use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
+    env,
     io::{BufRead, Write},
     path::PathBuf,
 };
 
 pub(crate) fn cookie_store_path() -> anyhow::Result<PathBuf> {
-    let data_local_dir =
-        dirs_next::data_local_dir().with_context(|| "Could not find the local date directory")?;
-    Ok(data_local_dir.join("snowchains").join("cookies.jsonl"))
+    data_file_path(&["cookies.jsonl"])
 }
 
 pub(crate) fn atcoder_username_and_password<'a, R: BufRead, W1, W2: Write>(
     shell: &'a RefCell<&'a mut crate::shell::Shell<R, W1, W2>>,
 ) -> impl FnMut() -> anyhow::Result<(String, String)> + 'a {
-    username_and_password(shell, "Username: ")
+    username_and_password(
+        shell,
+        "Username: ",
+        ("ATCODER_USERNAME", "ATCODER_PASSWORD"),
+    )
+}
+
+/// Returns a closure that reads the value of the `REVEL_SESSION` cookie of AtCoder.
+///
+/// `ATCODER_REVEL_SESSION` is used for the first call if it is set.
+pub(crate) fn atcoder_revel_session<'a, R: BufRead, W1, W2: Write>(
+    shell: &'a RefCell<&'a mut crate::shell::Shell<R, W1, W2>>,
+) -> impl FnMut() -> anyhow::Result<String> + 'a {
+    let mut from_env = env_var("ATCODER_REVEL_SESSION");
+    let mut used_env = false;
+
+    move || -> _ {
+        if let Some(revel_session) = from_env.take() {
+            used_env = true;
+            return Ok(revel_session);
+        }
+        if used_env {
+            bail!("Failed to log in with `ATCODER_REVEL_SESSION`");
+        }
+
+        shell
+            .borrow_mut()
+            .read_password("`REVEL_SESSION` (copy it from your browser): ")
+            .with_context(|| {
+                "Could not read `REVEL_SESSION`. To run non-interactively, set \
+                 `ATCODER_REVEL_SESSION`"
+            })
+    }
 }
 
 pub(crate) fn codeforces_username_and_password<'a, R: BufRead, W1, W2: Write>(
     shell: &'a RefCell<&'a mut crate::shell::Shell<R, W1, W2>>,
 ) -> impl FnMut() -> anyhow::Result<(String, String)> + 'a {
-    username_and_password(shell, "Handle/Email: ")
+    username_and_password(
+        shell,
+        "Handle/Email: ",
+        ("CODEFORCES_USERNAME", "CODEFORCES_PASSWORD"),
+    )
 }
 
+/// Returns a closure that reads a username and a password.
+///
+/// If both of the environment variables `env_var_names` are set, the first call returns their
+/// values instead of prompting, and the later calls (which mean the values were rejected) fail.
 pub(crate) fn username_and_password<'a, R: BufRead, W1, W2: Write>(
     shell: &'a RefCell<&'a mut crate::shell::Shell<R, W1, W2>>,
     username_prompt: &'static str,
+    env_var_names: (&'static str, &'static str),
 ) -> impl FnMut() -> anyhow::Result<(String, String)> + 'a {
+    let (username_var, password_var) = env_var_names;
+    let mut from_env = env_var(username_var).zip(env_var(password_var));
+    let mut used_env = false;
+
     move || -> _ {
+        if let Some(username_and_password) = from_env.take() {
+            used_env = true;
+            return Ok(username_and_password);
+        }
+        if used_env {
+            bail!(
+                "Failed to log in with `{}` and `{}`",
+                username_var,
+                password_var,
+            );
+        }
+
+        let hint = || {
+            format!(
+                "Could not read the credentials. To run non-interactively, set `{}` and `{}`",
+                username_var, password_var,
+            )
+        };
+
         let mut shell = shell.borrow_mut();
-        let username = shell.read_reply(username_prompt)?;
-        let password = shell.read_password("Password: ")?;
+        let username = shell.read_reply(username_prompt).with_context(hint)?;
+        let password = if username.is_empty() {
+            "".to_owned()
+        } else {
+            shell.read_password("Password: ").with_context(hint)?
+        };
         Ok((username, password))
     }
 }
 
 pub(crate) fn dropbox_access_token() -> anyhow::Result<String> {
+    if let Some(access_token) = env_var("DROPBOX_ACCESS_TOKEN") {
+        return Ok(access_token);
+    }
+
     let path = token_path("dropbox.json")?;
 
-    let Dropbox { access_token } = crate::fs::read_json(&path)
-        .with_context(|| format!("First, save the access token to `{}`", path.display()))?;
+    let Dropbox { access_token } = crate::fs::read_json(&path).with_context(|| {
+        format!(
+            "First, save the access token to `{}` or set `DROPBOX_ACCESS_TOKEN`",
+            path.display(),
+        )
+    })?;
 
     return Ok(access_token);
 
@@ -53,6 +128,13 @@ pub(crate) fn dropbox_access_token() -> anyhow::Result<String> {
 pub(crate) fn codeforces_api_key_and_secret(
     shell: &mut crate::shell::Shell<impl BufRead, impl Sized, impl Write>,
 ) -> anyhow::Result<(String, String)> {
+    if let (Some(api_key), Some(api_secret)) = (
+        env_var("CODEFORCES_API_KEY"),
+        env_var("CODEFORCES_API_SECRET"),
+    ) {
+        return Ok((api_key, api_secret));
+    }
+
     let path = token_path("codeforces.json")?;
 
     let Codeforces {
@@ -61,8 +143,14 @@ pub(crate) fn codeforces_api_key_and_secret(
     } = if path.exists() {
         crate::fs::read_json(path)?
     } else {
-        let api_key = shell.read_password("Codeforces `api_key`: ")?;
-        let api_secret = shell.read_password("Codeforces `api_secret`: ")?;
+        let hint = "Could not read the API key. To run non-interactively, set \
+                    `CODEFORCES_API_KEY` and `CODEFORCES_API_SECRET`";
+        let api_key = shell
+            .read_password("Codeforces `api_key`: ")
+            .context(hint)?;
+        let api_secret = shell
+            .read_password("Codeforces `api_secret`: ")
+            .context(hint)?;
 
         let pair = Codeforces {
             api_key,
@@ -85,23 +173,60 @@ pub(crate) fn codeforces_api_key_and_secret(
 pub(crate) fn yukicoder_api_key(
     shell: &mut crate::shell::Shell<impl BufRead, impl Sized, impl Write>,
 ) -> anyhow::Result<String> {
+    if let Some(api_key) = env_var("YUKICODER_API_KEY") {
+        return Ok(api_key);
+    }
+
     let path = token_path("yukicoder.json")?;
 
     if path.exists() {
         crate::fs::read_json(path)
     } else {
-        let api_key = shell.read_password("yukicoder API key: ")?;
+        let api_key = shell
+            .read_password("yukicoder API key: ")
+            .with_context(|| {
+                "Could not read the API key. To run non-interactively, set `YUKICODER_API_KEY`"
+            })?;
         crate::fs::write_json(path, &api_key, true)?;
         Ok(api_key)
     }
 }
 
+/// Returns the value of the environment variable `name` unless it is unset or empty.
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
 fn token_path(file_name: &str) -> anyhow::Result<PathBuf> {
-    let data_local_dir =
-        dirs_next::data_local_dir().with_context(|| "Could not find the local data directory")?;
+    data_file_path(&["tokens", file_name])
+}
+
+/// Returns `<local data directory>/snowchains/<components>`.
+///
+/// The local data directory is `$XDG_DATA_HOME` (or `~/.local/share`) on Linux,
+/// `~/Library/Application Support` on macOS, and `%LOCALAPPDATA%` on Windows. If the file does
+/// not exist there but does in `~/.local/share/snowchains`, where older versions wrote it
+/// regardless of the platform and `$XDG_DATA_HOME`, the old path is returned instead.
+fn data_file_path(components: &[&str]) -> anyhow::Result<PathBuf> {
+    let join = |dir: PathBuf| -> PathBuf {
+        components
+            .iter()
+            .fold(dir.join("snowchains"), |path, c| path.join(c))
+    };
+
+    let path = join(
+        dirs_next::data_local_dir().with_context(|| "Could not find the local data directory")?,
+    );
+
+    if !path.exists() {
+        if let Some(legacy_path) =
+            dirs_next::home_dir().map(|home| join(home.join(".local").join("share")))
+        {
+            if legacy_path.exists() {
+                return Ok(legacy_path);
+            }
+        }
+    }
 
-    Ok(data_local_dir
-        .join("snowchains")
-        .join("tokens")
-        .join(file_name))
+    Ok(path)
 }
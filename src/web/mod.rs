@@ -1,19 +1,44 @@
 pub(crate) mod credentials;
 
+use anyhow::{bail, ensure, Context as _};
 use heck::{CamelCase as _, KebabCase as _, MixedCase as _, SnakeCase as _};
 use serde::Serialize;
-use std::time::Duration;
+use snowchains_core::web::PlatformKind;
+use std::{
+    env,
+    ffi::OsStr,
+    process::{Command, Stdio},
+    time::Duration,
+};
+use url::Url;
 
 pub(crate) const SESSION_TIMEOUT: Duration = Duration::from_secs(30);
 
+pub(crate) const USER_AGENT: &str = concat!(
+    "snowchains/",
+    env!("CARGO_PKG_VERSION"),
+    " <https://github.com/qryxip/snowchains>",
+);
+
+/// Case conversions of a contest ID or a problem index.
+///
+/// These are what `target.contest` and `target.problem` provide in `snowchains.dhall`
+/// (`lowercase`, `uppercase`, `snakeCase`, `kebabCase`, `mixedCase`, and `pascalCase`).
 #[derive(Debug, Serialize)]
 pub(crate) struct CaseConversions {
+    /// `Two-Sum` → `Two-Sum`
     pub(crate) original: String,
+    /// `Two-Sum` → `two-sum`
     pub(crate) lower: String,
+    /// `Two-Sum` → `TWO-SUM`
     pub(crate) upper: String,
+    /// `Two-Sum` → `two_sum`
     pub(crate) snake: String,
+    /// `Two-Sum` → `two-sum`
     pub(crate) kebab: String,
+    /// camelCase. `Two-Sum` → `twoSum`
     pub(crate) mixed: String,
+    /// `Two-Sum` → `TwoSum`
     pub(crate) pascal: String,
 }
 
@@ -31,3 +56,173 @@ impl CaseConversions {
         }
     }
 }
+
+/// Extracts the service, the contest ID, and the problem index from a problem URL.
+///
+/// Supports AtCoder (`/contests/<contest>/tasks/<screen name>`) and Codeforces
+/// (`/contest/<contest>/problem/<index>` and `/problemset/problem/<contest>/<index>`).
+pub(crate) fn parse_problem_url(url: &Url) -> anyhow::Result<(PlatformKind, String, String)> {
+    let segments = url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    match url.domain() {
+        Some("atcoder.jp") => {
+            let contest = snowchains_core::web::atcoder_contest_id(url)?;
+
+            let screen_name = match *segments {
+                ["contests", _, "tasks", screen_name] => screen_name,
+                _ => bail!("Not a URL of an AtCoder problem: {}", url),
+            };
+
+            // `abc300_a` → `a`
+            let problem = screen_name
+                .strip_prefix(&format!("{}_", contest.replace('-', "_")))
+                .unwrap_or(screen_name)
+                .to_owned();
+
+            Ok((PlatformKind::Atcoder, contest, problem))
+        }
+        Some("codeforces.com") => {
+            let (contest, problem) = match *segments {
+                ["contest", contest, "problem", problem]
+                | ["problemset", "problem", contest, problem] => (contest, problem),
+                _ => bail!("Not a URL of a Codeforces problem: {}", url),
+            };
+
+            let contest = contest
+                .parse::<u64>()
+                .with_context(|| format!("Invalid contest ID in {}", url))?;
+
+            Ok((
+                PlatformKind::Codeforces,
+                contest.to_string(),
+                problem.to_owned(),
+            ))
+        }
+        _ => bail!(
+            "Unsupported URL: {}. Only AtCoder and Codeforces problem URLs are supported",
+            url,
+        ),
+    }
+}
+
+/// Opens `url` with `$BROWSER` if it is set, otherwise with the platform's default opener.
+///
+/// As with `xdg-open`, `$BROWSER` is a `:`-separated list of commands that are tried in order.
+/// Each command is split on whitespace, and `%s` in it is replaced with the URL. If there is no
+/// `%s`, the URL is appended.
+pub(crate) fn open_in_browser(url: &Url) -> anyhow::Result<()> {
+    if let Some(browser) = env::var("BROWSER").ok().filter(|s| !s.is_empty()) {
+        let mut errors = vec![];
+        for command in browser.split(':').filter(|s| !s.trim().is_empty()) {
+            match open_with_command(command, url) {
+                Ok(()) => return Ok(()),
+                Err(err) => errors.push(format!("{:#}", err)),
+            }
+        }
+        bail!(
+            "Could not open {} with `$BROWSER`: {}",
+            url,
+            errors.join(", ")
+        );
+    }
+
+    // `cmd /C start` would cut the URL at `&`.
+    let (program, args): (_, &[_]) = if cfg!(windows) {
+        ("rundll32", &["url.dll,FileProtocolHandler"])
+    } else if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else {
+        ("xdg-open", &[])
+    };
+    run_opener(program, args.iter().copied().chain(Some(url.as_str())))
+}
+
+fn open_with_command(command: &str, url: &Url) -> anyhow::Result<()> {
+    let mut words = command.split_whitespace();
+    let program = words.next().with_context(|| "Empty command")?;
+    let mut args = words
+        .map(|arg| arg.replace("%s", url.as_str()))
+        .collect::<Vec<_>>();
+    if !command.contains("%s") {
+        args.push(url.as_str().to_owned());
+    }
+    run_opener(program, args)
+}
+
+fn run_opener<S: AsRef<OsStr>>(
+    program: &str,
+    args: impl IntoIterator<Item = S>,
+) -> anyhow::Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .with_context(|| format!("Could not execute `{}`", program))?;
+
+    ensure!(status.success(), "`{}` failed ({})", program, status);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CaseConversions;
+    use snowchains_core::web::PlatformKind;
+
+    #[test]
+    fn case_conversions() {
+        let CaseConversions {
+            original,
+            lower,
+            upper,
+            snake,
+            kebab,
+            mixed,
+            pascal,
+        } = CaseConversions::new("two-sum");
+
+        assert_eq!("two-sum", original);
+        assert_eq!("two-sum", lower);
+        assert_eq!("TWO-SUM", upper);
+        assert_eq!("two_sum", snake);
+        assert_eq!("two-sum", kebab);
+        assert_eq!("twoSum", mixed);
+        assert_eq!("TwoSum", pascal);
+
+        let CaseConversions {
+            snake,
+            kebab,
+            mixed,
+            pascal,
+            ..
+        } = CaseConversions::new("TwoSum");
+
+        assert_eq!("two_sum", snake);
+        assert_eq!("two-sum", kebab);
+        assert_eq!("twoSum", mixed);
+        assert_eq!("TwoSum", pascal);
+    }
+
+    #[test]
+    fn parse_problem_url() -> anyhow::Result<()> {
+        let parse = |url: &str| super::parse_problem_url(&url.parse()?);
+
+        assert_eq!(
+            (PlatformKind::Atcoder, "abc300".to_owned(), "a".to_owned()),
+            parse("https://atcoder.jp/contests/abc300/tasks/abc300_a")?,
+        );
+        assert_eq!(
+            (PlatformKind::Codeforces, "1234".to_owned(), "B".to_owned()),
+            parse("https://codeforces.com/contest/1234/problem/B")?,
+        );
+        assert_eq!(
+            (PlatformKind::Codeforces, "1234".to_owned(), "B".to_owned()),
+            parse("https://codeforces.com/problemset/problem/1234/B")?,
+        );
+        assert!(parse("https://yukicoder.me/problems/no/1").is_err());
+        Ok(())
+    }
+}